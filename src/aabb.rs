@@ -0,0 +1,112 @@
+use crate::ray::Ray;
+use crate::vec3::Vec3;
+
+/// An axis aligned bounding box
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Aabb {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl Aabb {
+    /// The smallest box containing both points
+    pub fn new(a: Vec3, b: Vec3) -> Aabb {
+        Aabb {
+            min: a.min(b),
+            max: a.max(b),
+        }
+    }
+
+    /// A box containing nothing, the identity of [`Aabb::union`]
+    pub fn empty() -> Aabb {
+        Aabb {
+            min: Vec3::all(f64::INFINITY),
+            max: Vec3::all(f64::NEG_INFINITY),
+        }
+    }
+
+    pub fn union(&self, other: &Aabb) -> Aabb {
+        Aabb {
+            min: self.min.min(other.min),
+            max: self.max.max(other.max),
+        }
+    }
+
+    /// Extend the box so that it contains `p`
+    pub fn grow(&self, p: Vec3) -> Aabb {
+        Aabb {
+            min: self.min.min(p),
+            max: self.max.max(p),
+        }
+    }
+
+    pub fn centroid(&self) -> Vec3 {
+        0.5 * (self.min + self.max)
+    }
+
+    pub fn surface_area(&self) -> f64 {
+        let d = self.max - self.min;
+        if d.x < 0.0 || d.y < 0.0 || d.z < 0.0 {
+            0.0
+        } else {
+            2.0 * (d.x * d.y + d.y * d.z + d.z * d.x)
+        }
+    }
+
+    /// The axis along which the box is the longest
+    pub fn largest_axis(&self) -> usize {
+        let d = self.max - self.min;
+        if d.x >= d.y && d.x >= d.z {
+            0
+        } else if d.y >= d.z {
+            1
+        } else {
+            2
+        }
+    }
+
+    /// Whether `r` goes through the box for some parameter in `(t_min, t_max)`
+    pub fn hit(&self, r: &Ray, mut t_min: f64, mut t_max: f64) -> bool {
+        for axis in 0..3 {
+            let inv_d = 1.0 / r.direction[axis];
+            let mut t0 = (self.min[axis] - r.origin[axis]) * inv_d;
+            let mut t1 = (self.max[axis] - r.origin[axis]) * inv_d;
+            if inv_d < 0.0 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+            // written so that NaN, from a ray lying in a slab boundary, keeps the ray in
+            t_min = if t0 > t_min { t0 } else { t_min };
+            t_max = if t1 < t_max { t1 } else { t_max };
+            if t_max < t_min {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn hit_test() {
+        let b = Aabb::new(Vec3::all(-1.0), Vec3::all(1.0));
+        let towards = Ray::new(Vec3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0));
+        let away = Ray::new(Vec3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, 1.0));
+        let beside = Ray::new(Vec3::new(2.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0));
+        assert!(b.hit(&towards, 0.0, f64::MAX));
+        assert!(!b.hit(&towards, 0.0, 3.0));
+        assert!(!b.hit(&away, 0.0, f64::MAX));
+        assert!(!b.hit(&beside, 0.0, f64::MAX));
+    }
+
+    #[test]
+    fn union_test() {
+        let a = Aabb::new(Vec3::zeros(), Vec3::all(1.0));
+        let b = Aabb::new(Vec3::all(2.0), Vec3::all(3.0));
+        assert_eq!(a.union(&b), Aabb::new(Vec3::zeros(), Vec3::all(3.0)));
+        assert_eq!(Aabb::empty().union(&a), a);
+        assert_eq!(a.surface_area(), 6.0);
+    }
+}
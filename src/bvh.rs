@@ -0,0 +1,231 @@
+//! Bounding volume hierarchy over any set of bounded primitives.
+//!
+//! The tree is built top-down with the surface area heuristic evaluated on a
+//! fixed number of bins per node. Nodes are stored depth first so the left
+//! child of an interior node always directly follows it.
+use crate::aabb::Aabb;
+use crate::hittable::HitRecord;
+use crate::ray::Ray;
+use crate::vec3::Vec3;
+
+/// Number of candidate split positions evaluated along the chosen axis
+const BINS: usize = 16;
+/// Cost of visiting a node relative to intersecting a primitive
+const TRAVERSAL_COST: f64 = 0.125;
+/// Leaves larger than this are split even when the heuristic disagrees
+const MAX_LEAF_SIZE: usize = 8;
+/// Depth below which nodes are split in halves instead of with the heuristic,
+/// which keeps the tree shallow enough for the traversal stack
+const MAX_SAH_DEPTH: usize = 32;
+/// Nodes waiting to be visited during a traversal, enough for any tree of
+/// less than 2^31 primitives
+const STACK_SIZE: usize = 64;
+
+struct Node {
+    bounds: Aabb,
+    /// index of the first primitive of a leaf, or of the right child of an interior node
+    offset: usize,
+    /// number of primitives of a leaf, 0 for interior nodes
+    count: usize,
+    /// axis the children of an interior node were split along
+    axis: usize,
+}
+
+pub struct Bvh {
+    nodes: Vec<Node>,
+    /// ids of the primitives ordered so that every leaf covers a contiguous range
+    ids: Vec<usize>,
+}
+
+#[derive(Clone, Copy)]
+struct Bin {
+    bounds: Aabb,
+    count: usize,
+}
+
+impl Bvh {
+    /// Build the hierarchy over primitives given as `(id, bounding box)`,
+    /// the ids being what [`Bvh::hit`] passes back to identify a primitive
+    pub fn build(primitives: &[(usize, Aabb)]) -> Bvh {
+        let mut bvh = Bvh {
+            nodes: Vec::with_capacity(2 * primitives.len()),
+            ids: Vec::with_capacity(primitives.len()),
+        };
+        let mut items: Vec<(usize, Aabb, Vec3)> = primitives
+            .iter()
+            .map(|(id, bounds)| (*id, *bounds, bounds.centroid()))
+            .collect();
+        if !items.is_empty() {
+            bvh.build_node(&mut items, 0);
+        }
+        bvh
+    }
+
+    /// Append the subtree over `items` and return the index of its root
+    fn build_node(&mut self, items: &mut [(usize, Aabb, Vec3)], depth: usize) -> usize {
+        let bounds = items
+            .iter()
+            .fold(Aabb::empty(), |acc, (_, b, _)| acc.union(b));
+        let index = self.nodes.len();
+        self.nodes.push(Node {
+            bounds,
+            offset: self.ids.len(),
+            count: items.len(),
+            axis: 0,
+        });
+
+        let make_leaf = |bvh: &mut Bvh, items: &[(usize, Aabb, Vec3)]| {
+            bvh.ids.extend(items.iter().map(|(id, _, _)| *id));
+            index
+        };
+        if items.len() == 1 {
+            return make_leaf(self, items);
+        }
+
+        let centroids = items
+            .iter()
+            .fold(Aabb::empty(), |acc, (_, _, c)| acc.grow(*c));
+        let axis = centroids.largest_axis();
+        let (low, extent) = (
+            centroids.min[axis],
+            centroids.max[axis] - centroids.min[axis],
+        );
+
+        let mid = if extent > 0.0 && depth < MAX_SAH_DEPTH {
+            let bin_of =
+                |c: &Vec3| (((c[axis] - low) / extent * BINS as f64) as usize).min(BINS - 1);
+            let mut bins = [Bin {
+                bounds: Aabb::empty(),
+                count: 0,
+            }; BINS];
+            for (_, b, c) in items.iter() {
+                let bin = &mut bins[bin_of(c)];
+                bin.bounds = bin.bounds.union(b);
+                bin.count += 1;
+            }
+
+            // cost of splitting after each bin, sweeping from both ends
+            let mut right_area = [0.0; BINS];
+            let mut right_count = [0; BINS];
+            let mut acc = Bin {
+                bounds: Aabb::empty(),
+                count: 0,
+            };
+            for i in (1..BINS).rev() {
+                acc.bounds = acc.bounds.union(&bins[i].bounds);
+                acc.count += bins[i].count;
+                right_area[i] = acc.bounds.surface_area();
+                right_count[i] = acc.count;
+            }
+            let mut best = (f64::INFINITY, 0);
+            acc = Bin {
+                bounds: Aabb::empty(),
+                count: 0,
+            };
+            for i in 1..BINS {
+                acc.bounds = acc.bounds.union(&bins[i - 1].bounds);
+                acc.count += bins[i - 1].count;
+                let cost = acc.bounds.surface_area() * acc.count as f64
+                    + right_area[i] * right_count[i] as f64;
+                if cost < best.0 {
+                    best = (cost, i);
+                }
+            }
+
+            let area = bounds.surface_area();
+            let split_cost = if area > 0.0 {
+                TRAVERSAL_COST + best.0 / area
+            } else {
+                TRAVERSAL_COST
+            };
+            if split_cost >= items.len() as f64 && items.len() <= MAX_LEAF_SIZE {
+                return make_leaf(self, items);
+            }
+
+            let mut mid = 0;
+            for i in 0..items.len() {
+                if bin_of(&items[i].2) < best.1 {
+                    items.swap(i, mid);
+                    mid += 1;
+                }
+            }
+            mid
+        } else if items.len() <= MAX_LEAF_SIZE {
+            return make_leaf(self, items);
+        } else {
+            0
+        };
+
+        // fall back to halving the primitives when binning cannot separate them
+        let mid = if mid == 0 || mid == items.len() {
+            items.sort_by(|a, b| a.2[axis].total_cmp(&b.2[axis]));
+            items.len() / 2
+        } else {
+            mid
+        };
+
+        let (left, right) = items.split_at_mut(mid);
+        self.build_node(left, depth + 1);
+        let right = self.build_node(right, depth + 1);
+        let node = &mut self.nodes[index];
+        node.offset = right;
+        node.count = 0;
+        node.axis = axis;
+        index
+    }
+
+    /// The bounds of everything in the hierarchy
+    pub fn bounds(&self) -> Option<Aabb> {
+        self.nodes.first().map(|node| node.bounds)
+    }
+
+    /// Find the closest hit of `r` in `(t_min, t_max)`.
+    ///
+    /// `hit_primitive` is called with the id of every primitive whose leaf is
+    /// reached by the ray and the current search interval.
+    pub fn hit<'a, F>(
+        &self,
+        r: &Ray,
+        t_min: f64,
+        t_max: f64,
+        mut hit_primitive: F,
+    ) -> Option<HitRecord<'a>>
+    where
+        F: FnMut(usize, &Ray, f64, f64) -> Option<HitRecord<'a>>,
+    {
+        if self.nodes.is_empty() {
+            return None;
+        }
+        let mut closest_so_far = t_max;
+        let mut record = None;
+        let mut stack = [0; STACK_SIZE];
+        let mut len = 1;
+        while len > 0 {
+            len -= 1;
+            let i = stack[len];
+            let node = &self.nodes[i];
+            if !node.bounds.hit(r, t_min, closest_so_far) {
+                continue;
+            }
+            if node.count > 0 {
+                for id in &self.ids[node.offset..node.offset + node.count] {
+                    if let Some(temp) = hit_primitive(*id, r, t_min, closest_so_far) {
+                        closest_so_far = temp.t;
+                        record = Some(temp);
+                    }
+                }
+            } else {
+                // visit the child closer to the ray origin first
+                let (near, far) = if r.direction[node.axis] < 0.0 {
+                    (node.offset, i + 1)
+                } else {
+                    (i + 1, node.offset)
+                };
+                stack[len] = far;
+                stack[len + 1] = near;
+                len += 2;
+            }
+        }
+        record
+    }
+}
@@ -1,9 +1,59 @@
 use crate::ray::Ray;
+use crate::sampler::Sampler;
 use crate::util;
 use crate::vec3::Vec3;
-use rand::prelude::*;
 use std::f64::consts::PI;
 
+/// Where a camera stands and how its lens is set, independently of the
+/// aspect ratio of the image it renders
+#[derive(Clone, Debug, PartialEq)]
+pub struct CameraSettings {
+    pub lookfrom: Vec3,
+    pub lookat: Vec3,
+    pub vup: Vec3,
+    /// vertical field of view in degrees
+    pub vfov: f64,
+    pub aperture: f64,
+    pub focus_dist: f64,
+    /// the instants the shutter opens and closes, moving objects are blurred
+    /// along their path in between
+    pub shutter_open: f64,
+    pub shutter_close: f64,
+}
+
+impl Default for CameraSettings {
+    fn default() -> CameraSettings {
+        CameraSettings {
+            lookfrom: Vec3::new(13.0, 2.0, 3.0),
+            lookat: Vec3::zeros(),
+            vup: Vec3::new(0.0, 1.0, 0.0),
+            vfov: 20.0,
+            aperture: 0.5,
+            focus_dist: 10.0,
+            shutter_open: 0.0,
+            shutter_close: 0.0,
+        }
+    }
+}
+
+impl CameraSettings {
+    /// Create the camera for images of the given aspect ratio
+    pub fn build(&self, aspect: f64) -> Camera {
+        let mut camera = Camera::new(
+            self.lookfrom,
+            self.lookat,
+            self.vup,
+            self.vfov,
+            aspect,
+            self.aperture,
+            self.focus_dist,
+        );
+        camera.set_shutter(self.shutter_open, self.shutter_close);
+        camera
+    }
+}
+
+/// A thin lens camera.
 pub struct Camera {
     lower_left_corner: Vec3,
     horizontal: Vec3,
@@ -12,9 +62,17 @@ pub struct Camera {
     u: Vec3,
     v: Vec3,
     lens_radius: f64,
+    shutter_open: f64,
+    shutter_close: f64,
 }
 
 impl Camera {
+    /// Create a camera located at `lookfrom` looking towards `lookat`.
+    ///
+    /// `vfov` is the vertical field of view in degrees and `aspect` the ratio
+    /// between the width and the height of the image. Objects `focus_dist`
+    /// away from the camera are in focus, the amount of blur elsewhere grows
+    /// with `aperture`.
     pub fn new(
         lookfrom: Vec3,
         lookat: Vec3,
@@ -44,15 +102,33 @@ impl Camera {
             u,
             v,
             lens_radius,
+            shutter_open: 0.0,
+            shutter_close: 0.0,
         }
     }
 
-    pub fn get_ray(&self, u: f64, v: f64, rng: &mut ThreadRng) -> Ray {
-        let rd = self.lens_radius * util::random_in_unit_disk(rng);
+    /// Cast rays at random instants between `open` and `close`, rather than
+    /// all at the instant 0
+    pub fn set_shutter(&mut self, open: f64, close: f64) {
+        self.shutter_open = open;
+        self.shutter_close = close;
+    }
+
+    /// Generate the ray going through the point `(u, v)` of the image plane,
+    /// both coordinates being in `[0, 1]` starting from the lower left corner.
+    pub fn get_ray(&self, u: f64, v: f64, sampler: &mut Sampler) -> Ray {
+        let rd = self.lens_radius * util::random_in_unit_disk(sampler);
         let offset = rd.x * self.u + rd.y * self.v;
-        Ray::new(
+        // a still shutter draws no sample, keeping still images unchanged
+        let time = if self.shutter_close > self.shutter_open {
+            sampler.gen_range(self.shutter_open, self.shutter_close)
+        } else {
+            self.shutter_open
+        };
+        Ray::with_time(
             self.origin + offset,
             self.lower_left_corner + u * self.horizontal + v * self.vertical - self.origin - offset,
+            time,
         )
     }
 }
@@ -0,0 +1,379 @@
+use rustracer::image::ImageFormat;
+use rustracer::{scenes, CameraSettings, RenderSettings, Vec3};
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+pub const USAGE: &str = "\
+Usage: rustracer [OPTIONS]
+
+Render options, which override the settings of a scene file:
+    --width <PIXELS>         width of the image [default: 800]
+    --height <PIXELS>        height of the image [default: 600]
+    --spp <N>                samples per pixel [default: 100]
+    --max-depth <N>          maximum number of bounces of a ray [default: 50]
+    --threads <N>            number of worker threads [default: one per core]
+    --tile-size <PIXELS>     size of the square tiles the image is split into [default: 32]
+    --seed <N>               seed of the random numbers [default: 0]
+    --no-mis                 only sample the lights to find them from diffuse and
+                             glossy surfaces, instead of weighting light and
+                             material samples by multiple importance sampling
+    --scene <NAME|FILE>      scene to render, either built-in: simple, sphere_sea,
+                             cornell_box
+                             or a file: .scene, .obj, .ply, .gltf, .glb
+                             [default: sphere_sea]
+    -o, --output <PATH>      write the rendered image to PATH, the format is picked
+                             from the extension: .ppm, .pam, .png, .hdr or .pfm
+    --format <FORMAT>        override the format of the output: ppm, ppm-ascii,
+                             pam, png, png-stored, hdr, pfm
+    --headless               do not open a window, only write the image to disk
+                             [default output: render.png]
+
+Camera options, which override the camera of a scene file:
+    --lookfrom <X,Y,Z>       position of the camera [default: 13,2,3]
+    --lookat <X,Y,Z>         point the camera looks at [default: 0,0,0]
+    --vup <X,Y,Z>            up direction of the camera [default: 0,1,0]
+    --vfov <DEGREES>         vertical field of view [default: 20]
+    --aperture <SIZE>        lens aperture, 0 disables depth of field [default: 0.5]
+    --focus-dist <DIST>      distance to the plane in focus [default: 10]
+    --shutter <OPEN,CLOSE>   instants the shutter opens and closes, moving
+                             objects blur in between [default: 0,0]
+
+    -h, --help               print this help and exit
+";
+
+#[derive(Clone, Debug)]
+pub struct Options {
+    pub settings: RenderSettings,
+    pub camera: CameraSettings,
+    pub scene: String,
+    pub output: Option<PathBuf>,
+    pub format: Option<ImageFormat>,
+    /// render without opening a window
+    pub headless: bool,
+}
+
+impl Default for Options {
+    fn default() -> Options {
+        Options {
+            settings: RenderSettings::default(),
+            camera: CameraSettings::default(),
+            scene: String::from("sphere_sea"),
+            output: None,
+            format: None,
+            headless: !cfg!(feature = "window"),
+        }
+    }
+}
+
+/// The outcome of parsing the command line which is not a set of options to render with
+#[derive(Debug, PartialEq)]
+pub enum CliError {
+    /// `--help` was requested
+    Help,
+    /// the arguments are not valid
+    Invalid(String),
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CliError::Help => write!(f, "{}", USAGE),
+            CliError::Invalid(msg) => write!(f, "error: {}", msg),
+        }
+    }
+}
+
+fn invalid<T>(msg: String) -> Result<T, CliError> {
+    Err(CliError::Invalid(msg))
+}
+
+fn parse_number<T: FromStr>(flag: &str, value: &str) -> Result<T, CliError> {
+    value
+        .parse()
+        .or_else(|_| invalid(format!("invalid value '{}' for {}", value, flag)))
+}
+
+fn parse_positive<T: FromStr + PartialOrd + Default>(
+    flag: &str,
+    value: &str,
+) -> Result<T, CliError> {
+    let n: T = parse_number(flag, value)?;
+    if n > T::default() {
+        Ok(n)
+    } else {
+        invalid(format!("{} must be greater than 0", flag))
+    }
+}
+
+fn parse_vec3(flag: &str, value: &str) -> Result<Vec3, CliError> {
+    let parts = value
+        .split(',')
+        .map(|part| parse_number::<f64>(flag, part.trim()))
+        .collect::<Result<Vec<f64>, CliError>>()?;
+    match parts[..] {
+        [x, y, z] => Ok(Vec3::new(x, y, z)),
+        _ => invalid(format!(
+            "{} expects three comma separated numbers, got '{}'",
+            flag, value
+        )),
+    }
+}
+
+/// A change the command line makes to the options
+type Override = Box<dyn FnOnce(&mut Options)>;
+
+fn set<T: 'static>(value: T, field: fn(&mut Options, T)) -> Override {
+    Box::new(move |options| field(options, value))
+}
+
+/// The parsed command line, to be applied on top of the options of the scene
+pub struct Arguments {
+    /// the scene to render
+    pub scene: String,
+    overrides: Vec<Override>,
+}
+
+/// Parse the command line arguments, not including the program name
+pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Arguments, CliError> {
+    let mut arguments = Arguments {
+        scene: Options::default().scene,
+        overrides: Vec::new(),
+    };
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        // accept both `--flag value` and `--flag=value`
+        let (flag, inline_value) = match arg.find('=') {
+            Some(i) if arg.starts_with("--") => {
+                (arg[..i].to_string(), Some(arg[i + 1..].to_string()))
+            }
+            _ => (arg.clone(), None),
+        };
+        if flag == "-h" || flag == "--help" {
+            return Err(CliError::Help);
+        }
+        if flag == "--headless" {
+            arguments.overrides.push(set((), |o, _| o.headless = true));
+            continue;
+        }
+        if flag == "--no-mis" {
+            arguments
+                .overrides
+                .push(set((), |o, _| o.settings.mis = false));
+            continue;
+        }
+        let value = match inline_value.or_else(|| args.next()) {
+            Some(value) => value,
+            None => return invalid(format!("missing value for {}", flag)),
+        };
+        let value = value.as_str();
+        let change = match flag.as_str() {
+            "--width" => set(parse_positive(&flag, value)?, |o, v| o.settings.width = v),
+            "--height" => set(parse_positive(&flag, value)?, |o, v| o.settings.height = v),
+            "--spp" => set(parse_positive(&flag, value)?, |o, v| {
+                o.settings.samples_per_pixel = v
+            }),
+            "--max-depth" => set(parse_positive(&flag, value)?, |o, v| {
+                o.settings.max_depth = v
+            }),
+            "--threads" => set(parse_positive(&flag, value)?, |o, v| o.settings.threads = v),
+            "--tile-size" => set(parse_positive(&flag, value)?, |o, v| {
+                o.settings.tile_size = v
+            }),
+            "--seed" => set(parse_number(&flag, value)?, |o, v| o.settings.seed = v),
+            "--scene" => {
+                if !scenes::NAMES.contains(&value) && !scenes::is_scene_file(value) {
+                    return invalid(format!(
+                        "unknown scene '{}', expected a file ending in .{} or one of: {}",
+                        value,
+                        scenes::FILE_EXTENSIONS.join(", ."),
+                        scenes::NAMES.join(", ")
+                    ));
+                }
+                arguments.scene = value.to_string();
+                continue;
+            }
+            "-o" | "--output" => set(PathBuf::from(value), |o, v| o.output = Some(v)),
+            "--format" => match ImageFormat::from_name(value) {
+                Some(format) => set(format, |o, v| o.format = Some(v)),
+                None => {
+                    return invalid(format!(
+                        "unknown image format '{}', expected one of: {}",
+                        value,
+                        ImageFormat::NAMES.join(", ")
+                    ))
+                }
+            },
+            "--lookfrom" => set(parse_vec3(&flag, value)?, |o, v| o.camera.lookfrom = v),
+            "--lookat" => set(parse_vec3(&flag, value)?, |o, v| o.camera.lookat = v),
+            "--vup" => set(parse_vec3(&flag, value)?, |o, v| o.camera.vup = v),
+            "--vfov" => {
+                let vfov: f64 = parse_number(&flag, value)?;
+                if !(vfov > 0.0 && vfov < 180.0) {
+                    return invalid(format!("{} must be between 0 and 180 degrees", flag));
+                }
+                set(vfov, |o, v| o.camera.vfov = v)
+            }
+            "--aperture" => {
+                let aperture: f64 = parse_number(&flag, value)?;
+                if aperture < 0.0 || aperture.is_nan() {
+                    return invalid(format!("{} must not be negative", flag));
+                }
+                set(aperture, |o, v| o.camera.aperture = v)
+            }
+            "--focus-dist" => set(parse_positive(&flag, value)?, |o, v| {
+                o.camera.focus_dist = v
+            }),
+            "--shutter" => {
+                let parts = value
+                    .split(',')
+                    .map(|part| parse_number::<f64>(&flag, part.trim()))
+                    .collect::<Result<Vec<f64>, CliError>>()?;
+                match parts[..] {
+                    [open, close] if open <= close => set((open, close), |o, (open, close)| {
+                        o.camera.shutter_open = open;
+                        o.camera.shutter_close = close;
+                    }),
+                    [_, _] => return invalid(format!("{} must not close before it opens", flag)),
+                    _ => {
+                        return invalid(format!(
+                            "{} expects two comma separated numbers, got '{}'",
+                            flag, value
+                        ))
+                    }
+                }
+            }
+            _ => return invalid(format!("unknown option '{}'", flag)),
+        };
+        arguments.overrides.push(change);
+    }
+    Ok(arguments)
+}
+
+impl Arguments {
+    /// The options given on the command line on top of `defaults` such as the
+    /// settings of a scene file, checked once merged
+    pub fn apply(self, defaults: Options) -> Result<Options, CliError> {
+        let mut options = defaults;
+        options.scene = self.scene;
+        for change in self.overrides {
+            change(&mut options);
+        }
+
+        if options.headless && options.output.is_none() {
+            options.output = Some(PathBuf::from("render.png"));
+        }
+        if let Some(output) = &options.output {
+            if options.format.is_none() && ImageFormat::from_path(output).is_none() {
+                return invalid(format!(
+                    "cannot tell the image format of {}, use --format",
+                    output.display()
+                ));
+            }
+        }
+
+        let camera = &options.camera;
+        if camera.lookfrom == camera.lookat {
+            return invalid(String::from(
+                "--lookfrom and --lookat must be different points",
+            ));
+        }
+        if camera
+            .vup
+            .cross(camera.lookat - camera.lookfrom)
+            .squared_length()
+            == 0.0
+        {
+            return invalid(String::from(
+                "--vup must not be parallel to the viewing direction",
+            ));
+        }
+        Ok(options)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn parse_str(args: &str) -> Result<Options, CliError> {
+        parse(args.split_whitespace().map(String::from))?.apply(Options::default())
+    }
+
+    #[test]
+    fn defaults_test() {
+        let options = parse_str("").unwrap();
+        assert_eq!(options.settings.width, 800);
+        assert_eq!(options.settings.height, 600);
+        assert_eq!(options.scene, "sphere_sea");
+        assert_eq!(options.camera, CameraSettings::default());
+    }
+
+    #[test]
+    fn overrides_test() {
+        let options =
+            parse_str("--width 320 --height=240 --spp 4 --scene simple --lookfrom 0,0,1 --lookat 0,0,-1 -o out.ppm")
+                .unwrap();
+        assert_eq!(options.settings.width, 320);
+        assert_eq!(options.settings.height, 240);
+        assert_eq!(options.settings.samples_per_pixel, 4);
+        assert_eq!(options.scene, "simple");
+        assert_eq!(options.camera.lookfrom, Vec3::new(0.0, 0.0, 1.0));
+        assert_eq!(options.output, Some(PathBuf::from("out.ppm")));
+
+        let options = parse_str("--shutter 0,0.5").unwrap();
+        assert_eq!(options.camera.shutter_close, 0.5);
+    }
+
+    #[test]
+    fn headless_test() {
+        let options = parse_str("--headless --spp 1").unwrap();
+        assert!(options.headless);
+        assert!(options.settings.mis);
+        assert!(!parse_str("--no-mis").unwrap().settings.mis);
+        assert_eq!(options.settings.samples_per_pixel, 1);
+        assert_eq!(options.output, Some(PathBuf::from("render.png")));
+    }
+
+    #[test]
+    fn format_test() {
+        let options = parse_str("-o image.out --format ppm-ascii").unwrap();
+        assert_eq!(options.format, Some(ImageFormat::PpmAscii));
+        assert!(parse_str("-o image.out").is_err());
+        assert!(parse_str("-o image.png --format gif").is_err());
+    }
+
+    #[test]
+    fn invalid_test() {
+        assert!(parse_str("--width 0").is_err());
+        assert!(parse_str("--spp many").is_err());
+        assert!(parse_str("--scene unknown").is_err());
+        assert!(parse_str("--scene model.obj").is_ok());
+        assert!(parse_str("--scene bunny.PLY").is_ok());
+        assert!(parse_str("--scene room.glb").is_ok());
+        assert!(parse_str("--lookat 1,2").is_err());
+        assert!(parse_str("--vfov 180").is_err());
+        assert!(parse_str("--shutter 1,0").is_err());
+        assert!(parse_str("--shutter 0.5").is_err());
+        assert!(parse_str("--threads").is_err());
+        assert!(parse_str("--frobnicate 1").is_err());
+        assert_eq!(parse_str("--help").unwrap_err(), CliError::Help);
+    }
+
+    #[test]
+    fn scene_camera_test() {
+        // the camera is checked once merged with the one of the scene
+        let arguments = || parse(vec![String::from("--lookat=13,2,3")]).unwrap();
+        assert!(arguments().apply(Options::default()).is_err());
+        let defaults = Options {
+            camera: CameraSettings {
+                lookfrom: Vec3::new(0.0, 1.0, 5.0),
+                ..CameraSettings::default()
+            },
+            ..Options::default()
+        };
+        let options = arguments().apply(defaults).unwrap();
+        assert_eq!(options.camera.lookfrom, Vec3::new(0.0, 1.0, 5.0));
+        assert_eq!(options.camera.lookat, Vec3::new(13.0, 2.0, 3.0));
+    }
+}
@@ -0,0 +1,230 @@
+use super::Environment;
+use crate::hittable::LightSample;
+use crate::render::FrameBuffer;
+use crate::sampler::Sampler;
+use crate::vec3::Vec3;
+use std::f64::consts::PI;
+use std::io;
+use std::path::Path;
+
+/// An equirectangular image of the surroundings.
+///
+/// The top row of the image is straight up, the bottom one straight down,
+/// and the center of the image is towards `-z` before the map is rotated,
+/// `+x` being a quarter of the width to its right.
+///
+/// Directions are sampled proportionally to the luminance of the pixels, so
+/// that small bright areas such as the sun or the lamps of a studio light the
+/// scene without noise.
+pub struct EnvironmentMap {
+    image: FrameBuffer,
+    /// counter-clockwise rotation around `+y`, as its sine and cosine
+    rotation: (f64, f64),
+    intensity: f64,
+    /// probabilities of the rows, then of the pixels of every row
+    rows: Distribution,
+    columns: Vec<Distribution>,
+}
+
+impl EnvironmentMap {
+    pub fn new(image: FrameBuffer) -> Box<EnvironmentMap> {
+        let (width, height) = (image.width, image.height);
+        // pixels near the poles cover smaller solid angles
+        let columns: Vec<Distribution> = image
+            .pixels()
+            .chunks(width)
+            .enumerate()
+            .map(|(j, row)| {
+                let sin = (PI * (j as f64 + 0.5) / height as f64).sin();
+                let weights: Vec<f64> = row.iter().map(|c| luminance(*c) * sin).collect();
+                Distribution::new(&weights)
+            })
+            .collect();
+        let row_weights: Vec<f64> = columns.iter().map(|c| c.total).collect();
+        Box::new(EnvironmentMap {
+            image,
+            rotation: (0.0, 1.0),
+            intensity: 1.0,
+            rows: Distribution::new(&row_weights),
+            columns,
+        })
+    }
+
+    /// Load the map from a `.hdr` or `.pfm` image
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Box<EnvironmentMap>> {
+        Ok(EnvironmentMap::new(crate::image::load(path)?))
+    }
+
+    /// Turn the map `degrees` counter-clockwise around `+y`
+    pub fn set_rotation(&mut self, degrees: f64) {
+        self.rotation = degrees.to_radians().sin_cos();
+    }
+
+    /// Scale the radiance of the map by `intensity`
+    pub fn set_intensity(&mut self, intensity: f64) {
+        self.intensity = intensity;
+    }
+
+    /// The pixel seen towards `direction` in world space
+    fn pixel(&self, direction: Vec3) -> (usize, usize) {
+        let d = direction.make_unit_vector();
+        // undo the rotation
+        let (sin, cos) = self.rotation;
+        let (x, z) = (cos * d.x - sin * d.z, sin * d.x + cos * d.z);
+        let u = 0.5 + x.atan2(-z) / (2.0 * PI);
+        let v = d.y.clamp(-1.0, 1.0).acos() / PI;
+        let (width, height) = (self.image.width, self.image.height);
+        let i = ((u * width as f64) as usize).min(width - 1);
+        let j = ((v * height as f64) as usize).min(height - 1);
+        (i, j)
+    }
+}
+
+impl Environment for EnvironmentMap {
+    fn radiance(&self, direction: Vec3) -> Vec3 {
+        let (i, j) = self.pixel(direction);
+        self.intensity * self.image.pixels()[j * self.image.width + i]
+    }
+
+    fn is_sampled(&self) -> bool {
+        self.rows.total > 0.0
+    }
+
+    fn sample(&self, sampler: &mut Sampler) -> Option<LightSample> {
+        if !self.is_sampled() {
+            return None;
+        }
+        let (j, v) = self.rows.sample(sampler.next_f64());
+        let (i, u) = self.columns[j].sample(sampler.next_f64());
+        let u = (i as f64 + u) / self.image.width as f64;
+        let v = (j as f64 + v) / self.image.height as f64;
+        let d = direction(u, v);
+        // apply the rotation
+        let (sin, cos) = self.rotation;
+        let direction = Vec3::new(cos * d.x + sin * d.z, d.y, -sin * d.x + cos * d.z);
+        let pdf = self.pdf(direction);
+        if pdf > 0.0 {
+            Some(LightSample { direction, pdf })
+        } else {
+            None
+        }
+    }
+
+    /// The density over the image spread over the solid angle of each pixel,
+    /// `dω = 2π² sin θ du dv`
+    fn pdf(&self, direction: Vec3) -> f64 {
+        if !self.is_sampled() {
+            return 0.0;
+        }
+        let y = direction.make_unit_vector().y;
+        let sin_theta = (1.0 - y * y).max(0.0).sqrt();
+        if sin_theta == 0.0 {
+            return 0.0;
+        }
+        let (i, j) = self.pixel(direction);
+        let probability = self.rows.probability(j) * self.columns[j].probability(i);
+        let pixels = (self.image.width * self.image.height) as f64;
+        probability * pixels / (2.0 * PI * PI * sin_theta)
+    }
+}
+
+/// The unit direction at `(u, v)` of an equirectangular image which is not
+/// rotated
+pub(super) fn direction(u: f64, v: f64) -> Vec3 {
+    let (sin_theta, cos_theta) = (PI * v).sin_cos();
+    let (sin_phi, cos_phi) = (2.0 * PI * (u - 0.5)).sin_cos();
+    Vec3::new(sin_theta * sin_phi, cos_theta, -sin_theta * cos_phi)
+}
+
+pub(super) fn luminance(c: Vec3) -> f64 {
+    0.2126 * c.x + 0.7152 * c.y + 0.0722 * c.z
+}
+
+/// A discrete distribution over indices, proportional to their weights
+struct Distribution {
+    /// `cdf[i]` is the probability of picking an index below `i`
+    cdf: Vec<f64>,
+    total: f64,
+}
+
+impl Distribution {
+    fn new(weights: &[f64]) -> Distribution {
+        let mut cdf = Vec::with_capacity(weights.len() + 1);
+        let mut total = 0.0;
+        cdf.push(0.0);
+        for w in weights {
+            total += w.max(0.0);
+            cdf.push(total);
+        }
+        if total > 0.0 {
+            cdf.iter_mut().for_each(|c| *c /= total);
+        }
+        Distribution { cdf, total }
+    }
+
+    /// The index `x` in `[0, 1)` falls on, with where it falls within it
+    fn sample(&self, x: f64) -> (usize, f64) {
+        let last = self.cdf.len() - 2;
+        let i = (self.cdf.partition_point(|c| *c <= x) - 1).min(last);
+        let width = self.cdf[i + 1] - self.cdf[i];
+        let offset = if width > 0.0 {
+            (x - self.cdf[i]) / width
+        } else {
+            0.5
+        };
+        (i, offset.clamp(0.0, 1.0))
+    }
+
+    fn probability(&self, i: usize) -> f64 {
+        self.cdf[i + 1] - self.cdf[i]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::util;
+
+    #[test]
+    fn sample_test() {
+        // a dim map with one bright pixel
+        let (width, height) = (16, 8);
+        let mut pixels = vec![Vec3::all(0.1); width * height];
+        pixels[2 * width + 12] = Vec3::all(50.0);
+        let mut map = EnvironmentMap::new(FrameBuffer::new(width, height, pixels));
+        map.set_rotation(30.0);
+        map.set_intensity(2.0);
+
+        // the bright pixel is at u = 12.5 / 16 and v = 2.5 / 8, towards +x
+        // before the rotation
+        let d = direction(12.5 / 16.0, 2.5 / 8.0);
+        let angle = 30.0_f64.to_radians();
+        let bright = Vec3::new(
+            angle.cos() * d.x + angle.sin() * d.z,
+            d.y,
+            -angle.sin() * d.x + angle.cos() * d.z,
+        );
+        assert_eq!(map.radiance(bright), Vec3::all(100.0));
+        assert_eq!(map.radiance(Vec3::new(0.0, -1.0, 0.0)), Vec3::all(0.2));
+
+        let mut sampler = Sampler::new(5, 0);
+        let mut towards_bright = 0;
+        for _ in 0..1000 {
+            let sample = map.sample(&mut sampler).unwrap();
+            assert!((sample.direction.length() - 1.0).abs() < 1e-9);
+            assert!((map.pdf(sample.direction) - sample.pdf).abs() < 1e-9 * sample.pdf);
+            if map.pixel(sample.direction) == map.pixel(bright) {
+                towards_bright += 1;
+            }
+        }
+        assert!(towards_bright > 500, "{}", towards_bright);
+
+        // the density integrates to one over the sphere of directions
+        let count = 200_000;
+        let total: f64 = (0..count)
+            .map(|_| map.pdf(util::random_unit_vector(&mut sampler)))
+            .sum();
+        let integral = total * 4.0 * PI / count as f64;
+        assert!((integral - 1.0).abs() < 0.03, "{}", integral);
+    }
+}
@@ -0,0 +1,73 @@
+//! The light reaching rays which leave the scene.
+//!
+//! The world holds one [`Environment`], the sky gradient by default. Those
+//! which vary a lot over the sphere of directions, such as maps of real
+//! surroundings, are sampled like the light sources of the scene.
+mod map;
+mod sky;
+
+pub use map::EnvironmentMap;
+pub use sky::SunSky;
+
+use crate::hittable::LightSample;
+use crate::sampler::Sampler;
+use crate::util;
+use crate::vec3::Vec3;
+
+/// The radiance infinitely far away in every direction
+pub trait Environment: Send + Sync {
+    /// The radiance coming from `direction`, which need not be a unit vector
+    fn radiance(&self, direction: Vec3) -> Vec3;
+
+    /// Whether [`Environment::sample`] picks directions, for environments
+    /// bright enough in places to be worth sampling like a light
+    fn is_sampled(&self) -> bool {
+        false
+    }
+
+    /// A direction towards the environment with its density, `None` when it
+    /// is not sampled
+    fn sample(&self, _sampler: &mut Sampler) -> Option<LightSample> {
+        None
+    }
+
+    /// The density of [`Environment::sample`] picking the unit `direction`
+    fn pdf(&self, _direction: Vec3) -> f64 {
+        0.0
+    }
+}
+
+/// The same radiance in every direction
+pub struct Uniform {
+    color: Vec3,
+}
+
+impl Uniform {
+    pub fn new(color: Vec3) -> Box<Uniform> {
+        Box::new(Uniform { color })
+    }
+}
+
+impl Environment for Uniform {
+    fn radiance(&self, _direction: Vec3) -> Vec3 {
+        self.color
+    }
+}
+
+/// A sky fading from white straight down to blue straight up
+#[derive(Default)]
+pub struct SkyGradient;
+
+impl SkyGradient {
+    pub fn new() -> Box<SkyGradient> {
+        Box::new(SkyGradient)
+    }
+}
+
+impl Environment for SkyGradient {
+    fn radiance(&self, direction: Vec3) -> Vec3 {
+        let unit_direction = direction.make_unit_vector();
+        let t = 0.5 * (unit_direction.y + 1.0);
+        util::lerp(Vec3::all(1.0), Vec3::new(0.5, 0.7, 1.0), t)
+    }
+}
@@ -0,0 +1,297 @@
+use super::map::{direction, luminance};
+use super::{Environment, EnvironmentMap};
+use crate::hittable::LightSample;
+use crate::render::FrameBuffer;
+use crate::sampler::Sampler;
+use crate::util;
+use crate::vec3::Vec3;
+use std::f64::consts::PI;
+
+/// Angular radius of the sun disk seen from the ground
+const SUN_RADIUS: f64 = 0.2665 * PI / 180.0;
+/// Luminance of the sun outside the atmosphere, in kcd/m² like the sky
+const SUN_LUMINANCE: f64 = 1.96e6;
+/// Converts the kcd/m² of the model so that a white surface facing the sun
+/// at noon is about as bright as 1
+const SCALE: f64 = 1.0 / 40.0;
+/// Size of the table the sky is sampled from
+const TABLE_WIDTH: usize = 128;
+const TABLE_HEIGHT: usize = 64;
+
+/// The clear sky of Preetham, Shirley and Smits, "A Practical Analytic Model
+/// for Daylight", and the sun disk.
+///
+/// The sky depends on the position of the sun and on the turbidity, the haze
+/// of the atmosphere: 2 is a very clear day and 10 a hazy one. The disk of the
+/// sun is sampled like a distant light and reddens as the sun gets lower,
+/// the rest of the sky is sampled from a table of its luminance. Below the
+/// horizon, directions see the sky at the horizon.
+pub struct SunSky {
+    sky: Sky,
+    sun_radiance: Vec3,
+    /// cosine of the angular radius of the sun
+    cos_sun: f64,
+    intensity: f64,
+    /// the sky without the sun, to sample it
+    table: Box<EnvironmentMap>,
+    /// probability of sampling the sun rather than the table
+    sun_probability: f64,
+}
+
+impl SunSky {
+    /// The sky with the sun `elevation` degrees above the horizon, clamped to
+    /// `[0, 90]`, and `azimuth` degrees away from `-z` towards `+x`
+    pub fn new(elevation: f64, azimuth: f64, turbidity: f64) -> Box<SunSky> {
+        let elevation = elevation.clamp(0.0, 90.0).to_radians();
+        let azimuth = azimuth.to_radians();
+        let sun = Vec3::new(
+            elevation.cos() * azimuth.sin(),
+            elevation.sin(),
+            -elevation.cos() * azimuth.cos(),
+        );
+        let theta_sun = PI / 2.0 - elevation;
+        let t = turbidity;
+        let perez = [
+            [
+                0.1787 * t - 1.4630,
+                -0.3554 * t + 0.4275,
+                -0.0227 * t + 5.3251,
+                0.1206 * t - 2.5771,
+                -0.0670 * t + 0.3703,
+            ],
+            [
+                -0.0193 * t - 0.2592,
+                -0.0665 * t + 0.0008,
+                -0.0004 * t + 0.2125,
+                -0.0641 * t - 0.8989,
+                -0.0033 * t + 0.0452,
+            ],
+            [
+                -0.0167 * t - 0.2608,
+                -0.0950 * t + 0.0092,
+                -0.0079 * t + 0.2102,
+                -0.0441 * t - 1.6537,
+                -0.0109 * t + 0.0529,
+            ],
+        ];
+        let chi = (4.0 / 9.0 - t / 120.0) * (PI - 2.0 * theta_sun);
+        let zenith_luminance = (4.0453 * t - 4.9710) * chi.tan() - 0.2155 * t + 2.4192;
+        let cubic = |c: [f64; 4]| {
+            let s = theta_sun;
+            c[0] * s * s * s + c[1] * s * s + c[2] * s + c[3]
+        };
+        let x = t * t * cubic([0.00166, -0.00375, 0.00209, 0.0])
+            + t * cubic([-0.02903, 0.06377, -0.03202, 0.00394])
+            + cubic([0.11693, -0.21196, 0.06052, 0.25886]);
+        let y = t * t * cubic([0.00275, -0.00610, 0.00317, 0.0])
+            + t * cubic([-0.04214, 0.08970, -0.04153, 0.00516])
+            + cubic([0.15346, -0.26756, 0.06670, 0.26688]);
+        let mut zenith = [zenith_luminance, x, y];
+        for (z, coefficients) in zenith.iter_mut().zip(&perez) {
+            *z /= perez_function(coefficients, 1.0, theta_sun);
+        }
+        let sky = Sky { sun, perez, zenith };
+
+        let mut sky_power = 0.0;
+        let mut pixels = Vec::with_capacity(TABLE_WIDTH * TABLE_HEIGHT);
+        for j in 0..TABLE_HEIGHT {
+            let v = (j as f64 + 0.5) / TABLE_HEIGHT as f64;
+            // the solid angle of the pixels of the row
+            let solid_angle = 2.0 * PI * PI * (PI * v).sin() / (TABLE_WIDTH * TABLE_HEIGHT) as f64;
+            for i in 0..TABLE_WIDTH {
+                let u = (i as f64 + 0.5) / TABLE_WIDTH as f64;
+                let radiance = sky.radiance(direction(u, v));
+                sky_power += luminance(radiance) * solid_angle;
+                pixels.push(radiance);
+            }
+        }
+        // share the samples by the power of the sun and of the sky
+        let sun_radiance = SUN_LUMINANCE * SCALE * transmittance(theta_sun, t);
+        let cos_sun = SUN_RADIUS.cos();
+        let sun_power = luminance(sun_radiance) * 2.0 * PI * (1.0 - cos_sun);
+        Box::new(SunSky {
+            sky,
+            sun_radiance,
+            cos_sun,
+            intensity: 1.0,
+            table: EnvironmentMap::new(FrameBuffer::new(TABLE_WIDTH, TABLE_HEIGHT, pixels)),
+            sun_probability: (sun_power / (sun_power + sky_power)).clamp(0.1, 0.9),
+        })
+    }
+
+    /// Scale the radiance of the sun and the sky by `intensity`
+    pub fn set_intensity(&mut self, intensity: f64) {
+        self.intensity = intensity;
+    }
+
+    /// Unit vector towards the center of the sun
+    pub fn sun_direction(&self) -> Vec3 {
+        self.sky.sun
+    }
+
+    fn sun_pdf(&self, direction: Vec3) -> f64 {
+        if direction.dot(self.sky.sun) >= self.cos_sun {
+            1.0 / (2.0 * PI * (1.0 - self.cos_sun))
+        } else {
+            0.0
+        }
+    }
+}
+
+/// The sky without the sun
+struct Sky {
+    /// unit vector towards the center of the sun
+    sun: Vec3,
+    /// Perez coefficients of the luminance and chromaticities `x` and `y`
+    perez: [[f64; 5]; 3],
+    /// luminance and chromaticities at the zenith, divided by the Perez
+    /// function there
+    zenith: [f64; 3],
+}
+
+impl Sky {
+    /// The radiance towards the unit `direction`
+    fn radiance(&self, direction: Vec3) -> Vec3 {
+        // the zenith angle only appears through its cosine, which the
+        // horizon bounds for directions below it
+        let cos_theta = direction.y.max(1e-3);
+        let cos_gamma = direction.dot(self.sun).clamp(-1.0, 1.0);
+        let mut values = [0.0; 3];
+        for (i, value) in values.iter_mut().enumerate() {
+            *value = self.zenith[i] * perez_function(&self.perez[i], cos_theta, cos_gamma.acos());
+        }
+        let [luminance, x, y] = values;
+        if y <= 0.0 {
+            return Vec3::zeros();
+        }
+        // xyY to XYZ then to linear sRGB
+        let (big_x, big_y, big_z) = (x / y * luminance, luminance, (1.0 - x - y) / y * luminance);
+        let rgb = Vec3::new(
+            3.2406 * big_x - 1.5372 * big_y - 0.4986 * big_z,
+            -0.9689 * big_x + 1.8758 * big_y + 0.0415 * big_z,
+            0.0557 * big_x - 0.2040 * big_y + 1.0570 * big_z,
+        );
+        SCALE * Vec3::new(rgb.x.max(0.0), rgb.y.max(0.0), rgb.z.max(0.0))
+    }
+}
+
+impl Environment for SunSky {
+    fn radiance(&self, direction: Vec3) -> Vec3 {
+        let d = direction.make_unit_vector();
+        let sun = if d.dot(self.sky.sun) >= self.cos_sun {
+            self.sun_radiance
+        } else {
+            Vec3::zeros()
+        };
+        self.intensity * (self.sky.radiance(d) + sun)
+    }
+
+    fn is_sampled(&self) -> bool {
+        true
+    }
+
+    /// Either uniformly in the cone of the sun, or from the table of the sky
+    fn sample(&self, sampler: &mut Sampler) -> Option<LightSample> {
+        let direction = if sampler.next_f64() < self.sun_probability {
+            let sun = self.sky.sun;
+            let (u, v) = util::orthonormal_basis(sun);
+            let cos = 1.0 + sampler.next_f64() * (self.cos_sun - 1.0);
+            let sin = (1.0 - cos * cos).max(0.0).sqrt();
+            let phi = 2.0 * PI * sampler.next_f64();
+            sin * phi.cos() * u + sin * phi.sin() * v + cos * sun
+        } else {
+            self.table.sample(sampler)?.direction
+        };
+        let pdf = self.pdf(direction);
+        if pdf > 0.0 {
+            Some(LightSample { direction, pdf })
+        } else {
+            None
+        }
+    }
+
+    fn pdf(&self, direction: Vec3) -> f64 {
+        let d = direction.make_unit_vector();
+        self.sun_probability * self.sun_pdf(d) + (1.0 - self.sun_probability) * self.table.pdf(d)
+    }
+}
+
+/// The Perez formula for the sky at zenith angle `theta` given by its
+/// cosine, `gamma` radians away from the sun
+fn perez_function(c: &[f64; 5], cos_theta: f64, gamma: f64) -> f64 {
+    let cos_gamma = gamma.cos();
+    (1.0 + c[0] * (c[1] / cos_theta).exp())
+        * (1.0 + c[2] * (c[3] * gamma).exp() + c[4] * cos_gamma * cos_gamma)
+}
+
+/// The fraction of the red, green and blue light of the sun going through
+/// the atmosphere when it is `theta_sun` radians from the zenith, from the
+/// Rayleigh and aerosol scattering of the appendix of Preetham et al.
+fn transmittance(theta_sun: f64, turbidity: f64) -> Vec3 {
+    // relative optical mass of the air, Kasten's formula
+    let degrees = theta_sun.to_degrees();
+    let mass = 1.0 / (theta_sun.cos() + 0.15 * (93.885 - degrees).max(1e-3).powf(-1.253));
+    let beta = 0.04608 * turbidity - 0.04586;
+    let channel = |lambda: f64| {
+        // wavelengths in micrometers
+        let rayleigh = 0.008735 * lambda.powf(-4.08);
+        let aerosol = beta * lambda.powf(-1.3);
+        (-mass * (rayleigh + aerosol)).exp()
+    };
+    Vec3::new(channel(0.65), channel(0.57), channel(0.475))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn sun_sky_test() {
+        let sky = SunSky::new(30.0, 90.0, 3.0);
+        let sun = sky.sun_direction();
+        assert!((sun - Vec3::new(30f64.to_radians().cos(), 0.5, 0.0)).length() < 1e-9);
+
+        // the sun outshines the sky, and is redder than it
+        let sun_radiance = sky.radiance(sun);
+        let zenith = sky.radiance(Vec3::new(0.0, 1.0, 0.0));
+        assert!(sun_radiance.x > 1000.0 * zenith.x);
+        assert!(sun_radiance.x > sun_radiance.z);
+        assert!(zenith.z > zenith.x);
+        // the sky is brighter around the sun than away from it
+        let near = sky.radiance(Vec3::new(0.8, 0.6, 0.0));
+        let away = sky.radiance(Vec3::new(-0.8, 0.6, 0.0));
+        assert!(luminance(near) > luminance(away));
+
+        // a lower sun is redder
+        let sunset = SunSky::new(3.0, 90.0, 3.0);
+        let low = sunset.radiance(sunset.sun_direction());
+        assert!(low.z / low.x < sun_radiance.z / sun_radiance.x);
+
+        let mut sampler = Sampler::new(6, 0);
+        let mut towards_sun = 0;
+        for _ in 0..1000 {
+            let sample = sky.sample(&mut sampler).unwrap();
+            assert!((sky.pdf(sample.direction) - sample.pdf).abs() < 1e-9 * sample.pdf);
+            if sample.direction.dot(sun) >= sky.cos_sun {
+                towards_sun += 1;
+            }
+        }
+        assert!(towards_sun > 50, "{}", towards_sun);
+
+        // away from the sun the density integrates to the share of the sky
+        let count = 200_000;
+        let total: f64 = (0..count)
+            .map(|_| util::random_unit_vector(&mut sampler))
+            .filter(|d| d.dot(sun) < sky.cos_sun)
+            .map(|d| sky.pdf(d))
+            .sum();
+        let integral = total * 4.0 * PI / count as f64;
+        let expected = 1.0 - sky.sun_probability;
+        assert!(
+            (integral - expected).abs() < 0.03,
+            "{} {}",
+            integral,
+            expected
+        );
+    }
+}
@@ -0,0 +1,406 @@
+use super::triangle;
+use super::{area_pdf, area_sample, HitRecord, Hittable, LightSample};
+use crate::aabb::Aabb;
+use crate::bvh::Bvh;
+use crate::materials::Materialable;
+use crate::sampler::Sampler;
+use crate::{ray::Ray, vec3::Vec3};
+use std::sync::OnceLock;
+
+/// Triangles sharing a pool of vertices.
+///
+/// Every vertex has a position and optionally a normal, used to smoothly shade
+/// the faces, and texture coordinates. Each face picks its material from the
+/// materials of the mesh. The triangles are organized in their own bounding
+/// volume hierarchy so a mesh is added to the [`World`](super::World) as a
+/// single object.
+pub struct TriangleMesh {
+    positions: Vec<Vec3>,
+    normals: Option<Vec<Vec3>>,
+    colors: Option<Vec<Vec3>>,
+    uvs: Option<Vec<(f64, f64)>>,
+    faces: Vec<[usize; 3]>,
+    face_materials: Vec<usize>,
+    materials: Vec<Box<dyn Materialable>>,
+    bvh: OnceLock<Bvh>,
+    emitters: OnceLock<Emitters>,
+}
+
+/// The faces made of an emitting material, sampled by area
+struct Emitters {
+    faces: Vec<usize>,
+    /// the area of the faces up to each one included
+    cumulative_area: Vec<f64>,
+}
+
+impl Emitters {
+    fn area(&self) -> f64 {
+        self.cumulative_area.last().copied().unwrap_or(0.0)
+    }
+}
+
+impl TriangleMesh {
+    /// Create a mesh whose face `faces[i]` lists the indices in `positions` of
+    /// its vertices and is made of `materials[face_materials[i]]`.
+    ///
+    /// # Panics
+    ///
+    /// If an index is out of range or the number of faces and face materials differ.
+    pub fn new(
+        positions: Vec<Vec3>,
+        faces: Vec<[usize; 3]>,
+        materials: Vec<Box<dyn Materialable>>,
+        face_materials: Vec<usize>,
+    ) -> Box<TriangleMesh> {
+        assert_eq!(
+            faces.len(),
+            face_materials.len(),
+            "every face needs a material"
+        );
+        assert!(
+            faces.iter().flatten().all(|&i| i < positions.len()),
+            "face referencing a missing vertex"
+        );
+        assert!(
+            face_materials.iter().all(|&m| m < materials.len()),
+            "face referencing a missing material"
+        );
+        Box::new(TriangleMesh {
+            positions,
+            normals: None,
+            colors: None,
+            uvs: None,
+            faces,
+            face_materials,
+            materials,
+            bvh: OnceLock::new(),
+            emitters: OnceLock::new(),
+        })
+    }
+
+    /// Set one normal per vertex, interpolated across the faces
+    ///
+    /// # Panics
+    ///
+    /// If there are not as many normals as vertices.
+    pub fn set_normals(&mut self, normals: Vec<Vec3>) {
+        assert_eq!(normals.len(), self.positions.len(), "one normal per vertex");
+        self.normals = Some(normals);
+    }
+
+    /// Set the color of every vertex, interpolated across the faces and
+    /// reported in [`HitRecord::color`]
+    ///
+    /// # Panics
+    ///
+    /// If there are not as many colors as vertices.
+    pub fn set_colors(&mut self, colors: Vec<Vec3>) {
+        assert_eq!(colors.len(), self.positions.len(), "one color per vertex");
+        self.colors = Some(colors);
+    }
+
+    /// Set the texture coordinates of every vertex, `v` growing upwards as
+    /// in OBJ files
+    ///
+    /// # Panics
+    ///
+    /// If there are not as many coordinates as vertices.
+    pub fn set_uvs(&mut self, uvs: Vec<(f64, f64)>) {
+        assert_eq!(uvs.len(), self.positions.len(), "one uv per vertex");
+        self.uvs = Some(uvs);
+    }
+
+    pub fn positions(&self) -> &[Vec3] {
+        &self.positions
+    }
+
+    pub fn normals(&self) -> Option<&[Vec3]> {
+        self.normals.as_deref()
+    }
+
+    pub fn colors(&self) -> Option<&[Vec3]> {
+        self.colors.as_deref()
+    }
+
+    pub fn uvs(&self) -> Option<&[(f64, f64)]> {
+        self.uvs.as_deref()
+    }
+
+    pub fn faces(&self) -> &[[usize; 3]] {
+        &self.faces
+    }
+
+    fn face_bounds(&self, face: usize) -> Aabb {
+        let [a, b, c] = self.faces[face];
+        Aabb::new(self.positions[a], self.positions[b]).grow(self.positions[c])
+    }
+
+    fn bvh(&self) -> &Bvh {
+        self.bvh.get_or_init(|| {
+            let bounds: Vec<(usize, Aabb)> = (0..self.faces.len())
+                .map(|i| (i, self.face_bounds(i)))
+                .collect();
+            Bvh::build(&bounds)
+        })
+    }
+
+    fn face_normal(&self, face: usize) -> Vec3 {
+        let [a, b, c] = self.faces[face];
+        let (pa, pb, pc) = (self.positions[a], self.positions[b], self.positions[c]);
+        (pb - pa).cross(pc - pa)
+    }
+
+    fn emitters(&self) -> &Emitters {
+        self.emitters.get_or_init(|| {
+            let mut faces = Vec::new();
+            let mut cumulative_area = Vec::new();
+            let mut area = 0.0;
+            for face in 0..self.faces.len() {
+                if self.materials[self.face_materials[face]].is_emitter() {
+                    area += 0.5 * self.face_normal(face).length();
+                    faces.push(face);
+                    cumulative_area.push(area);
+                }
+            }
+            Emitters {
+                faces,
+                cumulative_area,
+            }
+        })
+    }
+
+    /// The density of sampling the direction of `r` summed over the emitting
+    /// faces it crosses, but `skip`
+    fn emitter_pdf(&self, r: &Ray, skip: Option<usize>) -> f64 {
+        let area = self.emitters().area();
+        if area <= 0.0 {
+            return 0.0;
+        }
+        let mut pdf = 0.0;
+        // no hit is ever returned so that every face along the ray is visited
+        self.bvh().hit(r, 0.001, f64::MAX, |face, r, t_min, t_max| {
+            if Some(face) == skip || !self.materials[self.face_materials[face]].is_emitter() {
+                return None;
+            }
+            let [a, b, c] = self.faces[face];
+            let (pa, pb, pc) = (self.positions[a], self.positions[b], self.positions[c]);
+            if let Some(crossing) = triangle::intersect(r, pa, pb, pc, t_min, t_max) {
+                pdf += area_pdf(r.direction, crossing.t, self.face_normal(face), area);
+            }
+            None
+        });
+        pdf
+    }
+
+    fn hit_face(&self, face: usize, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let [a, b, c] = self.faces[face];
+        let (pa, pb, pc) = (self.positions[a], self.positions[b], self.positions[c]);
+        let crossing = triangle::intersect(r, pa, pb, pc, t_min, t_max)?;
+        let w = 1.0 - crossing.u - crossing.v;
+        let interpolate =
+            |values: &[Vec3]| w * values[a] + crossing.u * values[b] + crossing.v * values[c];
+        let geometric_normal = self.face_normal(face).make_unit_vector();
+        let mut n = match &self.normals {
+            Some(normals) => interpolate(normals).make_unit_vector(),
+            None => geometric_normal,
+        };
+        // vertex normals may be wound the other way than the face
+        if n.dot(geometric_normal) < 0.0 {
+            n = -n;
+        }
+        // without texture coordinates the barycentric ones stand in
+        let (uv, dpdu, dpdv) = match &self.uvs {
+            Some(uvs) => {
+                let (ua, va) = uvs[a];
+                let (ub, vb) = uvs[b];
+                let (uc, vc) = uvs[c];
+                let uv = (
+                    w * ua + crossing.u * ub + crossing.v * uc,
+                    w * va + crossing.u * vb + crossing.v * vc,
+                );
+                // solve p - pa = (u - ua) dpdu + (v - va) dpdv on both edges
+                let (du1, dv1, du2, dv2) = (ub - ua, vb - va, uc - ua, vc - va);
+                let det = du1 * dv2 - dv1 * du2;
+                if det.abs() < 1e-12 {
+                    (uv, pb - pa, pc - pa)
+                } else {
+                    let (e1, e2) = (pb - pa, pc - pa);
+                    (uv, (dv2 * e1 - dv1 * e2) / det, (du1 * e2 - du2 * e1) / det)
+                }
+            }
+            None => ((crossing.u, crossing.v), pb - pa, pc - pa),
+        };
+        Some(HitRecord {
+            t: crossing.t,
+            p: r.point_at_parameter(crossing.t),
+            n,
+            geometric_normal,
+            front_face: r.direction.dot(geometric_normal) < 0.0,
+            material: self.materials[self.face_materials[face]].as_ref(),
+            color: self.colors.as_deref().map(interpolate),
+            uv,
+            dpdu,
+            dpdv,
+            object_id: 0,
+            primitive_id: face,
+        })
+    }
+}
+
+impl Hittable for TriangleMesh {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        self.bvh().hit(r, t_min, t_max, |face, r, t_min, t_max| {
+            self.hit_face(face, r, t_min, t_max)
+        })
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        self.bvh().bounds()
+    }
+
+    fn is_emitter(&self) -> bool {
+        self.emitters().area() > 0.0
+    }
+
+    /// Emitting faces are picked in proportion to their area, then a point
+    /// uniformly on the face. The density sums those of every emitting face
+    /// along the sampled direction, as any of them may be shaded.
+    fn sample_towards(
+        &self,
+        origin: Vec3,
+        time: f64,
+        sampler: &mut Sampler,
+    ) -> Option<LightSample> {
+        let emitters = self.emitters();
+        let target = sampler.next_f64() * emitters.area();
+        let i = emitters
+            .cumulative_area
+            .partition_point(|&area| area <= target)
+            .min(emitters.faces.len().checked_sub(1)?);
+        let face = emitters.faces[i];
+        let [a, b, c] = self.faces[face];
+        let p = triangle::sample_point(
+            self.positions[a],
+            self.positions[b],
+            self.positions[c],
+            sampler,
+        );
+        let mut sample = area_sample(origin, p, self.face_normal(face), emitters.area())?;
+        let r = Ray::with_time(origin, sample.direction, time);
+        sample.pdf += self.emitter_pdf(&r, Some(face));
+        Some(sample)
+    }
+
+    fn pdf_towards(&self, origin: Vec3, direction: Vec3, time: f64) -> f64 {
+        self.emitter_pdf(&Ray::with_time(origin, direction, time), None)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::materials::{DiffuseLight, Lambertian};
+
+    fn quad() -> Box<TriangleMesh> {
+        TriangleMesh::new(
+            vec![
+                Vec3::new(-1.0, -1.0, 0.0),
+                Vec3::new(1.0, -1.0, 0.0),
+                Vec3::new(1.0, 1.0, 0.0),
+                Vec3::new(-1.0, 1.0, 0.0),
+            ],
+            vec![[0, 1, 2], [0, 2, 3]],
+            vec![Lambertian::new(0.5, 0.5, 0.5)],
+            vec![0, 0],
+        )
+    }
+
+    #[test]
+    fn hit_test() {
+        let mesh = quad();
+        for &(x, y) in &[(0.5, -0.5), (-0.5, 0.5)] {
+            let r = Ray::new(Vec3::new(x, y, 1.0), Vec3::new(0.0, 0.0, -1.0));
+            let hit = mesh.hit(&r, 0.001, f64::MAX).unwrap();
+            assert_eq!(hit.t, 1.0);
+            assert_eq!(hit.n, Vec3::new(0.0, 0.0, 1.0));
+        }
+        let miss = Ray::new(Vec3::new(1.5, 0.0, 1.0), Vec3::new(0.0, 0.0, -1.0));
+        assert!(mesh.hit(&miss, 0.001, f64::MAX).is_none());
+        assert_eq!(
+            mesh.bounding_box(),
+            Some(Aabb::new(
+                Vec3::new(-1.0, -1.0, 0.0),
+                Vec3::new(1.0, 1.0, 0.0)
+            ))
+        );
+    }
+
+    #[test]
+    fn smooth_normals_test() {
+        let mut mesh = quad();
+        let tilted = Vec3::new(1.0, 0.0, 1.0).make_unit_vector();
+        mesh.set_normals(vec![tilted; 4]);
+        let r = Ray::new(Vec3::new(0.5, -0.5, 1.0), Vec3::new(0.0, 0.0, -1.0));
+        let hit = mesh.hit(&r, 0.001, f64::MAX).unwrap();
+        assert!((hit.n - tilted).length() < 1e-12);
+        assert_eq!(hit.geometric_normal, Vec3::new(0.0, 0.0, 1.0));
+        assert!(hit.front_face);
+    }
+
+    #[test]
+    fn uv_test() {
+        let mut mesh = quad();
+        // the texture spans the quad upside down
+        mesh.set_uvs(vec![(0.0, 1.0), (1.0, 1.0), (1.0, 0.0), (0.0, 0.0)]);
+        let r = Ray::new(Vec3::new(0.5, -0.5, -1.0), Vec3::new(0.0, 0.0, 1.0));
+        let hit = mesh.hit(&r, 0.001, f64::MAX).unwrap();
+        assert!(!hit.front_face);
+        assert_eq!(hit.primitive_id, 0);
+        assert!((hit.uv.0 - 0.75).abs() < 1e-12 && (hit.uv.1 - 0.75).abs() < 1e-12);
+        assert!((hit.dpdu - Vec3::new(2.0, 0.0, 0.0)).length() < 1e-12);
+        assert!((hit.dpdv - Vec3::new(0.0, -2.0, 0.0)).length() < 1e-12);
+
+        let r = Ray::new(Vec3::new(-0.5, 0.5, 1.0), Vec3::new(0.0, 0.0, -1.0));
+        let hit = mesh.hit(&r, 0.001, f64::MAX).unwrap();
+        assert_eq!(hit.primitive_id, 1);
+        assert!((hit.dpdv - Vec3::new(0.0, -2.0, 0.0)).length() < 1e-12);
+    }
+
+    #[test]
+    fn closed_emitter_pdf_test() {
+        let mut positions = Vec::new();
+        for i in 0..8 {
+            positions.push(Vec3::new(
+                (i & 1) as f64,
+                (i >> 1 & 1) as f64,
+                (i >> 2 & 1) as f64,
+            ));
+        }
+        let quads = [
+            [0, 2, 3, 1],
+            [4, 5, 7, 6],
+            [0, 1, 5, 4],
+            [2, 6, 7, 3],
+            [0, 4, 6, 2],
+            [1, 3, 7, 5],
+        ];
+        let faces: Vec<[usize; 3]> = quads
+            .iter()
+            .flat_map(|&[a, b, c, d]| vec![[a, b, c], [a, c, d]])
+            .collect();
+        let face_materials = vec![0; faces.len()];
+        let cube = TriangleMesh::new(
+            positions,
+            faces,
+            vec![DiffuseLight::new(1.0, 1.0, 1.0)],
+            face_materials,
+        );
+        let mut sampler = Sampler::new(1, 0);
+        let origin = Vec3::new(2.0, 3.0, -1.5);
+        for _ in 0..100 {
+            let sample = cube.sample_towards(origin, 0.0, &mut sampler).unwrap();
+            let pdf = cube.pdf_towards(origin, sample.direction, 0.0);
+            assert!((sample.pdf - pdf).abs() < 1e-9 * pdf);
+        }
+    }
+}
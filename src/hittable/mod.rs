@@ -0,0 +1,120 @@
+mod mesh;
+mod quad;
+mod sphere;
+mod transformed;
+mod triangle;
+mod world;
+
+pub use mesh::TriangleMesh;
+pub use quad::Quad;
+pub use sphere::Sphere;
+pub use transformed::Transformed;
+pub use triangle::Triangle;
+pub use world::World;
+
+use crate::aabb::Aabb;
+use crate::materials::Materialable;
+use crate::sampler::Sampler;
+use crate::{ray::Ray, vec3::Vec3};
+
+/// Anything a ray can intersect with.
+pub trait Hittable: Send + Sync {
+    /// Return the closest intersection of `r` whose parameter lies in `(t_min, t_max)`.
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>>;
+
+    /// The box enclosing the object, `None` for unbounded objects which then
+    /// have to be tested against every ray
+    fn bounding_box(&self) -> Option<Aabb> {
+        None
+    }
+
+    /// Whether the object emits light, it is then sampled as a light source
+    /// and must implement [`Hittable::sample_towards`]
+    fn is_emitter(&self) -> bool {
+        false
+    }
+
+    /// Pick a direction from `origin` towards the emitting surface of the
+    /// object at `time`, `None` when it cannot be seen from there
+    fn sample_towards(
+        &self,
+        _origin: Vec3,
+        _time: f64,
+        _sampler: &mut Sampler,
+    ) -> Option<LightSample> {
+        None
+    }
+
+    /// The density with respect to solid angle of [`Hittable::sample_towards`]
+    /// picking the unit vector `direction`
+    fn pdf_towards(&self, _origin: Vec3, _direction: Vec3, _time: f64) -> f64 {
+        0.0
+    }
+}
+
+/// A direction towards a light source
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LightSample {
+    /// unit vector from the lit point
+    pub direction: Vec3,
+    /// density with respect to solid angle
+    pub pdf: f64,
+}
+
+/// Turn the point `p`, sampled uniformly on a surface of `area` whose normal
+/// is `n` there, into a direction from `origin`
+fn area_sample(origin: Vec3, p: Vec3, n: Vec3, area: f64) -> Option<LightSample> {
+    let to = p - origin;
+    let distance = to.length();
+    let direction = to / distance;
+    let pdf = area_pdf(direction, distance, n, area);
+    if pdf > 0.0 && pdf.is_finite() {
+        Some(LightSample { direction, pdf })
+    } else {
+        None
+    }
+}
+
+/// The solid angle density of reaching, along `direction`, a point `distance`
+/// away on a surface of `area` sampled uniformly
+fn area_pdf(direction: Vec3, distance: f64, n: Vec3, area: f64) -> f64 {
+    let cosine = direction.dot(n).abs() / n.length();
+    if area <= 0.0 || cosine.is_nan() || cosine < 1e-12 {
+        return 0.0;
+    }
+    distance * distance / (cosine * area)
+}
+
+/// HitRecord describe the following property of ray's interaction with the world:    
+/// 1. the closest intersection point of a ray
+/// 2. the normals of the intersection and the side the ray comes from
+/// 3. the material of the intersecting object
+/// 4. the texture coordinates and the tangents along them
+/// 5. which object and primitive was hit
+pub struct HitRecord<'a> {
+    pub t: f64,
+    /// the intersection vector
+    pub p: Vec3,
+    /// the shading normal, interpolated over smooth meshes, of unit length and
+    /// on the same side of the surface as `geometric_normal`
+    pub n: Vec3,
+    /// the unit normal of the surface itself, pointing away from the
+    /// material: outside of objects, but inwards on a sphere of negative
+    /// radius whose inside is empty, such as a bubble in glass
+    pub geometric_normal: Vec3,
+    /// whether the ray comes from the side `geometric_normal` points to
+    pub front_face: bool,
+    pub material: &'a dyn Materialable,
+    /// the interpolated vertex color, for meshes which have some
+    pub color: Option<Vec3>,
+    /// texture coordinates of the intersection, `v` growing upwards
+    pub uv: (f64, f64),
+    /// the derivatives of `p` along `u` and `v`
+    pub dpdu: Vec3,
+    pub dpdv: Vec3,
+    /// the index of the object in the [`World`], in the order objects were
+    /// added; objects of a nested world report the index of the outermost one
+    pub object_id: usize,
+    /// the index of the primitive in the object, such as the face of a mesh
+    pub primitive_id: usize,
+}
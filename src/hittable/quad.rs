@@ -0,0 +1,131 @@
+use super::{area_pdf, area_sample, HitRecord, Hittable, LightSample};
+use crate::aabb::Aabb;
+use crate::materials::Materialable;
+use crate::sampler::Sampler;
+use crate::{ray::Ray, util, vec3::Vec3};
+
+/// A parallelogram, the points `corner + a * u + b * v` for `a` and `b` in
+/// `[0, 1]`
+pub struct Quad {
+    corner: Vec3,
+    u: Vec3,
+    v: Vec3,
+    /// `u x v`, not normalized
+    normal: Vec3,
+    material: Box<dyn Materialable>,
+}
+
+impl Quad {
+    /// Create the parallelogram spanned by the edges `u` and `v` from
+    /// `corner`, its normal facing the side from which `v` is counterclockwise
+    /// from `u`
+    pub fn new(corner: Vec3, u: Vec3, v: Vec3, m: Box<dyn Materialable>) -> Box<Quad> {
+        Box::new(Quad {
+            corner,
+            u,
+            v,
+            normal: u.cross(v),
+            material: m,
+        })
+    }
+}
+
+impl Hittable for Quad {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let denom = self.normal.dot(r.direction);
+        // the ray is parallel to the plane of the quad
+        if denom.abs() < 1e-12 {
+            return None;
+        }
+        let t = self.normal.dot(self.corner - r.origin) / denom;
+        if !util::is_between(t, t_min, t_max) {
+            return None;
+        }
+        // coordinates of the hit point along both edges
+        let p = r.point_at_parameter(t);
+        let offset = p - self.corner;
+        let inv_area = 1.0 / self.normal.squared_length();
+        let a = self.normal.dot(offset.cross(self.v)) * inv_area;
+        let b = self.normal.dot(self.u.cross(offset)) * inv_area;
+        if !(0.0..=1.0).contains(&a) || !(0.0..=1.0).contains(&b) {
+            return None;
+        }
+        let n = self.normal.make_unit_vector();
+        Some(HitRecord {
+            t,
+            p,
+            n,
+            geometric_normal: n,
+            front_face: denom < 0.0,
+            material: self.material.as_ref(),
+            color: None,
+            uv: (a, b),
+            dpdu: self.u,
+            dpdv: self.v,
+            object_id: 0,
+            primitive_id: 0,
+        })
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let far = self.corner + self.u + self.v;
+        Some(
+            Aabb::new(self.corner, far)
+                .grow(self.corner + self.u)
+                .grow(self.corner + self.v),
+        )
+    }
+
+    fn is_emitter(&self) -> bool {
+        self.material.is_emitter()
+    }
+
+    fn sample_towards(
+        &self,
+        origin: Vec3,
+        _time: f64,
+        sampler: &mut Sampler,
+    ) -> Option<LightSample> {
+        let p = self.corner + sampler.next_f64() * self.u + sampler.next_f64() * self.v;
+        area_sample(origin, p, self.normal, self.normal.length())
+    }
+
+    fn pdf_towards(&self, origin: Vec3, direction: Vec3, time: f64) -> f64 {
+        let r = Ray::with_time(origin, direction, time);
+        match self.hit(&r, 0.001, f64::MAX) {
+            Some(hit) => area_pdf(direction, hit.t, self.normal, self.normal.length()),
+            None => 0.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::materials::Lambertian;
+
+    #[test]
+    fn hit_test() {
+        let quad = Quad::new(
+            Vec3::new(1.0, 0.0, 0.0),
+            Vec3::new(2.0, 0.0, 0.0),
+            Vec3::new(1.0, 1.0, 0.0),
+            Lambertian::new(0.5, 0.5, 0.5),
+        );
+        let down = Vec3::new(0.0, 0.0, -1.0);
+        let hit = quad
+            .hit(&Ray::new(Vec3::new(2.5, 0.5, 2.0), down), 0.001, f64::MAX)
+            .unwrap();
+        assert_eq!(hit.t, 2.0);
+        assert_eq!(hit.n, Vec3::new(0.0, 0.0, 1.0));
+        // beside the slanted edges of the parallelogram
+        let r = Ray::new(Vec3::new(1.2, 0.9, 2.0), down);
+        assert!(quad.hit(&r, 0.001, f64::MAX).is_none());
+        let r = Ray::new(Vec3::new(3.8, 0.1, 2.0), down);
+        assert!(quad.hit(&r, 0.001, f64::MAX).is_none());
+
+        let bounds = quad.bounding_box().unwrap();
+        assert_eq!(bounds.min, Vec3::new(1.0, 0.0, 0.0));
+        assert_eq!(bounds.max, Vec3::new(4.0, 1.0, 0.0));
+    }
+}
@@ -0,0 +1,250 @@
+use super::{HitRecord, Hittable, LightSample};
+use crate::aabb::Aabb;
+use crate::materials::Materialable;
+use crate::motion::Keyframes;
+use crate::sampler::Sampler;
+use crate::{ray::Ray, util, vec3::Vec3};
+use std::f64::consts::PI;
+
+pub struct Sphere {
+    /// the center at the time of a ray
+    center: Keyframes<Vec3>,
+    radius: f64,
+    material: Box<dyn Materialable>,
+}
+
+impl Sphere {
+    /// Create a sphere centered at `(x, y, z)` with radius `r`
+    pub fn new(x: f64, y: f64, z: f64, r: f64, m: Box<dyn Materialable>) -> Box<Sphere> {
+        Box::new(Sphere {
+            center: Keyframes::constant(Vec3::new(x, y, z)),
+            radius: r,
+            material: m,
+        })
+    }
+
+    /// Create a sphere of radius `r` whose center follows `path`
+    pub fn moving(path: Keyframes<Vec3>, r: f64, m: Box<dyn Materialable>) -> Box<Sphere> {
+        Box::new(Sphere {
+            center: path,
+            radius: r,
+            material: m,
+        })
+    }
+}
+
+impl Hittable for Sphere {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let center = self.center.at(r.time);
+        let oc = r.origin - center;
+        let a = r.direction.squared_length();
+        let b = 2.0 * r.direction.dot(oc);
+        let c = oc.squared_length() - self.radius * self.radius;
+
+        let descriminant = b * b - 4.0 * a * c;
+        if descriminant > 0.0 {
+            let maybe_t = {
+                let t1 = (-b - descriminant.sqrt()) / (2.0 * a);
+                let t2 = (-b + descriminant.sqrt()) / (2.0 * a);
+                if util::is_between(t1, t_min, t_max) {
+                    Some(t1)
+                } else if util::is_between(t2, t_min, t_max) {
+                    Some(t2)
+                } else {
+                    None
+                }
+            };
+            match maybe_t {
+                Some(t) => {
+                    let p = r.point_at_parameter(t);
+                    // a negative radius turns the normal inwards, the sphere
+                    // being a hollow in the material around it
+                    let n = (p - center) / self.radius;
+                    let uv = sphere_uv(self.radius.signum() * n);
+                    let (dpdu, dpdv) = sphere_tangents(uv, self.radius.abs());
+                    Some(HitRecord {
+                        t,
+                        p,
+                        n,
+                        geometric_normal: n,
+                        front_face: r.direction.dot(n) < 0.0,
+                        material: self.material.as_ref(),
+                        color: None,
+                        uv,
+                        dpdu,
+                        dpdv,
+                        object_id: 0,
+                        primitive_id: 0,
+                    })
+                }
+                None => None,
+            }
+        } else {
+            None
+        }
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        // the path is linear between keyframes, so their boxes enclose it
+        let r = Vec3::all(self.radius.abs());
+        let bounds = self
+            .center
+            .frames()
+            .iter()
+            .fold(Aabb::empty(), |b, (_, c)| {
+                b.union(&Aabb::new(*c - r, *c + r))
+            });
+        Some(bounds)
+    }
+
+    fn is_emitter(&self) -> bool {
+        self.material.is_emitter()
+    }
+
+    /// Seen from outside, directions are sampled uniformly in the cone the
+    /// sphere subtends, and over every direction from inside
+    fn sample_towards(
+        &self,
+        origin: Vec3,
+        time: f64,
+        sampler: &mut Sampler,
+    ) -> Option<LightSample> {
+        let to_center = self.center.at(time) - origin;
+        let squared_distance = to_center.squared_length();
+        let squared_radius = self.radius * self.radius;
+        let (direction, pdf) = if squared_distance <= squared_radius {
+            (util::random_unit_vector(sampler), 1.0 / (4.0 * PI))
+        } else {
+            let w = to_center / squared_distance.sqrt();
+            let (u, v) = util::orthonormal_basis(w);
+            let cos_max = (1.0 - squared_radius / squared_distance).sqrt();
+            let cos = 1.0 + sampler.next_f64() * (cos_max - 1.0);
+            let sin = (1.0 - cos * cos).max(0.0).sqrt();
+            let phi = 2.0 * PI * sampler.next_f64();
+            let direction = sin * phi.cos() * u + sin * phi.sin() * v + cos * w;
+            (
+                direction,
+                cone_pdf(squared_radius / squared_distance, cos_max),
+            )
+        };
+        Some(LightSample { direction, pdf })
+    }
+
+    fn pdf_towards(&self, origin: Vec3, direction: Vec3, time: f64) -> f64 {
+        let to_center = self.center.at(time) - origin;
+        let squared_distance = to_center.squared_length();
+        let squared_radius = self.radius * self.radius;
+        if squared_distance <= squared_radius {
+            return 1.0 / (4.0 * PI);
+        }
+        let cos_max = (1.0 - squared_radius / squared_distance).sqrt();
+        if direction.dot(to_center) < cos_max * squared_distance.sqrt() {
+            return 0.0;
+        }
+        cone_pdf(squared_radius / squared_distance, cos_max)
+    }
+}
+
+/// Longitude and latitude of the point `n` on the unit sphere, `u` starting
+/// from `-x` and going around the `y` axis, `v` from the bottom to the top
+fn sphere_uv(n: Vec3) -> (f64, f64) {
+    let u = ((-n.z).atan2(n.x) + PI) / (2.0 * PI);
+    let v = (-n.y).clamp(-1.0, 1.0).acos() / PI;
+    (u, v)
+}
+
+/// The derivatives of the point at `uv` on a sphere of radius `r`, see
+/// [`sphere_uv`]
+fn sphere_tangents((u, v): (f64, f64), r: f64) -> (Vec3, Vec3) {
+    let (theta, phi) = (PI * v, 2.0 * PI * u - PI);
+    let (sin_theta, cos_theta) = theta.sin_cos();
+    let (sin_phi, cos_phi) = phi.sin_cos();
+    (
+        (2.0 * PI * r) * Vec3::new(-sin_theta * sin_phi, 0.0, -sin_theta * cos_phi),
+        (PI * r) * Vec3::new(cos_theta * cos_phi, sin_theta, -cos_theta * sin_phi),
+    )
+}
+
+/// The uniform density over a cone of half angle `acos(cos_max)`, computing
+/// `1 - cos_max` from `sin_max²` to keep its precision for far away spheres
+fn cone_pdf(squared_sin_max: f64, cos_max: f64) -> f64 {
+    (1.0 + cos_max) / (2.0 * PI * squared_sin_max)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::materials::{DiffuseLight, Lambertian};
+
+    #[test]
+    fn moving_test() {
+        let path = Keyframes::linear(0.0, Vec3::zeros(), 1.0, Vec3::new(0.0, 4.0, 0.0));
+        let sphere = Sphere::moving(path, 1.0, Lambertian::new(0.5, 0.5, 0.5));
+        let at = |time| {
+            let r = Ray::with_time(Vec3::new(0.0, 2.0, 5.0), Vec3::new(0.0, 0.0, -1.0), time);
+            sphere.hit(&r, 0.001, f64::MAX).map(|h| h.t)
+        };
+        assert_eq!(at(0.0), None);
+        assert_eq!(at(0.5), Some(4.0));
+        assert_eq!(at(1.0), None);
+
+        let bounds = sphere.bounding_box().unwrap();
+        assert_eq!(bounds.min, Vec3::new(-1.0, -1.0, -1.0));
+        assert_eq!(bounds.max, Vec3::new(1.0, 5.0, 1.0));
+    }
+
+    #[test]
+    fn uv_test() {
+        let sphere = Sphere::new(1.0, 2.0, 3.0, 2.0, Lambertian::new(0.5, 0.5, 0.5));
+        let center = Vec3::new(1.0, 2.0, 3.0);
+        let mut sampler = Sampler::new(5, 0);
+        for _ in 0..100 {
+            let direction = util::random_unit_vector(&mut sampler);
+            let r = Ray::new(center + 5.0 * direction, -direction);
+            let hit = sphere.hit(&r, 0.001, f64::MAX).unwrap();
+            assert!(hit.front_face);
+            let (u, v) = hit.uv;
+            // moving along the tangents moves the texture coordinates
+            let uv = |p: Vec3| sphere_uv((p - center).make_unit_vector());
+            let (du, dv) = uv(hit.p + 1e-7 * hit.dpdu);
+            assert!(((du - u).rem_euclid(1.0) - 1e-7).abs() < 1e-9 && (dv - v).abs() < 1e-9);
+            let (du, dv) = uv(hit.p + 1e-7 * hit.dpdv);
+            assert!((du - u).abs() < 1e-6 && (dv - v - 1e-7).abs() < 1e-9);
+            // from the inside
+            let r = Ray::new(center, direction);
+            assert!(!sphere.hit(&r, 0.001, f64::MAX).unwrap().front_face);
+        }
+        let bottom = Ray::new(Vec3::new(1.0, -5.0, 3.0), Vec3::new(0.0, 1.0, 0.0));
+        assert!(sphere.hit(&bottom, 0.001, f64::MAX).unwrap().uv.1 < 1e-9);
+
+        // a hollow sphere faces inwards but keeps its texture coordinates
+        let hollow = Sphere::new(1.0, 2.0, 3.0, -2.0, Lambertian::new(0.5, 0.5, 0.5));
+        let hit = hollow.hit(&bottom, 0.001, f64::MAX).unwrap();
+        assert!(!hit.front_face);
+        assert_eq!(hit.geometric_normal, Vec3::new(0.0, 1.0, 0.0));
+        assert!(hit.uv.1 < 1e-9);
+    }
+
+    #[test]
+    fn sample_towards_test() {
+        let light = Sphere::new(0.0, 0.0, -4.0, 1.0, DiffuseLight::new(1.0, 1.0, 1.0));
+        assert!(light.is_emitter());
+        let mut sampler = Sampler::new(3, 0);
+        // the cone seen from 4 units away covers 2π(1 - cos(asin(1/4))) sr
+        let solid_angle = 2.0 * PI * (1.0 - (15.0_f64 / 16.0).sqrt());
+        for _ in 0..100 {
+            let sample = light
+                .sample_towards(Vec3::zeros(), 0.0, &mut sampler)
+                .unwrap();
+            assert!((sample.pdf - 1.0 / solid_angle).abs() < 1e-9);
+            let r = Ray::new(Vec3::zeros(), sample.direction);
+            assert!(light.hit(&r, 0.001, f64::MAX).is_some());
+            assert_eq!(
+                light.pdf_towards(Vec3::zeros(), sample.direction, 0.0),
+                sample.pdf
+            );
+        }
+        let away = Vec3::new(0.0, 0.0, 1.0);
+        assert_eq!(light.pdf_towards(Vec3::zeros(), away, 0.0), 0.0);
+    }
+}
@@ -0,0 +1,180 @@
+use super::{HitRecord, Hittable, LightSample};
+use crate::aabb::Aabb;
+use crate::motion::{Keyframes, Placement};
+use crate::ray::Ray;
+use crate::sampler::Sampler;
+use crate::transform::Transform;
+use crate::vec3::Vec3;
+use std::sync::Arc;
+
+/// An instance of an object placed in the world by a transform.
+///
+/// The object is shared, so placing it many times does not copy its
+/// geometry.
+pub struct Transformed<H: Hittable + ?Sized> {
+    object: Arc<H>,
+    /// from the space of the object to the world
+    transform: Transform,
+    /// replaces `transform` by the placement at the time of a ray
+    path: Option<Keyframes<Placement>>,
+}
+
+impl<H: Hittable + ?Sized> Transformed<H> {
+    pub fn new(object: Arc<H>, transform: Transform) -> Box<Transformed<H>> {
+        Box::new(Transformed {
+            object,
+            transform,
+            path: None,
+        })
+    }
+
+    /// An instance moving along `path` over time
+    pub fn moving(object: Arc<H>, path: Keyframes<Placement>) -> Box<Transformed<H>> {
+        Box::new(Transformed {
+            object,
+            transform: Transform::identity(),
+            path: Some(path),
+        })
+    }
+
+    pub fn object(&self) -> &Arc<H> {
+        &self.object
+    }
+
+    /// The transform from the space of the object to the world at `time`
+    pub fn transform_at(&self, time: f64) -> Transform {
+        match &self.path {
+            Some(path) => path.at(time).transform(),
+            None => self.transform,
+        }
+    }
+}
+
+impl<H: Hittable + ?Sized> Hittable for Transformed<H> {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let transform = self.transform_at(r.time);
+        let local = transform.inverse().ray(r);
+        let mut hit = self.object.hit(&local, t_min, t_max)?;
+        hit.p = transform.point(hit.p);
+        hit.n = transform.normal(hit.n).make_unit_vector();
+        // the side a ray comes from does not change, nor the texture coordinates
+        hit.geometric_normal = transform.normal(hit.geometric_normal).make_unit_vector();
+        hit.dpdu = transform.vector(hit.dpdu);
+        hit.dpdv = transform.vector(hit.dpdv);
+        Some(hit)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let bounds = self.object.bounding_box()?;
+        Some(match &self.path {
+            Some(path) => path.bounding_box(&bounds),
+            None => self.transform.bounding_box(&bounds),
+        })
+    }
+
+    fn is_emitter(&self) -> bool {
+        self.object.is_emitter()
+    }
+
+    /// Sample the object in its own space. A linear map `M` changes the
+    /// solid angle density of a unit direction `w` by `|M w|³ / |det M|`.
+    fn sample_towards(
+        &self,
+        origin: Vec3,
+        time: f64,
+        sampler: &mut Sampler,
+    ) -> Option<LightSample> {
+        let transform = self.transform_at(time);
+        let local_origin = transform.inverse().point(origin);
+        let sample = self.object.sample_towards(local_origin, time, sampler)?;
+        let direction = transform.vector(sample.direction);
+        let stretch = direction.length();
+        let determinant = transform.matrix().determinant3().abs();
+        Some(LightSample {
+            direction: direction / stretch,
+            pdf: sample.pdf * stretch.powi(3) / determinant,
+        })
+    }
+
+    fn pdf_towards(&self, origin: Vec3, direction: Vec3, time: f64) -> f64 {
+        let transform = self.transform_at(time);
+        let inverse = transform.inverse();
+        let local = inverse.vector(direction);
+        let shrink = local.length();
+        let pdf = self
+            .object
+            .pdf_towards(inverse.point(origin), local / shrink, time);
+        pdf / (shrink.powi(3) * transform.matrix().determinant3().abs())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::hittable::Sphere;
+    use crate::materials::Lambertian;
+    use crate::vec3::Vec3;
+
+    #[test]
+    fn instance_test() {
+        let sphere: Arc<Sphere> = Arc::from(Sphere::new(
+            0.0,
+            0.0,
+            0.0,
+            1.0,
+            Lambertian::new(0.5, 0.5, 0.5),
+        ));
+        let transform = Transform::scaling(Vec3::new(2.0, 1.0, 1.0))
+            .then(&Transform::translation(Vec3::new(0.0, 0.0, -5.0)));
+        let a = Transformed::new(Arc::clone(&sphere), transform);
+        let b = Transformed::new(Arc::clone(&sphere), Transform::identity());
+        assert_eq!(Arc::strong_count(&sphere), 3);
+
+        // the ellipsoid is two units wide along x
+        let r = Ray::new(Vec3::new(-10.0, 0.0, -5.0), Vec3::new(1.0, 0.0, 0.0));
+        let hit = a.hit(&r, 0.001, f64::MAX).unwrap();
+        assert!((hit.t - 8.0).abs() < 1e-9);
+        assert!((hit.p - Vec3::new(-2.0, 0.0, -5.0)).length() < 1e-9);
+        assert!((hit.n - Vec3::new(-1.0, 0.0, 0.0)).length() < 1e-9);
+        assert!(b.hit(&r, 0.001, f64::MAX).is_none());
+
+        let bounds = a.bounding_box().unwrap();
+        assert_eq!(bounds.min, Vec3::new(-2.0, -1.0, -6.0));
+        assert_eq!(bounds.max, Vec3::new(2.0, 1.0, -4.0));
+    }
+
+    #[test]
+    fn moving_test() {
+        let sphere: Arc<Sphere> = Arc::from(Sphere::new(
+            0.0,
+            0.0,
+            0.0,
+            1.0,
+            Lambertian::new(0.5, 0.5, 0.5),
+        ));
+        // a quarter turn around y while moving 4 units along x
+        let y = Vec3::new(0.0, 1.0, 0.0);
+        let squash = Vec3::new(2.0, 1.0, 1.0);
+        let path = Keyframes::linear(
+            0.0,
+            Placement::new(squash, y, 0.0, Vec3::zeros()),
+            1.0,
+            Placement::new(squash, y, 90.0, Vec3::new(4.0, 0.0, 0.0)),
+        );
+        let instance = Transformed::moving(sphere, path);
+        let down = Vec3::new(0.0, -1.0, 0.0);
+
+        // long along x at first, then along z
+        let r = Ray::with_time(Vec3::new(1.5, 5.0, 0.0), down, 0.0);
+        assert!(instance.hit(&r, 0.001, f64::MAX).is_some());
+        let r = Ray::with_time(Vec3::new(5.5, 5.0, 0.0), down, 1.0);
+        assert!(instance.hit(&r, 0.001, f64::MAX).is_none());
+        let r = Ray::with_time(Vec3::new(4.0, 5.0, 1.5), down, 1.0);
+        let hit = instance.hit(&r, 0.001, f64::MAX).unwrap();
+        assert!((hit.p.y - 0.661437827766).abs() < 1e-9);
+
+        let bounds = instance.bounding_box().unwrap();
+        assert!(bounds.min.x <= -2.0 && bounds.max.x >= 5.0);
+        assert!(bounds.min.z <= -2.0 && bounds.max.z >= 2.0);
+    }
+}
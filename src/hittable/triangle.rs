@@ -0,0 +1,151 @@
+use super::{area_pdf, area_sample, HitRecord, Hittable, LightSample};
+use crate::aabb::Aabb;
+use crate::materials::Materialable;
+use crate::sampler::Sampler;
+use crate::{ray::Ray, util, vec3::Vec3};
+
+/// Where a ray crosses a triangle
+pub(super) struct Crossing {
+    pub t: f64,
+    /// barycentric coordinates of the hit point relative to the second and
+    /// third vertex, the first one having weight `1 - u - v`
+    pub u: f64,
+    pub v: f64,
+}
+
+/// Möller–Trumbore ray triangle intersection
+pub(super) fn intersect(
+    r: &Ray,
+    a: Vec3,
+    b: Vec3,
+    c: Vec3,
+    t_min: f64,
+    t_max: f64,
+) -> Option<Crossing> {
+    let edge1 = b - a;
+    let edge2 = c - a;
+    let pvec = r.direction.cross(edge2);
+    let det = edge1.dot(pvec);
+    // the ray is parallel to the plane of the triangle
+    if det.abs() < 1e-12 {
+        return None;
+    }
+    let inv_det = 1.0 / det;
+    let tvec = r.origin - a;
+    let u = tvec.dot(pvec) * inv_det;
+    if !(0.0..=1.0).contains(&u) {
+        return None;
+    }
+    let qvec = tvec.cross(edge1);
+    let v = r.direction.dot(qvec) * inv_det;
+    if v < 0.0 || u + v > 1.0 {
+        return None;
+    }
+    let t = edge2.dot(qvec) * inv_det;
+    if util::is_between(t, t_min, t_max) {
+        Some(Crossing { t, u, v })
+    } else {
+        None
+    }
+}
+
+/// A point uniformly distributed over the triangle `abc`
+pub(super) fn sample_point(a: Vec3, b: Vec3, c: Vec3, sampler: &mut Sampler) -> Vec3 {
+    let s = sampler.next_f64().sqrt();
+    let (u, v) = (1.0 - s, sampler.next_f64() * s);
+    a + u * (b - a) + v * (c - a)
+}
+
+/// A single triangle, see [`TriangleMesh`](super::TriangleMesh) for triangles sharing vertices
+pub struct Triangle {
+    vertices: [Vec3; 3],
+    normal: Vec3,
+    material: Box<dyn Materialable>,
+}
+
+impl Triangle {
+    /// Create the triangle `abc`, its normal facing the side from which the
+    /// vertices appear counterclockwise
+    pub fn new(a: Vec3, b: Vec3, c: Vec3, m: Box<dyn Materialable>) -> Box<Triangle> {
+        Box::new(Triangle {
+            vertices: [a, b, c],
+            normal: (b - a).cross(c - a).make_unit_vector(),
+            material: m,
+        })
+    }
+
+    fn area(&self) -> f64 {
+        let [a, b, c] = self.vertices;
+        0.5 * (b - a).cross(c - a).length()
+    }
+}
+
+impl Hittable for Triangle {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let [a, b, c] = self.vertices;
+        intersect(r, a, b, c, t_min, t_max).map(|crossing| HitRecord {
+            t: crossing.t,
+            p: r.point_at_parameter(crossing.t),
+            n: self.normal,
+            geometric_normal: self.normal,
+            front_face: r.direction.dot(self.normal) < 0.0,
+            material: self.material.as_ref(),
+            color: None,
+            // the barycentric coordinates
+            uv: (crossing.u, crossing.v),
+            dpdu: b - a,
+            dpdv: c - a,
+            object_id: 0,
+            primitive_id: 0,
+        })
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let [a, b, c] = self.vertices;
+        Some(Aabb::new(a, b).grow(c))
+    }
+
+    fn is_emitter(&self) -> bool {
+        self.material.is_emitter()
+    }
+
+    fn sample_towards(
+        &self,
+        origin: Vec3,
+        _time: f64,
+        sampler: &mut Sampler,
+    ) -> Option<LightSample> {
+        let [a, b, c] = self.vertices;
+        let p = sample_point(a, b, c, sampler);
+        area_sample(origin, p, self.normal, self.area())
+    }
+
+    fn pdf_towards(&self, origin: Vec3, direction: Vec3, time: f64) -> f64 {
+        let r = Ray::with_time(origin, direction, time);
+        match self.hit(&r, 0.001, f64::MAX) {
+            Some(hit) => area_pdf(direction, hit.t, self.normal, self.area()),
+            None => 0.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn intersect_test() {
+        let (a, b, c) = (
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(1.0, 0.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+        );
+        let r = Ray::new(Vec3::new(0.25, 0.5, 2.0), Vec3::new(0.0, 0.0, -1.0));
+        let crossing = intersect(&r, a, b, c, 0.0, f64::MAX).unwrap();
+        assert_eq!((crossing.t, crossing.u, crossing.v), (2.0, 0.25, 0.5));
+
+        let outside = Ray::new(Vec3::new(0.75, 0.5, 2.0), Vec3::new(0.0, 0.0, -1.0));
+        assert!(intersect(&outside, a, b, c, 0.0, f64::MAX).is_none());
+        assert!(intersect(&r, a, b, c, 0.0, 1.0).is_none());
+    }
+}
@@ -0,0 +1,322 @@
+use super::{HitRecord, Hittable, LightSample};
+use crate::aabb::Aabb;
+use crate::bvh::Bvh;
+use crate::environment::{Environment, SkyGradient, Uniform};
+use crate::ray::Ray;
+use crate::sampler::Sampler;
+use crate::vec3::Vec3;
+use std::sync::OnceLock;
+
+/// The collection of every object in a scene.
+///
+/// Bounded objects are organized in a bounding volume hierarchy, built the
+/// first time the world is intersected after objects were added.
+#[derive(Default)]
+pub struct World {
+    meshes: Vec<Box<dyn Hittable>>,
+    accelerator: OnceLock<Accelerator>,
+    /// indices of the meshes emitting light
+    lights: Vec<usize>,
+    /// the radiance of rays leaving the scene, the sky gradient when `None`
+    environment: Option<Box<dyn Environment>>,
+}
+
+struct Accelerator {
+    bvh: Bvh,
+    /// indices of the meshes without bounding box
+    unbounded: Vec<usize>,
+}
+
+impl World {
+    pub fn new() -> World {
+        World::default()
+    }
+
+    pub fn add(&mut self, mesh: Box<dyn Hittable>) {
+        if mesh.is_emitter() {
+            self.lights.push(self.meshes.len());
+        }
+        self.meshes.push(mesh);
+        self.accelerator = OnceLock::new();
+    }
+
+    /// Move every object of `other` into this world
+    pub fn append(&mut self, other: World) {
+        for mesh in other.meshes {
+            self.add(mesh);
+        }
+    }
+
+    /// The objects emitting light, which are sampled directly
+    pub fn lights(&self) -> impl Iterator<Item = &dyn Hittable> + '_ {
+        self.lights.iter().map(move |&i| self.meshes[i].as_ref())
+    }
+
+    /// Light rays leaving the scene with a uniform `color`, black for closed
+    /// interiors lit by their own light sources
+    pub fn set_background(&mut self, color: Vec3) {
+        self.set_environment(Uniform::new(color));
+    }
+
+    /// Light rays leaving the scene with `environment`
+    pub fn set_environment(&mut self, environment: Box<dyn Environment>) {
+        self.environment = Some(environment);
+    }
+
+    /// The radiance of a ray going towards `direction` without hitting anything
+    pub fn background(&self, direction: Vec3) -> Vec3 {
+        match &self.environment {
+            Some(environment) => environment.radiance(direction),
+            None => SkyGradient.radiance(direction),
+        }
+    }
+
+    /// Whether the environment is sampled along with the lights, in which
+    /// case light sampling accounts for the rays leaving the scene
+    pub fn samples_environment(&self) -> bool {
+        self.environment.as_ref().is_some_and(|e| e.is_sampled())
+    }
+
+    /// Build the bounding volume hierarchy now rather than on the first intersection
+    pub fn build_bvh(&self) {
+        self.accelerator();
+    }
+
+    fn accelerator(&self) -> &Accelerator {
+        self.accelerator.get_or_init(|| {
+            let mut bounded = Vec::with_capacity(self.meshes.len());
+            let mut unbounded = Vec::new();
+            for (i, mesh) in self.meshes.iter().enumerate() {
+                match mesh.bounding_box() {
+                    Some(bounds) => bounded.push((i, bounds)),
+                    None => unbounded.push(i),
+                }
+            }
+            Accelerator {
+                bvh: Bvh::build(&bounded),
+                unbounded,
+            }
+        })
+    }
+
+    pub fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let accelerator = self.accelerator();
+        let mut closest_so_far = t_max;
+        let mut record: Option<HitRecord> = None;
+
+        for i in &accelerator.unbounded {
+            if let Some(mut temp) = self.meshes[*i].hit(r, t_min, closest_so_far) {
+                closest_so_far = temp.t;
+                temp.object_id = *i;
+                record = Some(temp);
+            }
+        }
+        let bvh_record = accelerator
+            .bvh
+            .hit(r, t_min, closest_so_far, |i, r, t_min, t_max| {
+                let mut hit = self.meshes[i].hit(r, t_min, t_max)?;
+                hit.object_id = i;
+                Some(hit)
+            });
+        bvh_record.or(record)
+    }
+
+    /// Intersect every mesh in turn, bypassing the bounding volume hierarchy
+    pub fn hit_linear(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let mut closest_so_far = t_max;
+        let mut record: Option<HitRecord> = None;
+
+        for (i, mesh) in self.meshes.iter().enumerate() {
+            if let Some(mut temp) = mesh.hit(r, t_min, closest_so_far) {
+                closest_so_far = temp.t;
+                temp.object_id = i;
+                record = Some(temp);
+            }
+        }
+        record
+    }
+}
+
+/// A world can be placed in another one, typically through [`super::Transformed`]
+impl Hittable for World {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        World::hit(self, r, t_min, t_max)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let accelerator = self.accelerator();
+        if accelerator.unbounded.is_empty() {
+            accelerator.bvh.bounds()
+        } else {
+            None
+        }
+    }
+
+    fn is_emitter(&self) -> bool {
+        !self.lights.is_empty()
+    }
+
+    /// Pick one of the lights, or the environment when it is sampled,
+    /// uniformly and sample it. The density accounts for every light which
+    /// could have produced the direction.
+    fn sample_towards(
+        &self,
+        origin: Vec3,
+        time: f64,
+        sampler: &mut Sampler,
+    ) -> Option<LightSample> {
+        let environment = self.environment.as_ref().filter(|e| e.is_sampled());
+        let count = self.lights.len() + environment.is_some() as usize;
+        if count == 0 {
+            return None;
+        }
+        let chosen = ((sampler.next_f64() * count as f64) as usize).min(count - 1);
+        let mut sample = match self.lights.get(chosen) {
+            Some(&light) => self.meshes[light].sample_towards(origin, time, sampler)?,
+            None => environment?.sample(sampler)?,
+        };
+        for (i, &light) in self.lights.iter().enumerate() {
+            if i != chosen {
+                sample.pdf += self.meshes[light].pdf_towards(origin, sample.direction, time);
+            }
+        }
+        if let Some(environment) = environment.filter(|_| chosen < self.lights.len()) {
+            sample.pdf += environment.pdf(sample.direction);
+        }
+        sample.pdf /= count as f64;
+        Some(sample)
+    }
+
+    fn pdf_towards(&self, origin: Vec3, direction: Vec3, time: f64) -> f64 {
+        let mut total: f64 = self
+            .lights()
+            .map(|light| light.pdf_towards(origin, direction, time))
+            .sum();
+        let mut count = self.lights.len();
+        if let Some(environment) = self.environment.as_ref().filter(|e| e.is_sampled()) {
+            total += environment.pdf(direction);
+            count += 1;
+        }
+        total / count.max(1) as f64
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::hittable::{Quad, Sphere, Transformed, TriangleMesh};
+    use crate::materials::{DiffuseLight, Lambertian};
+    use crate::transform::Transform;
+    use crate::util;
+    use std::sync::Arc;
+
+    #[test]
+    fn bvh_matches_linear_test() {
+        let mut sampler = Sampler::new(1, 0);
+        let mut world = World::new();
+        for _ in 0..200 {
+            world.add(Sphere::new(
+                sampler.gen_range(-10.0, 10.0),
+                sampler.gen_range(-10.0, 10.0),
+                sampler.gen_range(-10.0, 10.0),
+                sampler.gen_range(0.1, 1.0),
+                Lambertian::new(0.5, 0.5, 0.5),
+            ));
+        }
+        for _ in 0..500 {
+            let origin = Vec3::new(
+                sampler.gen_range(-15.0, 15.0),
+                sampler.gen_range(-15.0, 15.0),
+                sampler.gen_range(-15.0, 15.0),
+            );
+            let direction = util::random_in_unit_sphere(&mut sampler);
+            let r = Ray::new(origin, direction);
+            let fast = world.hit(&r, 0.001, f64::MAX).map(|h| (h.t, h.object_id));
+            let slow = world
+                .hit_linear(&r, 0.001, f64::MAX)
+                .map(|h| (h.t, h.object_id));
+            assert_eq!(fast, slow);
+        }
+    }
+
+    #[test]
+    fn degenerate_bvh_test() {
+        // spheres growing geometrically give a very unbalanced hierarchy, and
+        // one with a NaN center must not stop it from being built
+        let mut world = World::new();
+        for i in 0..600 {
+            let x = 1.5f64.powi(i);
+            world.add(Sphere::new(
+                x,
+                0.0,
+                0.0,
+                0.25 * x,
+                Lambertian::new(0.5, 0.5, 0.5),
+            ));
+        }
+        world.add(Sphere::new(
+            f64::NAN,
+            0.0,
+            0.0,
+            1.0,
+            Lambertian::new(0.5, 0.5, 0.5),
+        ));
+        for i in 0..600 {
+            let x = 1.5f64.powi(i);
+            let r = Ray::new(Vec3::new(x, 0.0, -2.0 * x), Vec3::new(0.0, 0.0, 1.0));
+            let fast = world.hit(&r, 0.001, f64::MAX).map(|h| h.object_id);
+            let slow = world.hit_linear(&r, 0.001, f64::MAX).map(|h| h.object_id);
+            assert_eq!(fast, slow);
+            assert!(fast.is_some());
+        }
+    }
+
+    #[test]
+    fn light_sampling_test() {
+        // two 2x2 squares one unit above and below the origin, each covering
+        // 4 asin(1 / 2) = 2π / 3 sr
+        let mut world = World::new();
+        let unit = Quad::new(
+            Vec3::new(-0.5, 0.0, -0.5),
+            Vec3::new(1.0, 0.0, 0.0),
+            Vec3::new(0.0, 0.0, 1.0),
+            DiffuseLight::new(1.0, 1.0, 1.0),
+        );
+        let placement = Transform::scaling(Vec3::new(2.0, 1.0, 2.0))
+            .then(&Transform::translation(Vec3::new(0.0, 1.0, 0.0)));
+        world.add(Transformed::new(Arc::<Quad>::from(unit), placement));
+        world.add(TriangleMesh::new(
+            vec![
+                Vec3::new(-1.0, -1.0, -1.0),
+                Vec3::new(1.0, -1.0, -1.0),
+                Vec3::new(1.0, -1.0, 1.0),
+                Vec3::new(-1.0, -1.0, 1.0),
+            ],
+            vec![[0, 1, 2], [0, 2, 3]],
+            vec![DiffuseLight::new(1.0, 1.0, 1.0)],
+            vec![0, 0],
+        ));
+        world.add(Sphere::new(
+            5.0,
+            0.0,
+            0.0,
+            1.0,
+            Lambertian::new(0.5, 0.5, 0.5),
+        ));
+        assert_eq!(world.lights().count(), 2);
+
+        let mut sampler = Sampler::new(5, 0);
+        let count = 20_000;
+        let mut solid_angle = 0.0;
+        for _ in 0..count {
+            let sample = world
+                .sample_towards(Vec3::zeros(), 0.0, &mut sampler)
+                .unwrap();
+            let pdf = world.pdf_towards(Vec3::zeros(), sample.direction, 0.0);
+            assert!((pdf - sample.pdf).abs() < 1e-9 * pdf);
+            solid_angle += 1.0 / sample.pdf / count as f64;
+        }
+        let expected = 4.0 * std::f64::consts::PI / 3.0;
+        assert!((solid_angle - expected).abs() < 0.02 * expected);
+    }
+}
@@ -0,0 +1,511 @@
+//! A small DEFLATE (RFC 1951) encoder and decoder wrapped in a zlib (RFC
+//! 1950) stream.
+//!
+//! Data is compressed with greedy LZ77 matching and the fixed Huffman codes,
+//! which is much simpler than building dynamic trees while still shrinking
+//! rendered images considerably. Stored blocks are available when speed
+//! matters more than size. The decoder reads every kind of block, as found in
+//! PNG files written by other programs.
+
+use std::io;
+
+/// How the data is encoded
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Compression {
+    /// no compression at all, the data is copied into stored blocks
+    Stored,
+    /// LZ77 compression with fixed Huffman codes
+    Fixed,
+}
+
+const WINDOW_SIZE: usize = 1 << 15;
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+const MAX_CHAIN: usize = 64;
+const HASH_BITS: u32 = 15;
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DIST_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DIST_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+
+/// Writes bits least significant first as required by DEFLATE
+struct BitWriter {
+    out: Vec<u8>,
+    acc: u64,
+    count: u32,
+}
+
+impl BitWriter {
+    fn new(out: Vec<u8>) -> BitWriter {
+        BitWriter {
+            out,
+            acc: 0,
+            count: 0,
+        }
+    }
+
+    fn write_bits(&mut self, bits: u32, count: u32) {
+        self.acc |= (bits as u64) << self.count;
+        self.count += count;
+        while self.count >= 8 {
+            self.out.push(self.acc as u8);
+            self.acc >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    /// Huffman codes are packed starting from their most significant bit
+    fn write_code(&mut self, code: u32, len: u32) {
+        let reversed = code.reverse_bits() >> (32 - len);
+        self.write_bits(reversed, len);
+    }
+
+    fn align(&mut self) {
+        if self.count > 0 {
+            self.write_bits(0, 8 - self.count);
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        self.align();
+        self.out
+    }
+}
+
+fn write_literal(w: &mut BitWriter, symbol: u16) {
+    let symbol = symbol as u32;
+    match symbol {
+        0..=143 => w.write_code(0x30 + symbol, 8),
+        144..=255 => w.write_code(0x190 + symbol - 144, 9),
+        256..=279 => w.write_code(symbol - 256, 7),
+        _ => w.write_code(0xc0 + symbol - 280, 8),
+    }
+}
+
+fn write_match(w: &mut BitWriter, length: usize, distance: usize) {
+    let li = LENGTH_BASE
+        .iter()
+        .rposition(|&b| b as usize <= length)
+        .unwrap();
+    write_literal(w, 257 + li as u16);
+    w.write_bits(
+        (length - LENGTH_BASE[li] as usize) as u32,
+        LENGTH_EXTRA[li] as u32,
+    );
+
+    let di = DIST_BASE
+        .iter()
+        .rposition(|&b| b as usize <= distance)
+        .unwrap();
+    w.write_code(di as u32, 5);
+    w.write_bits(
+        (distance - DIST_BASE[di] as usize) as u32,
+        DIST_EXTRA[di] as u32,
+    );
+}
+
+fn hash(data: &[u8], i: usize) -> usize {
+    let v = (data[i] as u32) << 16 | (data[i + 1] as u32) << 8 | data[i + 2] as u32;
+    (v.wrapping_mul(2_654_435_761) >> (32 - HASH_BITS)) as usize
+}
+
+fn compress_fixed(data: &[u8], w: &mut BitWriter) {
+    // single final block using the fixed codes
+    w.write_bits(1, 1);
+    w.write_bits(1, 2);
+
+    // most recent position of every hash and the previous position with the same hash
+    let mut head = vec![usize::MAX; 1 << HASH_BITS];
+    let mut prev = vec![usize::MAX; WINDOW_SIZE];
+    let insert = |head: &mut [usize], prev: &mut [usize], i: usize| {
+        if i + MIN_MATCH <= data.len() {
+            let h = hash(data, i);
+            prev[i % WINDOW_SIZE] = head[h];
+            head[h] = i;
+        }
+    };
+
+    let mut i = 0;
+    while i < data.len() {
+        let mut best_len = 0;
+        let mut best_dist = 0;
+        if i + MIN_MATCH <= data.len() {
+            let max_len = MAX_MATCH.min(data.len() - i);
+            let mut candidate = head[hash(data, i)];
+            let mut chain = 0;
+            while candidate != usize::MAX && i - candidate <= WINDOW_SIZE && chain < MAX_CHAIN {
+                let len = data[candidate..]
+                    .iter()
+                    .zip(&data[i..i + max_len])
+                    .take_while(|(a, b)| a == b)
+                    .count();
+                if len > best_len {
+                    best_len = len;
+                    best_dist = i - candidate;
+                    if len == max_len {
+                        break;
+                    }
+                }
+                let next = prev[candidate % WINDOW_SIZE];
+                // entries of the chain older than the window have been overwritten
+                if next == usize::MAX || next >= candidate {
+                    break;
+                }
+                candidate = next;
+                chain += 1;
+            }
+        }
+
+        if best_len >= MIN_MATCH {
+            write_match(w, best_len, best_dist);
+            for j in i..i + best_len {
+                insert(&mut head, &mut prev, j);
+            }
+            i += best_len;
+        } else {
+            write_literal(w, data[i] as u16);
+            insert(&mut head, &mut prev, i);
+            i += 1;
+        }
+    }
+    write_literal(w, 256);
+}
+
+fn compress_stored(data: &[u8], w: &mut BitWriter) {
+    let mut chunks = data.chunks(0xffff).peekable();
+    if chunks.peek().is_none() {
+        // an empty input still needs one final block
+        w.write_bits(1, 3);
+        w.align();
+        w.out.extend_from_slice(&[0, 0, 0xff, 0xff]);
+        return;
+    }
+    while let Some(chunk) = chunks.next() {
+        let last = chunks.peek().is_none();
+        w.write_bits(last as u32, 3);
+        w.align();
+        let len = chunk.len() as u16;
+        w.out.extend_from_slice(&len.to_le_bytes());
+        w.out.extend_from_slice(&(!len).to_le_bytes());
+        w.out.extend_from_slice(chunk);
+    }
+}
+
+/// Checksum used by the zlib stream
+pub fn adler32(data: &[u8]) -> u32 {
+    const MOD: u32 = 65521;
+    let (mut a, mut b) = (1u32, 0u32);
+    // 5552 is the largest number of bytes that cannot overflow `b`
+    for chunk in data.chunks(5552) {
+        for &byte in chunk {
+            a += byte as u32;
+            b += a;
+        }
+        a %= MOD;
+        b %= MOD;
+    }
+    (b << 16) | a
+}
+
+/// Compress `data` into a zlib stream
+pub fn zlib_compress(data: &[u8], compression: Compression) -> Vec<u8> {
+    // 32K window, deflate, check bits making the header a multiple of 31
+    let mut w = BitWriter::new(vec![0x78, 0x9c]);
+    match compression {
+        Compression::Stored => compress_stored(data, &mut w),
+        Compression::Fixed => compress_fixed(data, &mut w),
+    }
+    let mut out = w.finish();
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+fn corrupt(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+/// Reads bits least significant first
+struct BitReader<'a> {
+    data: &'a [u8],
+    position: usize,
+    acc: u64,
+    count: u32,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8]) -> BitReader<'a> {
+        BitReader {
+            data,
+            position: 0,
+            acc: 0,
+            count: 0,
+        }
+    }
+
+    fn read_bits(&mut self, count: u32) -> io::Result<u32> {
+        while self.count < count {
+            let byte = *self
+                .data
+                .get(self.position)
+                .ok_or_else(|| corrupt("truncated deflate stream"))?;
+            self.acc |= (byte as u64) << self.count;
+            self.position += 1;
+            self.count += 8;
+        }
+        let bits = (self.acc & ((1u64 << count) - 1)) as u32;
+        self.acc >>= count;
+        self.count -= count;
+        Ok(bits)
+    }
+
+    /// Skip to the next byte boundary
+    fn align(&mut self) {
+        let extra = self.count % 8;
+        self.acc >>= extra;
+        self.count -= extra;
+    }
+}
+
+/// A canonical Huffman code, decoded one bit at a time
+struct Huffman {
+    /// number of codes of each length
+    counts: [u16; 16],
+    /// symbols sorted by code
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Huffman {
+        let mut counts = [0u16; 16];
+        for &length in lengths {
+            counts[length as usize] += 1;
+        }
+        counts[0] = 0;
+        let mut offsets = [0u16; 16];
+        for i in 1..16 {
+            offsets[i] = offsets[i - 1] + counts[i - 1];
+        }
+        let mut symbols = vec![0; lengths.len()];
+        for (symbol, &length) in lengths.iter().enumerate() {
+            if length > 0 {
+                symbols[offsets[length as usize] as usize] = symbol as u16;
+                offsets[length as usize] += 1;
+            }
+        }
+        Huffman { counts, symbols }
+    }
+
+    fn decode(&self, reader: &mut BitReader) -> io::Result<u16> {
+        // codes of each length follow the last one of the previous length
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+        for &count in &self.counts[1..] {
+            code |= reader.read_bits(1)? as i32;
+            let count = count as i32;
+            if code - first < count {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err(corrupt("invalid Huffman code"))
+    }
+}
+
+fn fixed_codes() -> (Huffman, Huffman) {
+    let mut lengths = [0u8; 288];
+    for (symbol, length) in lengths.iter_mut().enumerate() {
+        *length = match symbol {
+            0..=143 => 8,
+            144..=255 => 9,
+            256..=279 => 7,
+            _ => 8,
+        };
+    }
+    (Huffman::new(&lengths), Huffman::new(&[5; 30]))
+}
+
+/// Order in which the lengths of the code length code are stored
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+fn dynamic_codes(reader: &mut BitReader) -> io::Result<(Huffman, Huffman)> {
+    let literals = reader.read_bits(5)? as usize + 257;
+    let distances = reader.read_bits(5)? as usize + 1;
+    let code_lengths = reader.read_bits(4)? as usize + 4;
+    let mut lengths = [0u8; 19];
+    for &i in &CODE_LENGTH_ORDER[..code_lengths] {
+        lengths[i] = reader.read_bits(3)? as u8;
+    }
+    let code_length_code = Huffman::new(&lengths);
+
+    let mut lengths = Vec::with_capacity(literals + distances);
+    while lengths.len() < literals + distances {
+        let (value, repeat) = match code_length_code.decode(reader)? {
+            symbol @ 0..=15 => (symbol as u8, 1),
+            16 => {
+                let previous = *lengths
+                    .last()
+                    .ok_or_else(|| corrupt("repeated length without a previous one"))?;
+                (previous, 3 + reader.read_bits(2)?)
+            }
+            17 => (0, 3 + reader.read_bits(3)?),
+            _ => (0, 11 + reader.read_bits(7)?),
+        };
+        for _ in 0..repeat {
+            lengths.push(value);
+        }
+    }
+    if lengths.len() > literals + distances {
+        return Err(corrupt("too many code lengths"));
+    }
+    Ok((
+        Huffman::new(&lengths[..literals]),
+        Huffman::new(&lengths[literals..]),
+    ))
+}
+
+fn inflate_block(
+    reader: &mut BitReader,
+    out: &mut Vec<u8>,
+    literals: &Huffman,
+    distances: &Huffman,
+) -> io::Result<()> {
+    loop {
+        let symbol = literals.decode(reader)? as usize;
+        match symbol {
+            0..=255 => out.push(symbol as u8),
+            256 => return Ok(()),
+            _ => {
+                let i = symbol - 257;
+                if i >= LENGTH_BASE.len() {
+                    return Err(corrupt("invalid length symbol"));
+                }
+                let length =
+                    LENGTH_BASE[i] as usize + reader.read_bits(LENGTH_EXTRA[i] as u32)? as usize;
+                let d = distances.decode(reader)? as usize;
+                if d >= DIST_BASE.len() {
+                    return Err(corrupt("invalid distance symbol"));
+                }
+                let distance =
+                    DIST_BASE[d] as usize + reader.read_bits(DIST_EXTRA[d] as u32)? as usize;
+                if distance > out.len() {
+                    return Err(corrupt("distance before the start of the data"));
+                }
+                // the copy may overlap the bytes it produces
+                let start = out.len() - distance;
+                for k in 0..length {
+                    out.push(out[start + k]);
+                }
+            }
+        }
+    }
+}
+
+/// Decompress a zlib stream, checking its checksum
+pub fn zlib_decompress(data: &[u8]) -> io::Result<Vec<u8>> {
+    if data.len() < 6
+        || data[0] & 0x0f != 8
+        || !u16::from_be_bytes([data[0], data[1]]).is_multiple_of(31)
+    {
+        return Err(corrupt("invalid zlib header"));
+    }
+    if data[1] & 0x20 != 0 {
+        return Err(corrupt("preset dictionaries are not supported"));
+    }
+    let mut reader = BitReader::new(&data[2..]);
+    let mut out = Vec::new();
+    loop {
+        let last = reader.read_bits(1)? == 1;
+        match reader.read_bits(2)? {
+            0 => {
+                reader.align();
+                let len = reader.read_bits(16)? as u16;
+                if reader.read_bits(16)? as u16 != !len {
+                    return Err(corrupt("invalid stored block length"));
+                }
+                for _ in 0..len {
+                    out.push(reader.read_bits(8)? as u8);
+                }
+            }
+            1 => {
+                let (literals, distances) = fixed_codes();
+                inflate_block(&mut reader, &mut out, &literals, &distances)?;
+            }
+            2 => {
+                let (literals, distances) = dynamic_codes(&mut reader)?;
+                inflate_block(&mut reader, &mut out, &literals, &distances)?;
+            }
+            _ => return Err(corrupt("invalid block type")),
+        }
+        if last {
+            break;
+        }
+    }
+    reader.align();
+    let checksum = [0; 4].map(|_| reader.read_bits(8).unwrap_or(0) as u8);
+    if u32::from_be_bytes(checksum) != adler32(&out) {
+        return Err(corrupt("zlib checksum mismatch"));
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn adler32_test() {
+        assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+    }
+
+    #[test]
+    fn stored_test() {
+        let out = zlib_compress(b"abc", Compression::Stored);
+        assert_eq!(
+            out,
+            vec![0x78, 0x9c, 1, 3, 0, 0xfc, 0xff, b'a', b'b', b'c', 0x02, 0x4d, 0x01, 0x27]
+        );
+    }
+
+    #[test]
+    fn fixed_test() {
+        let data: Vec<u8> = b"abcabcabcabcabcabcabcabcabcabc".repeat(100);
+        let out = zlib_compress(&data, Compression::Fixed);
+        assert!(out.len() < data.len() / 10);
+    }
+
+    #[test]
+    fn decompress_test() {
+        let data: Vec<u8> = b"abcabcabcabcabcabcabcabcabcabc".repeat(100);
+        for compression in [Compression::Stored, Compression::Fixed] {
+            let out = zlib_compress(&data, compression);
+            assert_eq!(zlib_decompress(&out).unwrap(), data);
+        }
+        // zlib output with a dynamic Huffman block
+        let dynamic = [
+            0x78, 0x01, 0x05, 0xc1, 0x01, 0x01, 0x00, 0x00, 0x08, 0xc3, 0xa0, 0xac, 0xec, 0xf6,
+            0xcf, 0x20, 0x00, 0x00, 0x40, 0x55, 0x55, 0xb5, 0xed, 0x00, 0x00, 0xa0, 0xaa, 0xaa,
+            0xda, 0x76, 0x0f, 0xb1, 0xca, 0x1a, 0xaf,
+        ];
+        let expected = b"aaaaaaaaaaaaaaaaaaaabbbbbbbbbbbcccd".repeat(2);
+        assert_eq!(zlib_decompress(&dynamic).unwrap(), expected);
+        let mut corrupted = zlib_compress(&data, Compression::Fixed);
+        let last = corrupted.len() - 1;
+        corrupted[last] ^= 1;
+        assert!(zlib_decompress(&corrupted).is_err());
+    }
+}
@@ -0,0 +1,277 @@
+//! Reader and writer for the Radiance RGBE (`.hdr`) format.
+use crate::render::FrameBuffer;
+use crate::vec3::Vec3;
+use std::io::{self, BufRead, Read, Write};
+
+/// Scanlines outside this range cannot be run length encoded
+const RLE_WIDTHS: std::ops::RangeInclusive<usize> = 8..=0x7fff;
+const MIN_RUN: usize = 4;
+/// Most pixels reserved before any of them is read
+const MAX_RESERVED_PIXELS: usize = 1 << 20;
+/// The exponent stored as 255 in the fourth byte
+const MAX_EXPONENT: i32 = 127;
+
+/// Encode a color with a shared exponent, the mantissas keeping 8 bits of precision
+pub fn to_rgbe(c: Vec3) -> [u8; 4] {
+    let v = c.x.max(c.y).max(c.z);
+    if v.is_nan() || v <= 1e-32 {
+        return [0, 0, 0, 0];
+    }
+    // v = m * 2^e with m in [0.5, 1), too bright values saturating at the
+    // largest exponent the byte can hold
+    let mut e = MAX_EXPONENT;
+    if v.is_finite() {
+        e = v.log2().floor() as i32 + 1;
+        if v / 2f64.powi(e) >= 1.0 {
+            e += 1;
+        }
+        e = e.min(MAX_EXPONENT);
+    }
+    let scale = 256.0 / 2f64.powi(e);
+    let channel = |x: f64| (x.max(0.0) * scale).min(255.0) as u8;
+    [channel(c.x), channel(c.y), channel(c.z), (e + 128) as u8]
+}
+
+/// Run length encode one channel of a scanline
+fn encode_channel(data: &[u8], out: &mut Vec<u8>) {
+    let mut cur = 0;
+    while cur < data.len() {
+        // look for the next run long enough to be worth encoding
+        let mut run_start = cur;
+        let mut run_len = 0;
+        let mut previous_run_len = 0;
+        while run_len < MIN_RUN && run_start < data.len() {
+            run_start += run_len;
+            previous_run_len = run_len;
+            run_len = 1;
+            while run_start + run_len < data.len()
+                && run_len < 127
+                && data[run_start] == data[run_start + run_len]
+            {
+                run_len += 1;
+            }
+        }
+        // a short run right before the long one is still cheaper as a run
+        if previous_run_len > 1 && previous_run_len == run_start - cur {
+            out.push(128 + previous_run_len as u8);
+            out.push(data[cur]);
+            cur = run_start;
+        }
+        while cur < run_start {
+            let count = (run_start - cur).min(128);
+            out.push(count as u8);
+            out.extend_from_slice(&data[cur..cur + count]);
+            cur += count;
+        }
+        if run_len >= MIN_RUN {
+            out.push(128 + run_len as u8);
+            out.push(data[run_start]);
+            cur += run_len;
+        }
+    }
+}
+
+/// Encode the linear radiance of the image as a Radiance `.hdr` file
+pub fn write_hdr<W: Write>(frame: &FrameBuffer, mut out: W) -> io::Result<()> {
+    write!(
+        out,
+        "#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y {} +X {}\n",
+        frame.height, frame.width
+    )?;
+    let mut channels = vec![vec![0u8; frame.width]; 4];
+    let mut encoded = Vec::new();
+    for row in frame.pixels().chunks(frame.width) {
+        encoded.clear();
+        if RLE_WIDTHS.contains(&frame.width) {
+            for (x, pixel) in row.iter().enumerate() {
+                for (channel, byte) in channels.iter_mut().zip(&to_rgbe(*pixel)) {
+                    channel[x] = *byte;
+                }
+            }
+            encoded.extend_from_slice(&[2, 2, (frame.width >> 8) as u8, frame.width as u8]);
+            for channel in &channels {
+                encode_channel(channel, &mut encoded);
+            }
+        } else {
+            for pixel in row {
+                encoded.extend_from_slice(&to_rgbe(*pixel));
+            }
+        }
+        out.write_all(&encoded)?;
+    }
+    out.flush()
+}
+
+/// Decode a color with a shared exponent, the inverse of [`to_rgbe`]
+pub fn from_rgbe(rgbe: [u8; 4]) -> Vec3 {
+    if rgbe[3] == 0 {
+        return Vec3::zeros();
+    }
+    // the mantissas are read at the center of their interval
+    let scale = 2f64.powi(rgbe[3] as i32 - 136);
+    let channel = |m: u8| (m as f64 + 0.5) * scale;
+    Vec3::new(channel(rgbe[0]), channel(rgbe[1]), channel(rgbe[2]))
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+/// Run length decode one channel of a scanline
+fn decode_channel<R: Read>(input: &mut R, channel: &mut [u8]) -> io::Result<()> {
+    let mut x = 0;
+    let mut byte = [0u8; 1];
+    while x < channel.len() {
+        input.read_exact(&mut byte)?;
+        let count = byte[0] as usize;
+        if count > 128 {
+            let run = count - 128;
+            if x + run > channel.len() {
+                return Err(invalid("run past the end of a scanline"));
+            }
+            input.read_exact(&mut byte)?;
+            channel[x..x + run].fill(byte[0]);
+            x += run;
+        } else {
+            if count == 0 || x + count > channel.len() {
+                return Err(invalid("invalid run in a scanline"));
+            }
+            input.read_exact(&mut channel[x..x + count])?;
+            x += count;
+        }
+    }
+    Ok(())
+}
+
+/// Decode a Radiance `.hdr` file whose rows go from top to bottom, flat or
+/// run length encoded
+pub fn read_hdr<R: BufRead>(mut input: R) -> io::Result<FrameBuffer> {
+    let mut line = String::new();
+    input.read_line(&mut line)?;
+    if !line.starts_with("#?") {
+        return Err(invalid("not a Radiance file"));
+    }
+    // the header ends with an empty line
+    loop {
+        line.clear();
+        if input.read_line(&mut line)? == 0 {
+            return Err(invalid("missing resolution"));
+        }
+        let field = line.trim_end();
+        if field.is_empty() {
+            break;
+        }
+        if let Some(format) = field.strip_prefix("FORMAT=") {
+            if format != "32-bit_rle_rgbe" {
+                return Err(invalid("only RGBE pixels are supported"));
+            }
+        }
+    }
+    line.clear();
+    input.read_line(&mut line)?;
+    let (height, width): (usize, usize) = match line.split_whitespace().collect::<Vec<_>>()[..] {
+        ["-Y", height, "+X", width] => match (height.parse(), width.parse()) {
+            (Ok(height), Ok(width)) => (height, width),
+            _ => return Err(invalid("invalid resolution")),
+        },
+        _ => return Err(invalid("only '-Y height +X width' images are supported")),
+    };
+    let count = match width.checked_mul(height) {
+        Some(count) if count > 0 => count,
+        _ => return Err(invalid("invalid resolution")),
+    };
+
+    // the header alone does not prove the pixels are there, the reservation
+    // is capped and the buffer grows as they are read
+    let mut pixels = Vec::with_capacity(count.min(MAX_RESERVED_PIXELS));
+    let rle_width = if RLE_WIDTHS.contains(&width) {
+        width
+    } else {
+        0
+    };
+    let mut channels = [(); 4].map(|_| vec![0u8; rle_width]);
+    for _ in 0..height {
+        let mut start = [0u8; 4];
+        input.read_exact(&mut start)?;
+        if RLE_WIDTHS.contains(&width) && start[0] == 2 && start[1] == 2 && start[2] < 128 {
+            if ((start[2] as usize) << 8 | start[3] as usize) != width {
+                return Err(invalid("scanline width mismatch"));
+            }
+            for channel in channels.iter_mut() {
+                decode_channel(&mut input, channel)?;
+            }
+            pixels.extend((0..width).map(|x| from_rgbe(channels.each_ref().map(|c| c[x]))));
+        } else {
+            pixels.push(from_rgbe(start));
+            for _ in 1..width {
+                let mut rgbe = [0u8; 4];
+                input.read_exact(&mut rgbe)?;
+                pixels.push(from_rgbe(rgbe));
+            }
+        }
+    }
+    Ok(FrameBuffer::new(width, height, pixels))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn to_rgbe_test() {
+        assert_eq!(to_rgbe(Vec3::all(1.0)), [128, 128, 128, 129]);
+        assert_eq!(to_rgbe(Vec3::new(0.5, 0.25, 0.0)), [128, 64, 0, 128]);
+        assert_eq!(to_rgbe(Vec3::zeros()), [0, 0, 0, 0]);
+        assert_eq!(
+            to_rgbe(Vec3::new(f64::INFINITY, 1.0, 0.0)),
+            [255, 0, 0, 255]
+        );
+        assert_eq!(to_rgbe(Vec3::all(1e300)), [255, 255, 255, 255]);
+        assert_eq!(
+            to_rgbe(Vec3::new(1e300, 2f64.powi(126), 0.0)),
+            [255, 128, 0, 255]
+        );
+    }
+
+    #[test]
+    fn encode_channel_test() {
+        let mut out = Vec::new();
+        encode_channel(&[1, 2, 3, 7, 7, 7, 7, 7, 9, 9], &mut out);
+        assert_eq!(out, vec![3, 1, 2, 3, 133, 7, 130, 9]);
+    }
+
+    #[test]
+    fn read_hdr_test() {
+        // wide enough to be run length encoded, and too narrow
+        for width in [3, 20] {
+            let pixels = (0..width * 2)
+                .map(|i| Vec3::new(i as f64, 0.5, if i < 10 { 0.0 } else { 100.0 }))
+                .collect();
+            let frame = FrameBuffer::new(width, 2, pixels);
+            let mut bytes = Vec::new();
+            write_hdr(&frame, &mut bytes).unwrap();
+            let read = read_hdr(&bytes[..]).unwrap();
+            assert_eq!((read.width, read.height), (width, 2));
+            for (a, b) in read.pixels().iter().zip(frame.pixels()) {
+                // 8 bit mantissas relative to the largest channel
+                let tolerance = a.x.max(a.y).max(a.z) / 128.0;
+                assert!((*a - *b).length() <= tolerance, "{:?} {:?}", a, b);
+            }
+        }
+        assert!(read_hdr(&b"P6\n"[..]).is_err());
+        for resolution in [
+            "-Y 0 +X 0",
+            "-Y 0 +X 4",
+            "-Y 4 +X 0",
+            "-Y 100000000000 +X 100000000000",
+        ] {
+            let header = format!("#?RADIANCE\n\n{}\n", resolution);
+            let error = read_hdr(header.as_bytes()).err().unwrap();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        }
+        // too large to allocate, and cut short
+        let header = "#?RADIANCE\n\n-Y 100000 +X 100000\n";
+        let error = read_hdr(header.as_bytes()).err().unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+    }
+}
@@ -0,0 +1,132 @@
+//! Saving rendered frames to disk, and loading images.
+//!
+//! The format is normally picked from the extension of the output file:
+//!
+//! | extension | format                            |
+//! |-----------|-----------------------------------|
+//! | `.ppm`    | binary PPM (`P6`)                 |
+//! | `.pam`    | PAM (`P7`)                        |
+//! | `.png`    | PNG, deflate compressed           |
+//! | `.hdr`    | Radiance RGBE, run length encoded |
+//! | `.pfm`    | Portable Float Map                |
+//!
+//! PPM, PAM and PNG hold gamma corrected 8 bit colors while Radiance and PFM
+//! files keep the linear radiance computed by the renderer, highlights
+//! included. The plain text PPM (`P3`) and uncompressed PNG flavours can only be
+//! selected explicitly through [`ImageFormat`].
+//!
+//! Radiance, PFM and PNG files can also be read back with [`load`], for
+//! instance to light scenes with environment maps or to texture surfaces. PNG
+//! colors are taken to be sRGB and converted to linear values.
+mod deflate;
+mod hdr;
+mod pfm;
+mod png;
+mod ppm;
+
+pub use deflate::Compression;
+pub use hdr::{read_hdr, write_hdr};
+pub use pfm::{read_pfm, write_pfm};
+pub use png::{read_png, write_png};
+pub use ppm::{write_pam, write_ppm, write_ppm_ascii};
+
+use crate::render::FrameBuffer;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Write};
+use std::path::Path;
+
+/// The file formats a frame can be written as
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ImageFormat {
+    Ppm,
+    PpmAscii,
+    Pam,
+    Png(Compression),
+    Hdr,
+    Pfm,
+}
+
+impl ImageFormat {
+    /// Names accepted by [`ImageFormat::from_name`]
+    pub const NAMES: [&'static str; 7] =
+        ["ppm", "ppm-ascii", "pam", "png", "png-stored", "hdr", "pfm"];
+
+    pub fn from_name(name: &str) -> Option<ImageFormat> {
+        match name {
+            "ppm" => Some(ImageFormat::Ppm),
+            "ppm-ascii" => Some(ImageFormat::PpmAscii),
+            "pam" => Some(ImageFormat::Pam),
+            "png" => Some(ImageFormat::Png(Compression::Fixed)),
+            "png-stored" => Some(ImageFormat::Png(Compression::Stored)),
+            "hdr" => Some(ImageFormat::Hdr),
+            "pfm" => Some(ImageFormat::Pfm),
+            _ => None,
+        }
+    }
+
+    /// Guess the format from the extension of `path`
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Option<ImageFormat> {
+        let extension = path.as_ref().extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "ppm" => Some(ImageFormat::Ppm),
+            "pam" => Some(ImageFormat::Pam),
+            "png" => Some(ImageFormat::Png(Compression::Fixed)),
+            "hdr" => Some(ImageFormat::Hdr),
+            "pfm" => Some(ImageFormat::Pfm),
+            _ => None,
+        }
+    }
+}
+
+/// Encode `frame` in the given format
+pub fn write<W: Write>(frame: &FrameBuffer, format: ImageFormat, out: W) -> io::Result<()> {
+    match format {
+        ImageFormat::Ppm => write_ppm(frame, out),
+        ImageFormat::PpmAscii => write_ppm_ascii(frame, out),
+        ImageFormat::Pam => write_pam(frame, out),
+        ImageFormat::Png(compression) => write_png(frame, compression, out),
+        ImageFormat::Hdr => write_hdr(frame, out),
+        ImageFormat::Pfm => write_pfm(frame, out),
+    }
+}
+
+/// Save `frame` to `path` in the given format
+pub fn save_as<P: AsRef<Path>>(
+    frame: &FrameBuffer,
+    path: P,
+    format: ImageFormat,
+) -> io::Result<()> {
+    let file = File::create(path)?;
+    write(frame, format, BufWriter::new(file))
+}
+
+/// Save `frame` to `path`, the format being chosen by the extension of the file
+pub fn save<P: AsRef<Path>>(frame: &FrameBuffer, path: P) -> io::Result<()> {
+    let format = ImageFormat::from_path(&path).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("unsupported image extension in {}", path.as_ref().display()),
+        )
+    })?;
+    save_as(frame, path, format)
+}
+
+/// Read the `.hdr`, `.pfm` or `.png` image at `path`, the format being chosen
+/// by the extension of the file
+pub fn load<P: AsRef<Path>>(path: P) -> io::Result<FrameBuffer> {
+    let read = match ImageFormat::from_path(&path) {
+        Some(ImageFormat::Hdr) => read_hdr,
+        Some(ImageFormat::Pfm) => read_pfm,
+        Some(ImageFormat::Png(_)) => |input| read_png(input),
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "cannot read {}, expected a .hdr, .pfm or .png image",
+                    path.as_ref().display()
+                ),
+            ))
+        }
+    };
+    read(BufReader::new(File::open(&path)?))
+}
@@ -0,0 +1,142 @@
+//! Reader and writer for the Portable Float Map (`.pfm`) format.
+use crate::render::FrameBuffer;
+use crate::vec3::Vec3;
+use std::io::{self, BufRead, Read, Write};
+
+/// Encode the linear radiance of the image as a color PFM file.
+///
+/// Values are little endian 32 bit floats and, as the format requires, the
+/// rows go from the bottom of the image to its top.
+pub fn write_pfm<W: Write>(frame: &FrameBuffer, mut out: W) -> io::Result<()> {
+    // a negative scale marks little endian data
+    write!(out, "PF\n{} {}\n-1.0\n", frame.width, frame.height)?;
+    let mut bytes = Vec::with_capacity(frame.width * 12);
+    for row in frame.pixels().chunks(frame.width).rev() {
+        bytes.clear();
+        for pixel in row {
+            for c in &[pixel.x, pixel.y, pixel.z] {
+                bytes.extend_from_slice(&(*c as f32).to_le_bytes());
+            }
+        }
+        out.write_all(&bytes)?;
+    }
+    out.flush()
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+/// The next whitespace separated word of the header
+fn header_token<R: BufRead>(input: &mut R) -> io::Result<String> {
+    let mut token = String::new();
+    let mut byte = [0u8; 1];
+    loop {
+        input.read_exact(&mut byte)?;
+        if byte[0].is_ascii_whitespace() {
+            if !token.is_empty() {
+                return Ok(token);
+            }
+        } else {
+            token.push(byte[0] as char);
+        }
+    }
+}
+
+/// Decode a color (`PF`) or grayscale (`Pf`) PFM file of either byte order
+pub fn read_pfm<R: BufRead>(mut input: R) -> io::Result<FrameBuffer> {
+    let channels = match header_token(&mut input)?.as_str() {
+        "PF" => 3,
+        "Pf" => 1,
+        _ => return Err(invalid("not a PFM file")),
+    };
+    let mut number = || -> io::Result<f64> {
+        header_token(&mut input)?
+            .parse()
+            .map_err(|_| invalid("invalid PFM header"))
+    };
+    let (width, height, scale) = (number()?, number()?, number()?);
+    if width < 1.0 || height < 1.0 || width.fract() != 0.0 || height.fract() != 0.0 {
+        return Err(invalid("invalid PFM size"));
+    }
+    let (width, height) = (width as usize, height as usize);
+    let row_length = width
+        .checked_mul(channels * 4)
+        .filter(|length| length.checked_mul(height).is_some())
+        .ok_or_else(|| invalid("invalid PFM size"))?;
+
+    // nothing is allocated from the header alone, the rows grow as they are
+    // read in case the file is cut short
+    let mut row = Vec::new();
+    let mut rows = Vec::new();
+    for _ in 0..height {
+        row.clear();
+        (&mut input).take(row_length as u64).read_to_end(&mut row)?;
+        if row.len() < row_length {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "truncated PFM data",
+            ));
+        }
+        let values: Vec<f64> = row
+            .chunks(4)
+            .map(|b| {
+                let bytes = [b[0], b[1], b[2], b[3]];
+                let value = if scale < 0.0 {
+                    f32::from_le_bytes(bytes)
+                } else {
+                    f32::from_be_bytes(bytes)
+                };
+                value as f64
+            })
+            .collect();
+        rows.push(
+            values
+                .chunks(channels)
+                .map(|c| match c {
+                    [r, g, b] => Vec3::new(*r, *g, *b),
+                    _ => Vec3::all(c[0]),
+                })
+                .collect::<Vec<_>>(),
+        );
+    }
+    // the file starts with the bottom row
+    let pixels = rows.into_iter().rev().flatten().collect();
+    Ok(FrameBuffer::new(width, height, pixels))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn read_pfm_test() {
+        let pixels = vec![
+            Vec3::new(1.0, 2.0, 3.0),
+            Vec3::new(0.5, 0.25, 0.0),
+            Vec3::new(-1.0, 1e6, 7.0),
+            Vec3::zeros(),
+        ];
+        let frame = FrameBuffer::new(2, 2, pixels);
+        let mut bytes = Vec::new();
+        write_pfm(&frame, &mut bytes).unwrap();
+        let read = read_pfm(&bytes[..]).unwrap();
+        assert_eq!(read.pixels(), frame.pixels());
+
+        // big endian grayscale
+        let mut bytes = b"Pf\n1 2\n1.0\n".to_vec();
+        bytes.extend_from_slice(&2.0f32.to_be_bytes());
+        bytes.extend_from_slice(&4.0f32.to_be_bytes());
+        let read = read_pfm(&bytes[..]).unwrap();
+        assert_eq!(read.pixels(), &[Vec3::all(4.0), Vec3::all(2.0)]);
+
+        let error = |header: &[u8]| read_pfm(header).err().unwrap().kind();
+        assert_eq!(error(b"PF\n0 1 -1\n"), io::ErrorKind::InvalidData);
+        assert_eq!(error(b"PF\n1e30 1e30 -1\n"), io::ErrorKind::InvalidData);
+        // too large to allocate, and cut short
+        assert_eq!(
+            error(b"PF\n1 1000000000000000 -1\n"),
+            io::ErrorKind::UnexpectedEof
+        );
+    }
+}
@@ -0,0 +1,326 @@
+//! A self-contained PNG encoder producing 8 bit RGB images, and a decoder for
+//! the common kinds of PNG files.
+use super::deflate::{self, Compression};
+use crate::render::FrameBuffer;
+use crate::vec3::Vec3;
+use std::io::{self, Read, Write};
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+const BYTES_PER_PIXEL: usize = 3;
+
+fn crc_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    for (n, entry) in table.iter_mut().enumerate() {
+        let mut c = n as u32;
+        for _ in 0..8 {
+            c = if c & 1 == 1 {
+                0xedb8_8320 ^ (c >> 1)
+            } else {
+                c >> 1
+            };
+        }
+        *entry = c;
+    }
+    table
+}
+
+/// CRC-32 as used by PNG chunks
+pub fn crc32(bytes: &[u8]) -> u32 {
+    let table = crc_table();
+    !bytes.iter().fold(!0u32, |c, &b| {
+        table[((c ^ b as u32) & 0xff) as usize] ^ (c >> 8)
+    })
+}
+
+fn write_chunk<W: Write>(out: &mut W, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    out.write_all(&(data.len() as u32).to_be_bytes())?;
+    let mut crc_input = Vec::with_capacity(data.len() + 4);
+    crc_input.extend_from_slice(kind);
+    crc_input.extend_from_slice(data);
+    out.write_all(&crc_input)?;
+    out.write_all(&crc32(&crc_input).to_be_bytes())
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let pa = (p - a as i16).abs();
+    let pb = (p - b as i16).abs();
+    let pc = (p - c as i16).abs();
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+/// Apply filter `kind` to `row`, `prev` being the unfiltered row above it
+fn filter_row(kind: u8, row: &[u8], prev: &[u8], out: &mut Vec<u8>) {
+    out.push(kind);
+    for i in 0..row.len() {
+        let a = if i >= BYTES_PER_PIXEL {
+            row[i - BYTES_PER_PIXEL]
+        } else {
+            0
+        };
+        let b = prev[i];
+        let c = if i >= BYTES_PER_PIXEL {
+            prev[i - BYTES_PER_PIXEL]
+        } else {
+            0
+        };
+        let predicted = match kind {
+            0 => 0,
+            1 => a,
+            2 => b,
+            3 => ((a as u16 + b as u16) / 2) as u8,
+            _ => paeth(a, b, c),
+        };
+        out.push(row[i].wrapping_sub(predicted));
+    }
+}
+
+/// Filter every scanline, picking for each the filter with the smallest sum of
+/// absolute differences as suggested by the PNG specification
+fn filter(rgb: &[u8], width: usize) -> Vec<u8> {
+    let stride = width * BYTES_PER_PIXEL;
+    let mut filtered = Vec::with_capacity(rgb.len() + rgb.len() / stride.max(1));
+    let zeros = vec![0u8; stride];
+    let mut candidate = Vec::with_capacity(stride + 1);
+    for (y, row) in rgb.chunks(stride).enumerate() {
+        let prev = if y == 0 {
+            &zeros[..]
+        } else {
+            &rgb[(y - 1) * stride..y * stride]
+        };
+        let best = (0..5)
+            .min_by_key(|&kind| {
+                candidate.clear();
+                filter_row(kind, row, prev, &mut candidate);
+                candidate[1..]
+                    .iter()
+                    .map(|&v| (v as i8).unsigned_abs() as u64)
+                    .sum::<u64>()
+            })
+            .unwrap();
+        filter_row(best, row, prev, &mut filtered);
+    }
+    filtered
+}
+
+/// Encode the image as a PNG file
+pub fn write_png<W: Write>(
+    frame: &FrameBuffer,
+    compression: Compression,
+    mut out: W,
+) -> io::Result<()> {
+    out.write_all(&SIGNATURE)?;
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&(frame.width as u32).to_be_bytes());
+    header.extend_from_slice(&(frame.height as u32).to_be_bytes());
+    // 8 bits per channel, truecolor, deflate, adaptive filtering, no interlace
+    header.extend_from_slice(&[8, 2, 0, 0, 0]);
+    write_chunk(&mut out, b"IHDR", &header)?;
+
+    let data = match compression {
+        Compression::Stored => {
+            let stride = frame.width * BYTES_PER_PIXEL;
+            let rgb = frame.to_rgb8();
+            let mut raw = Vec::with_capacity(rgb.len() + frame.height);
+            for row in rgb.chunks(stride) {
+                raw.push(0);
+                raw.extend_from_slice(row);
+            }
+            raw
+        }
+        Compression::Fixed => filter(&frame.to_rgb8(), frame.width),
+    };
+    write_chunk(
+        &mut out,
+        b"IDAT",
+        &deflate::zlib_compress(&data, compression),
+    )?;
+    write_chunk(&mut out, b"IEND", &[])?;
+    out.flush()
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+/// Undo filter `kind` in place, `prev` being the reconstructed row above
+fn unfilter_row(kind: u8, row: &mut [u8], prev: &[u8], bpp: usize) -> io::Result<()> {
+    for i in 0..row.len() {
+        let a = if i >= bpp { row[i - bpp] } else { 0 };
+        let b = prev[i];
+        let c = if i >= bpp { prev[i - bpp] } else { 0 };
+        let predicted = match kind {
+            0 => 0,
+            1 => a,
+            2 => b,
+            3 => ((a as u16 + b as u16) / 2) as u8,
+            4 => paeth(a, b, c),
+            _ => return Err(invalid("unknown PNG filter")),
+        };
+        row[i] = row[i].wrapping_add(predicted);
+    }
+    Ok(())
+}
+
+/// Decode an 8 bit sRGB value to linear
+fn srgb_to_linear(value: u8) -> f64 {
+    let c = value as f64 / 255.0;
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Decode a non interlaced PNG file of 8 or 16 bits per channel, in
+/// grayscale, truecolor or with a palette, alpha being ignored.
+///
+/// Colors are taken to be sRGB encoded and converted to linear values.
+pub fn read_png<R: Read>(mut input: R) -> io::Result<FrameBuffer> {
+    let mut signature = [0u8; 8];
+    input.read_exact(&mut signature)?;
+    if signature != SIGNATURE {
+        return Err(invalid("not a PNG file"));
+    }
+
+    let mut header = None;
+    let mut palette = Vec::new();
+    let mut compressed = Vec::new();
+    loop {
+        let mut length = [0u8; 4];
+        input.read_exact(&mut length)?;
+        let mut chunk = vec![0u8; u32::from_be_bytes(length) as usize + 4];
+        input.read_exact(&mut chunk)?;
+        let mut crc = [0u8; 4];
+        input.read_exact(&mut crc)?;
+        if crc32(&chunk) != u32::from_be_bytes(crc) {
+            return Err(invalid("PNG chunk checksum mismatch"));
+        }
+        let (kind, data) = chunk.split_at(4);
+        match kind {
+            b"IHDR" if data.len() == 13 => header = Some(data.to_vec()),
+            b"PLTE" => palette = data.to_vec(),
+            b"IDAT" => compressed.extend_from_slice(data),
+            b"IEND" => break,
+            _ => {}
+        }
+    }
+
+    let header = header.ok_or_else(|| invalid("missing PNG header"))?;
+    let width = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize;
+    let height = u32::from_be_bytes([header[4], header[5], header[6], header[7]]) as usize;
+    let (depth, color_type, interlace) = (header[8], header[9], header[12]);
+    let channels = match color_type {
+        0 => 1,
+        2 => 3,
+        3 => 1,
+        4 => 2,
+        6 => 4,
+        _ => return Err(invalid("unknown PNG color type")),
+    };
+    if width == 0 || height == 0 {
+        return Err(invalid("invalid PNG size"));
+    }
+    if !(depth == 8 || depth == 16 && color_type != 3) {
+        return Err(invalid("only 8 and 16 bit PNG images are supported"));
+    }
+    if interlace != 0 {
+        return Err(invalid("interlaced PNG images are not supported"));
+    }
+
+    let bytes = depth as usize / 8;
+    let bpp = channels * bytes;
+    let stride = width * bpp;
+    let mut data = deflate::zlib_decompress(&compressed)?;
+    if data.len() < (stride + 1) * height {
+        return Err(invalid("truncated PNG image data"));
+    }
+    let mut prev = vec![0u8; stride];
+    let mut pixels = Vec::with_capacity(width * height);
+    for line in data.chunks_mut(stride + 1).take(height) {
+        let (kind, row) = line.split_at_mut(1);
+        unfilter_row(kind[0], row, &prev, bpp)?;
+        for pixel in row.chunks(bpp) {
+            // the most significant byte is enough for colors
+            let sample = |i: usize| pixel[i * bytes];
+            let color = match color_type {
+                0 | 4 => Vec3::all(srgb_to_linear(sample(0))),
+                3 => {
+                    let i = 3 * sample(0) as usize;
+                    let entry = palette
+                        .get(i..i + 3)
+                        .ok_or_else(|| invalid("PNG palette index out of range"))?;
+                    Vec3::new(
+                        srgb_to_linear(entry[0]),
+                        srgb_to_linear(entry[1]),
+                        srgb_to_linear(entry[2]),
+                    )
+                }
+                _ => Vec3::new(
+                    srgb_to_linear(sample(0)),
+                    srgb_to_linear(sample(1)),
+                    srgb_to_linear(sample(2)),
+                ),
+            };
+            pixels.push(color);
+        }
+        prev.copy_from_slice(row);
+    }
+    Ok(FrameBuffer::new(width, height, pixels))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn crc32_test() {
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+        assert_eq!(crc32(b"IEND"), 0xae42_6082);
+    }
+
+    #[test]
+    fn paeth_test() {
+        assert_eq!(paeth(10, 20, 15), 15);
+        assert_eq!(paeth(10, 20, 30), 10);
+    }
+
+    #[test]
+    fn srgb_to_linear_test() {
+        assert_eq!(srgb_to_linear(0), 0.0);
+        assert_eq!(srgb_to_linear(255), 1.0);
+        assert!((srgb_to_linear(188) - 0.5).abs() < 0.005);
+    }
+
+    #[test]
+    fn read_png_test() {
+        let pixels = (0..12)
+            .map(|i| Vec3::new(i as f64 / 12.0, 0.25, 1.0 - i as f64 / 12.0))
+            .collect();
+        let frame = FrameBuffer::new(4, 3, pixels);
+        for compression in [Compression::Stored, Compression::Fixed] {
+            let mut bytes = Vec::new();
+            write_png(&frame, compression, &mut bytes).unwrap();
+            let read = read_png(&bytes[..]).unwrap();
+            assert_eq!((read.width, read.height), (4, 3));
+            let expected = frame.to_rgb8();
+            for (a, b) in read.pixels().iter().zip(expected.chunks(3)) {
+                assert_eq!(a.x, srgb_to_linear(b[0]));
+                assert_eq!(a.z, srgb_to_linear(b[2]));
+            }
+        }
+
+        let mut bytes = Vec::new();
+        write_png(&frame, Compression::Fixed, &mut bytes).unwrap();
+        bytes[20] ^= 1;
+        assert!(read_png(&bytes[..]).is_err());
+        assert!(read_png(&b"GIF89a"[..]).is_err());
+    }
+}
@@ -0,0 +1,31 @@
+//! Writers for the Netpbm family: binary and ASCII PPM as well as PAM.
+use crate::render::FrameBuffer;
+use std::io::{self, Write};
+
+/// Encode the image as a binary PPM (`P6`) file
+pub fn write_ppm<W: Write>(frame: &FrameBuffer, mut out: W) -> io::Result<()> {
+    write!(out, "P6\n{} {}\n255\n", frame.width, frame.height)?;
+    out.write_all(&frame.to_rgb8())?;
+    out.flush()
+}
+
+/// Encode the image as a plain text PPM (`P3`) file, one row per line
+pub fn write_ppm_ascii<W: Write>(frame: &FrameBuffer, mut out: W) -> io::Result<()> {
+    write!(out, "P3\n{} {}\n255\n", frame.width, frame.height)?;
+    for row in frame.to_rgb8().chunks(frame.width * 3) {
+        let line: Vec<String> = row.iter().map(|c| c.to_string()).collect();
+        writeln!(out, "{}", line.join(" "))?;
+    }
+    out.flush()
+}
+
+/// Encode the image as a PAM (`P7`) file with the `RGB` tuple type
+pub fn write_pam<W: Write>(frame: &FrameBuffer, mut out: W) -> io::Result<()> {
+    write!(
+        out,
+        "P7\nWIDTH {}\nHEIGHT {}\nDEPTH 3\nMAXVAL 255\nTUPLTYPE RGB\nENDHDR\n",
+        frame.width, frame.height
+    )?;
+    out.write_all(&frame.to_rgb8())?;
+    out.flush()
+}
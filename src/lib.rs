@@ -0,0 +1,44 @@
+//! A small path tracer following "Ray Tracing in One Weekend".
+//!
+//! The crate exposes everything needed to build a scene and render it:
+//!
+//! ```no_run
+//! use rustracer::{render, scenes};
+//!
+//! let scene = scenes::load("simple").unwrap();
+//! let settings = scene.settings;
+//! let camera = scene.camera.unwrap_or_default().build(settings.aspect_ratio());
+//! let frame = render(&scene.world, &camera, &settings);
+//! assert_eq!(frame.pixels().len(), settings.width * settings.height);
+//! ```
+
+pub mod aabb;
+pub mod bvh;
+pub mod camera;
+pub mod environment;
+pub mod hittable;
+pub mod image;
+pub mod loaders;
+pub mod mat4;
+pub mod materials;
+pub mod motion;
+pub mod noise;
+pub mod ray;
+pub mod render;
+pub mod sampler;
+pub mod scenes;
+pub mod task;
+pub mod textures;
+pub mod transform;
+pub mod util;
+pub mod vec3;
+
+pub use camera::{Camera, CameraSettings};
+pub use hittable::{HitRecord, Hittable, Quad, Sphere, Transformed, Triangle, TriangleMesh, World};
+pub use mat4::Mat4;
+pub use materials::Materialable;
+pub use motion::{Keyframes, Placement};
+pub use ray::Ray;
+pub use render::{render, FrameBuffer, RenderSettings, Renderer};
+pub use transform::Transform;
+pub use vec3::Vec3;
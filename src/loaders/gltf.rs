@@ -0,0 +1,1012 @@
+//! glTF 2.0 importer, for both `.gltf` files and `.glb` binaries.
+//!
+//! Buffers may be embedded as base64 data URIs, stored next to the asset or,
+//! for `.glb` files, in the binary chunk. The node hierarchy of the default
+//! scene is walked and every mesh primitive made of triangles becomes a
+//! [`TriangleMesh`], shared by all the nodes placing the mesh. The first perspective camera found
+//! becomes the camera of the scene. Metallic-roughness materials are mapped
+//! onto the materials of the crate, with their base color textures when these
+//! are PNG images; other textures are ignored.
+use super::json::Json;
+use super::LoadError;
+use crate::camera::CameraSettings;
+use crate::hittable::{Transformed, TriangleMesh, World};
+use crate::image::read_png;
+use crate::mat4::Mat4;
+use crate::materials::{Dielectrics, DiffuseLight, Lambertian, Materialable, Metal};
+use crate::textures::{ImageTexture, Texture};
+use crate::transform::Transform;
+use crate::vec3::Vec3;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::path::Path;
+use std::sync::Arc;
+
+/// A scene imported from a glTF asset
+pub struct GltfScene {
+    pub world: World,
+    /// the first perspective camera of the scene, if any
+    pub camera: Option<CameraSettings>,
+}
+
+/// Decode standard base64, ignoring whitespace
+fn decode_base64(text: &str) -> Option<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(text.len() * 3 / 4);
+    let mut bits = 0u32;
+    let mut count = 0;
+    for c in text.bytes() {
+        let value = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            b'=' => break,
+            b' ' | b'\n' | b'\r' | b'\t' => continue,
+            _ => return None,
+        };
+        bits = bits << 6 | value as u32;
+        count += 6;
+        if count >= 8 {
+            count -= 8;
+            bytes.push((bits >> count) as u8);
+        }
+    }
+    Some(bytes)
+}
+
+/// Decode the `%XX` escapes of a relative URI
+fn decode_uri(uri: &str) -> String {
+    let bytes = uri.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) if bytes[i] == b'%' => {
+                decoded.push(byte);
+                i += 3;
+            }
+            _ => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+const GLB_MAGIC: &[u8; 4] = b"glTF";
+const GLB_JSON_CHUNK: u32 = 0x4e4f_534a;
+const GLB_BIN_CHUNK: u32 = 0x004e_4942;
+/// Largest number of values of an accessor without a buffer view, which are
+/// all zero and not backed by any data
+const MAX_ZERO_VALUES: usize = 1 << 24;
+
+/// Split a `.glb` file into its JSON and binary chunks
+fn split_glb(data: &[u8]) -> Result<(&[u8], Option<&[u8]>), String> {
+    let word = |offset: usize| {
+        data.get(offset..offset + 4)
+            .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .ok_or_else(|| String::from("truncated binary glTF"))
+    };
+    if word(4)? != 2 {
+        return Err(String::from("only version 2 of binary glTF is supported"));
+    }
+    let length = (word(8)? as usize).min(data.len());
+    let mut offset = 12;
+    let mut json = None;
+    let mut bin = None;
+    while offset + 8 <= length {
+        let size = word(offset)? as usize;
+        let kind = word(offset + 4)?;
+        let chunk = data
+            .get(offset + 8..offset + 8 + size)
+            .ok_or_else(|| String::from("truncated binary glTF chunk"))?;
+        match kind {
+            GLB_JSON_CHUNK if json.is_none() => json = Some(chunk),
+            GLB_BIN_CHUNK if bin.is_none() => bin = Some(chunk),
+            _ => {}
+        }
+        offset += 8 + size;
+    }
+    match json {
+        Some(json) => Ok((json, bin)),
+        None => Err(String::from("binary glTF without a JSON chunk")),
+    }
+}
+
+/// The numbers stored by an accessor, `components` per element
+struct Accessor {
+    values: Vec<f64>,
+    components: usize,
+}
+
+impl Accessor {
+    fn vec3(&self, i: usize) -> Vec3 {
+        let v = &self.values[i * self.components..];
+        Vec3::new(v[0], v[1], v[2])
+    }
+}
+
+/// The parameters of a metallic-roughness material the crate can render
+struct PbrMaterial {
+    base_color: Vec3,
+    /// the base color texture multiplied by `base_color`
+    base_color_texture: Option<Arc<dyn Texture>>,
+    alpha: f64,
+    blend: bool,
+    metallic: f64,
+    roughness: f64,
+    transmission: f64,
+    ior: f64,
+    emission: Vec3,
+}
+
+impl PbrMaterial {
+    fn to_material(&self, vertex_colors: bool) -> Box<dyn Materialable> {
+        let c = self.base_color;
+        let e = self.emission;
+        if e.x.max(e.y).max(e.z) > 0.0 {
+            DiffuseLight::new(e.x, e.y, e.z)
+        } else if self.transmission > 0.0 || (self.blend && self.alpha < 1.0) {
+            Dielectrics::new(self.ior)
+        } else if self.metallic >= 0.5 {
+            match &self.base_color_texture {
+                Some(texture) => Metal::textured(Arc::clone(texture), self.roughness),
+                None => Metal::new(c.x, c.y, c.z, self.roughness),
+            }
+        } else if let Some(texture) = &self.base_color_texture {
+            Lambertian::textured(Arc::clone(texture))
+        } else if vertex_colors {
+            Lambertian::vertex_colored(c.x, c.y, c.z)
+        } else {
+            Lambertian::new(c.x, c.y, c.z)
+        }
+    }
+}
+
+/// Everything needed to resolve references while walking the asset
+struct Document<'a> {
+    json: &'a Json,
+    buffers: Vec<Vec<u8>>,
+    path: &'a Path,
+    /// the base color texture of every material, loaded once
+    textures: Vec<Option<Arc<dyn Texture>>>,
+}
+
+impl<'a> Document<'a> {
+    fn error<S: Into<String>>(&self, message: S) -> LoadError {
+        LoadError::invalid(self.path, message)
+    }
+
+    /// The `index`-th entry of the top level array `kind`
+    fn item(&self, kind: &str, index: usize) -> Result<&'a Json, LoadError> {
+        self.json
+            .get(kind)
+            .and_then(Json::as_array)
+            .and_then(|items| items.get(index))
+            .ok_or_else(|| self.error(format!("{}[{}] does not exist", kind, index)))
+    }
+
+    fn index(&self, value: Option<&Json>, what: &str) -> Result<Option<usize>, LoadError> {
+        match value {
+            None => Ok(None),
+            Some(value) => match value.as_usize() {
+                Some(index) => Ok(Some(index)),
+                None => Err(self.error(format!("invalid {}", what))),
+            },
+        }
+    }
+
+    fn accessor(&self, index: usize) -> Result<Accessor, LoadError> {
+        let accessor = self.item("accessors", index)?;
+        let error = |message: &str| self.error(format!("accessors[{}]: {}", index, message));
+        if accessor.get("sparse").is_some() {
+            return Err(error("sparse accessors are not supported"));
+        }
+        let count = accessor
+            .get("count")
+            .and_then(Json::as_usize)
+            .ok_or_else(|| error("missing count"))?;
+        let components = match accessor.get("type").and_then(Json::as_str) {
+            Some("SCALAR") => 1,
+            Some("VEC2") => 2,
+            Some("VEC3") => 3,
+            Some("VEC4") => 4,
+            Some("MAT2") => 4,
+            Some("MAT3") => 9,
+            Some("MAT4") => 16,
+            _ => return Err(error("invalid type")),
+        };
+        let component_type = accessor.get("componentType").and_then(Json::as_usize);
+        let size = match component_type {
+            Some(5120) | Some(5121) => 1,
+            Some(5122) | Some(5123) => 2,
+            Some(5125) | Some(5126) => 4,
+            _ => return Err(error("invalid componentType")),
+        };
+        let normalized = accessor
+            .get("normalized")
+            .and_then(Json::as_bool)
+            .unwrap_or(false);
+
+        let view_index = match self.index(accessor.get("bufferView"), "bufferView")? {
+            Some(view) => view,
+            // without a view every value is zero
+            None => {
+                return match count.checked_mul(components) {
+                    Some(n) if n <= MAX_ZERO_VALUES => Ok(Accessor {
+                        values: vec![0.0; n],
+                        components,
+                    }),
+                    _ => Err(error("too many values without a buffer view")),
+                };
+            }
+        };
+        let view = self.item("bufferViews", view_index)?;
+        let buffer_index = view
+            .get("buffer")
+            .and_then(Json::as_usize)
+            .ok_or_else(|| error("buffer view without a buffer"))?;
+        let buffer = self
+            .buffers
+            .get(buffer_index)
+            .ok_or_else(|| error("buffer view of a missing buffer"))?;
+        let view_offset = view.get("byteOffset").and_then(Json::as_usize).unwrap_or(0);
+        let view_length = view
+            .get("byteLength")
+            .and_then(Json::as_usize)
+            .ok_or_else(|| error("buffer view without a byteLength"))?;
+        let data = view_offset
+            .checked_add(view_length)
+            .and_then(|end| buffer.get(view_offset..end))
+            .ok_or_else(|| error("buffer view out of the buffer"))?;
+        let offset = accessor
+            .get("byteOffset")
+            .and_then(Json::as_usize)
+            .unwrap_or(0);
+        let stride = view
+            .get("byteStride")
+            .and_then(Json::as_usize)
+            .unwrap_or(size * components);
+        if stride < size * components {
+            return Err(error("byteStride shorter than an element"));
+        }
+        // the last element must end within the view
+        let end = match count.checked_sub(1) {
+            Some(last) => last
+                .checked_mul(stride)
+                .and_then(|x| x.checked_add(offset))
+                .and_then(|x| x.checked_add(size * components)),
+            None => Some(offset),
+        };
+        if !matches!(end, Some(end) if end <= data.len()) {
+            return Err(error("accessor out of the buffer view"));
+        }
+
+        let mut values = Vec::with_capacity(count * components);
+        for i in 0..count {
+            for c in 0..components {
+                let start = offset + i * stride + c * size;
+                let b = data
+                    .get(start..start + size)
+                    .ok_or_else(|| error("accessor out of the buffer view"))?;
+                let value = match component_type {
+                    Some(5120) if normalized => (b[0] as i8 as f64 / 127.0).max(-1.0),
+                    Some(5120) => b[0] as i8 as f64,
+                    Some(5121) if normalized => b[0] as f64 / 255.0,
+                    Some(5121) => b[0] as f64,
+                    Some(5122) => {
+                        let x = i16::from_le_bytes([b[0], b[1]]) as f64;
+                        if normalized {
+                            (x / 32767.0).max(-1.0)
+                        } else {
+                            x
+                        }
+                    }
+                    Some(5123) => {
+                        let x = u16::from_le_bytes([b[0], b[1]]) as f64;
+                        if normalized {
+                            x / 65535.0
+                        } else {
+                            x
+                        }
+                    }
+                    Some(5125) => u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64,
+                    _ => f32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64,
+                };
+                values.push(value);
+            }
+        }
+        Ok(Accessor { values, components })
+    }
+
+    fn material(&self, index: Option<usize>) -> Result<PbrMaterial, LoadError> {
+        let mut material = PbrMaterial {
+            base_color: Vec3::all(0.8),
+            base_color_texture: None,
+            alpha: 1.0,
+            blend: false,
+            metallic: 0.0,
+            roughness: 1.0,
+            transmission: 0.0,
+            ior: 1.5,
+            emission: Vec3::zeros(),
+        };
+        let json = match index {
+            Some(index) => self.item("materials", index)?,
+            None => return Ok(material),
+        };
+        let pbr = json.get("pbrMetallicRoughness");
+        let factor = |name: &str| pbr.and_then(|p| p.get(name)).and_then(Json::as_f64);
+        material.base_color = Vec3::all(1.0);
+        if let Some(color) = pbr
+            .and_then(|p| p.get("baseColorFactor"))
+            .and_then(Json::as_f64_array)
+        {
+            if let [r, g, b, a] = color[..] {
+                material.base_color = Vec3::new(r, g, b);
+                material.alpha = a;
+            }
+        }
+        material.base_color_texture = index.and_then(|i| self.textures.get(i)).cloned().flatten();
+        material.metallic = factor("metallicFactor").unwrap_or(1.0);
+        material.roughness = factor("roughnessFactor").unwrap_or(1.0);
+        material.blend = json.get("alphaMode").and_then(Json::as_str) == Some("BLEND");
+        let extension = |name: &str, key: &str| {
+            json.get("extensions")
+                .and_then(|e| e.get(name))
+                .and_then(|e| e.get(key))
+                .and_then(Json::as_f64)
+        };
+        material.transmission =
+            extension("KHR_materials_transmission", "transmissionFactor").unwrap_or(0.0);
+        material.ior = extension("KHR_materials_ior", "ior").unwrap_or(1.5);
+        if let Some(color) = json.get("emissiveFactor").and_then(Json::as_f64_array) {
+            if let [r, g, b] = color[..] {
+                let strength =
+                    extension("KHR_materials_emissive_strength", "emissiveStrength").unwrap_or(1.0);
+                material.emission = strength * Vec3::new(r, g, b);
+            }
+        }
+        Ok(material)
+    }
+
+    /// The base color texture of every material, tinted by its factor
+    fn base_color_textures(&self) -> Result<Vec<Option<Arc<dyn Texture>>>, LoadError> {
+        let count = self
+            .json
+            .get("materials")
+            .and_then(Json::as_array)
+            .map_or(0, <[Json]>::len);
+        (0..count)
+            .map(|i| {
+                let info = self
+                    .item("materials", i)?
+                    .get("pbrMetallicRoughness")
+                    .and_then(|p| p.get("baseColorTexture"));
+                match self.index(info.and_then(|t| t.get("index")), "texture")? {
+                    Some(texture) => self.texture(texture, self.material(Some(i))?.base_color),
+                    None => Ok(None),
+                }
+            })
+            .collect()
+    }
+
+    /// The image of `textures[index]` multiplied by `tint`, or nothing when
+    /// it is not a PNG image
+    fn texture(&self, index: usize, tint: Vec3) -> Result<Option<Arc<dyn Texture>>, LoadError> {
+        let texture = self.item("textures", index)?;
+        let source = match self.index(texture.get("source"), "source")? {
+            Some(source) => source,
+            None => return Ok(None),
+        };
+        let image = self.item("images", source)?;
+        let error = |message: &str| self.error(format!("images[{}]: {}", source, message));
+        let data = match image.get("uri").and_then(Json::as_str) {
+            Some(uri) if uri.starts_with("data:") => {
+                let payload = uri.find(";base64,").map(|start| &uri[start + 8..]);
+                payload
+                    .and_then(decode_base64)
+                    .ok_or_else(|| error("invalid data URI"))?
+            }
+            Some(uri) => {
+                let dir = self.path.parent().unwrap_or_else(|| Path::new(""));
+                let file = dir.join(decode_uri(uri));
+                std::fs::read(&file).map_err(|error| LoadError::Io { path: file, error })?
+            }
+            None => {
+                let view = self
+                    .index(image.get("bufferView"), "bufferView")?
+                    .ok_or_else(|| error("image without data"))?;
+                let view = self.item("bufferViews", view)?;
+                let buffer = view
+                    .get("buffer")
+                    .and_then(Json::as_usize)
+                    .and_then(|i| self.buffers.get(i))
+                    .ok_or_else(|| error("buffer view of a missing buffer"))?;
+                let offset = view.get("byteOffset").and_then(Json::as_usize).unwrap_or(0);
+                let length = view.get("byteLength").and_then(Json::as_usize).unwrap_or(0);
+                buffer
+                    .get(offset..offset + length)
+                    .ok_or_else(|| error("buffer view out of the buffer"))?
+                    .to_vec()
+            }
+        };
+        // JPEG images cannot be decoded, the base color factor remains
+        if !data.starts_with(b"\x89PNG") {
+            return Ok(None);
+        }
+        let image = read_png(&data[..]).map_err(|e| error(&e.to_string()))?;
+        Ok(Some(ImageTexture::tinted(image, tint)))
+    }
+
+    /// Turn the triangles of a primitive into a mesh
+    fn primitive(&self, primitive: &Json) -> Result<Option<Box<TriangleMesh>>, LoadError> {
+        let attributes = primitive
+            .get("attributes")
+            .ok_or_else(|| self.error("primitive without attributes"))?;
+        let attribute = |name: &str| -> Result<Option<Accessor>, LoadError> {
+            match self.index(attributes.get(name), name)? {
+                Some(index) => self.accessor(index).map(Some),
+                None => Ok(None),
+            }
+        };
+        let positions = match attribute("POSITION")? {
+            Some(positions) if positions.components == 3 => positions,
+            Some(_) => return Err(self.error("POSITION must be a VEC3")),
+            None => return Ok(None),
+        };
+        let count = positions.values.len() / 3;
+        let indices: Vec<usize> = match self.index(primitive.get("indices"), "indices")? {
+            Some(index) => self
+                .accessor(index)?
+                .values
+                .iter()
+                .map(|&i| i as usize)
+                .collect(),
+            None => (0..count).collect(),
+        };
+        if let Some(&i) = indices.iter().find(|&&i| i >= count) {
+            return Err(self.error(format!(
+                "index {} out of range, {} vertices defined",
+                i, count
+            )));
+        }
+
+        let mut faces = Vec::new();
+        match primitive.get("mode").and_then(Json::as_usize).unwrap_or(4) {
+            4 => faces.extend(indices.chunks_exact(3).map(|f| [f[0], f[1], f[2]])),
+            5 => {
+                for k in 2..indices.len() {
+                    let (a, b, c) = (indices[k - 2], indices[k - 1], indices[k]);
+                    faces.push(if k % 2 == 0 { [a, b, c] } else { [b, a, c] });
+                }
+            }
+            6 => {
+                for k in 2..indices.len() {
+                    faces.push([indices[0], indices[k - 1], indices[k]]);
+                }
+            }
+            // points and lines have no surface
+            _ => return Ok(None),
+        }
+        if faces.is_empty() {
+            return Ok(None);
+        }
+
+        // the other attributes hold one value per vertex
+        let vertex_attribute = |name: &str,
+                                components: &[usize]|
+         -> Result<Option<Accessor>, LoadError> {
+            let accessor = match attribute(name)? {
+                Some(accessor) => accessor,
+                None => return Ok(None),
+            };
+            if !components.contains(&accessor.components) {
+                let types: Vec<String> = components.iter().map(|c| format!("VEC{}", c)).collect();
+                return Err(self.error(format!("{} must be a {}", name, types.join(" or "))));
+            }
+            let values = accessor.values.len() / accessor.components;
+            if values != count {
+                return Err(self.error(format!(
+                    "{} has {} values, {} vertices defined",
+                    name, values, count
+                )));
+            }
+            Ok(Some(accessor))
+        };
+        let colors = vertex_attribute("COLOR_0", &[3, 4])?;
+        let material = self.material(self.index(primitive.get("material"), "material")?)?;
+        let face_materials = vec![0; faces.len()];
+        let mut mesh = TriangleMesh::new(
+            (0..count).map(|i| positions.vec3(i)).collect(),
+            faces,
+            vec![material.to_material(colors.is_some())],
+            face_materials,
+        );
+        if let Some(normals) = vertex_attribute("NORMAL", &[3])? {
+            mesh.set_normals(
+                (0..count)
+                    .map(|i| normals.vec3(i).make_unit_vector())
+                    .collect(),
+            );
+        }
+        if let Some(uvs) = vertex_attribute("TEXCOORD_0", &[2])? {
+            mesh.set_uvs(
+                uvs.values
+                    .chunks_exact(uvs.components)
+                    // glTF images start at the top
+                    .map(|uv| (uv[0], 1.0 - uv[1]))
+                    .collect(),
+            );
+        }
+        if let Some(colors) = colors {
+            // vertex colors multiply the base color
+            mesh.set_colors(
+                (0..count)
+                    .map(|i| colors.vec3(i) * material.base_color)
+                    .collect(),
+            );
+        }
+        Ok(Some(mesh))
+    }
+
+    fn camera(&self, index: usize, transform: &Mat4) -> Result<Option<CameraSettings>, LoadError> {
+        let camera = self.item("cameras", index)?;
+        let yfov = match camera
+            .get("perspective")
+            .and_then(|p| p.get("yfov"))
+            .and_then(Json::as_f64)
+        {
+            Some(yfov) => yfov,
+            // orthographic cameras are not supported
+            None => return Ok(None),
+        };
+        let origin = transform.transform_point(Vec3::zeros());
+        let forward = transform.transform_vector(Vec3::new(0.0, 0.0, -1.0));
+        let up = transform.transform_vector(Vec3::new(0.0, 1.0, 0.0));
+        Ok(Some(CameraSettings {
+            lookfrom: origin,
+            lookat: origin + forward,
+            vup: up,
+            vfov: yfov.to_degrees(),
+            aperture: 0.0,
+            focus_dist: forward.length(),
+            ..CameraSettings::default()
+        }))
+    }
+
+    fn node_transform(&self, node: &Json) -> Mat4 {
+        let matrix = node.get("matrix").and_then(Json::as_f64_array);
+        if let Some(Ok(values)) = matrix.map(|m| m.try_into()) {
+            return Mat4::from_column_major(&values);
+        }
+        let array = |name: &str, default: &[f64]| {
+            node.get(name)
+                .and_then(Json::as_f64_array)
+                .filter(|a| a.len() == default.len())
+                .unwrap_or_else(|| default.to_vec())
+        };
+        let (t, q, s) = (
+            array("translation", &[0.0; 3]),
+            array("rotation", &[0.0, 0.0, 0.0, 1.0]),
+            array("scale", &[1.0; 3]),
+        );
+        Mat4::translation(Vec3::new(t[0], t[1], t[2]))
+            * Mat4::from_quaternion(q[0], q[1], q[2], q[3])
+            * Mat4::scaling(Vec3::new(s[0], s[1], s[2]))
+    }
+
+    /// The meshes made of the primitives of `meshes[index]`, built the first
+    /// time they are needed
+    fn mesh<'c>(
+        &self,
+        index: usize,
+        cache: &'c mut HashMap<usize, Vec<Arc<TriangleMesh>>>,
+    ) -> Result<&'c [Arc<TriangleMesh>], LoadError> {
+        if let Entry::Vacant(entry) = cache.entry(index) {
+            let mut meshes = Vec::new();
+            let primitives = self
+                .item("meshes", index)?
+                .get("primitives")
+                .and_then(Json::as_array)
+                .unwrap_or(&[]);
+            for primitive in primitives {
+                if let Some(mesh) = self.primitive(primitive)? {
+                    meshes.push(Arc::from(mesh));
+                }
+            }
+            entry.insert(meshes);
+        }
+        Ok(&cache[&index])
+    }
+
+    /// Add the node and its descendants to `scene`
+    fn visit(
+        &self,
+        index: usize,
+        parent: &Mat4,
+        depth: usize,
+        cache: &mut HashMap<usize, Vec<Arc<TriangleMesh>>>,
+        scene: &mut GltfScene,
+    ) -> Result<(), LoadError> {
+        // a node can only appear once in a hierarchy, deeper means a cycle
+        if depth
+            > self
+                .json
+                .get("nodes")
+                .and_then(Json::as_array)
+                .map_or(0, <[Json]>::len)
+        {
+            return Err(self.error("the node hierarchy has a cycle"));
+        }
+        let node = self.item("nodes", index)?;
+        let transform = *parent * self.node_transform(node);
+        if let Some(mesh) = self.index(node.get("mesh"), "mesh")? {
+            // a node scaled down to nothing hides its mesh
+            if let Some(placement) = Transform::new(transform) {
+                for mesh in self.mesh(mesh, cache)? {
+                    scene
+                        .world
+                        .add(Transformed::new(Arc::clone(mesh), placement));
+                }
+            }
+        }
+        if let Some(camera) = self.index(node.get("camera"), "camera")? {
+            if scene.camera.is_none() {
+                scene.camera = self.camera(camera, &transform)?;
+            }
+        }
+        for child in node.get("children").and_then(Json::as_array).unwrap_or(&[]) {
+            let child = self.index(Some(child), "child")?.unwrap_or(0);
+            self.visit(child, &transform, depth + 1, cache, scene)?;
+        }
+        Ok(())
+    }
+
+    /// The nodes at the root of the scene to import
+    fn roots(&self) -> Result<Vec<usize>, LoadError> {
+        let scene = self.index(self.json.get("scene"), "scene")?.unwrap_or(0);
+        if let Some(scenes) = self.json.get("scenes").and_then(Json::as_array) {
+            if !scenes.is_empty() {
+                let scene = self.item("scenes", scene)?;
+                return scene
+                    .get("nodes")
+                    .and_then(Json::as_array)
+                    .unwrap_or(&[])
+                    .iter()
+                    .map(|n| n.as_usize().ok_or_else(|| self.error("invalid scene node")))
+                    .collect();
+            }
+        }
+        // without scenes every node which is no one's child is a root
+        let nodes = self
+            .json
+            .get("nodes")
+            .and_then(Json::as_array)
+            .unwrap_or(&[]);
+        let children: Vec<usize> = nodes
+            .iter()
+            .filter_map(|n| n.get("children").and_then(Json::as_array))
+            .flatten()
+            .filter_map(Json::as_usize)
+            .collect();
+        Ok((0..nodes.len()).filter(|i| !children.contains(i)).collect())
+    }
+}
+
+impl GltfScene {
+    /// Import the glTF or binary glTF asset found at `path`, external buffers
+    /// being looked up relative to it
+    pub fn parse(data: &[u8], path: &Path) -> Result<GltfScene, LoadError> {
+        let (text, bin) = if data.starts_with(GLB_MAGIC) {
+            split_glb(data).map_err(|message| LoadError::invalid(path, message))?
+        } else {
+            (data, None)
+        };
+        let text = std::str::from_utf8(text)
+            .map_err(|_| LoadError::invalid(path, "the JSON is not valid UTF-8"))?;
+        let json = Json::parse(text).map_err(|e| LoadError::parse(path, e.line, e.message))?;
+        let version = json
+            .get("asset")
+            .and_then(|a| a.get("version"))
+            .and_then(Json::as_str);
+        if !version.is_some_and(|v| v.starts_with("2.")) {
+            return Err(LoadError::invalid(
+                path,
+                "only glTF 2.0 assets are supported",
+            ));
+        }
+
+        let dir = path.parent().unwrap_or_else(|| Path::new(""));
+        let mut buffers = Vec::new();
+        for (i, buffer) in json
+            .get("buffers")
+            .and_then(Json::as_array)
+            .unwrap_or(&[])
+            .iter()
+            .enumerate()
+        {
+            let mut data = match buffer.get("uri").and_then(Json::as_str) {
+                Some(uri) if uri.starts_with("data:") => {
+                    let payload = uri.find(";base64,").map(|start| &uri[start + 8..]);
+                    payload.and_then(decode_base64).ok_or_else(|| {
+                        LoadError::invalid(path, format!("buffers[{}]: invalid data URI", i))
+                    })?
+                }
+                Some(uri) => {
+                    let file = dir.join(decode_uri(uri));
+                    std::fs::read(&file).map_err(|error| LoadError::Io { path: file, error })?
+                }
+                None if i == 0 && bin.is_some() => bin.unwrap_or(&[]).to_vec(),
+                None => {
+                    return Err(LoadError::invalid(
+                        path,
+                        format!("buffers[{}] has no data", i),
+                    ))
+                }
+            };
+            let length = buffer
+                .get("byteLength")
+                .and_then(Json::as_usize)
+                .unwrap_or(0);
+            if data.len() < length {
+                return Err(LoadError::invalid(
+                    path,
+                    format!("buffers[{}] is shorter than its byteLength", i),
+                ));
+            }
+            // the binary chunk may be padded
+            data.truncate(length);
+            buffers.push(data);
+        }
+
+        let mut document = Document {
+            json: &json,
+            buffers,
+            path,
+            textures: Vec::new(),
+        };
+        document.textures = document.base_color_textures()?;
+        let mut scene = GltfScene {
+            world: World::new(),
+            camera: None,
+        };
+        let mut cache = HashMap::new();
+        for root in document.roots()? {
+            document.visit(root, &Mat4::identity(), 0, &mut cache, &mut scene)?;
+        }
+        Ok(scene)
+    }
+
+    /// Read and import a glTF asset
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<GltfScene, LoadError> {
+        let data = std::fs::read(&path).map_err(|error| LoadError::Io {
+            path: path.as_ref().to_path_buf(),
+            error,
+        })?;
+        GltfScene::parse(&data, path.as_ref())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ray::Ray;
+    use crate::sampler::Sampler;
+
+    /// A triangle in the plane z = 0 and the indices 0, 1, 2 as u16
+    fn buffer() -> Vec<u8> {
+        let mut data = Vec::new();
+        for x in &[0.0f32, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0] {
+            data.extend_from_slice(&x.to_le_bytes());
+        }
+        for i in &[0u16, 1, 2, 0] {
+            data.extend_from_slice(&i.to_le_bytes());
+        }
+        data
+    }
+
+    fn document(uri: &str) -> String {
+        format!(
+            r#"{{
+  "asset": {{"version": "2.0"}},
+  "scene": 0,
+  "scenes": [{{"nodes": [0]}}],
+  "nodes": [
+    {{"translation": [0, 0, -5], "children": [1, 2, 3]}},
+    {{"mesh": 0, "scale": [2, 2, 2]}},
+    {{"camera": 0, "translation": [0, 0, 10]}},
+    {{"mesh": 0, "translation": [10, 0, 0], "rotation": [0, 0, 1, 0]}}
+  ],
+  "cameras": [{{"type": "perspective", "perspective": {{"yfov": 0.5, "znear": 0.1}}}}],
+  "meshes": [{{"primitives": [{{"attributes": {{"POSITION": 0}}, "indices": 1, "material": 0}}]}}],
+  "materials": [{{"pbrMetallicRoughness": {{"baseColorFactor": [1, 0, 0, 1], "metallicFactor": 0}}}}],
+  "accessors": [
+    {{"bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3"}},
+    {{"bufferView": 1, "componentType": 5123, "count": 3, "type": "SCALAR"}}
+  ],
+  "bufferViews": [
+    {{"buffer": 0, "byteLength": 36}},
+    {{"buffer": 0, "byteOffset": 36, "byteLength": 6}}
+  ],
+  "buffers": [{{"byteLength": 44{}}}]
+}}"#,
+            uri
+        )
+    }
+
+    fn check(scene: &GltfScene) {
+        // the triangle was scaled by 2 and moved 5 units away along -z
+        let r = Ray::new(Vec3::new(1.5, 0.2, 0.0), Vec3::new(0.0, 0.0, -1.0));
+        assert_eq!(scene.world.hit(&r, 0.001, f64::MAX).map(|h| h.t), Some(5.0));
+        let r = Ray::new(Vec3::new(1.2, 1.2, 0.0), Vec3::new(0.0, 0.0, -1.0));
+        assert!(scene.world.hit(&r, 0.001, f64::MAX).is_none());
+        // the second instance was turned upside down around z
+        let r = Ray::new(Vec3::new(9.8, -0.2, 0.0), Vec3::new(0.0, 0.0, -1.0));
+        let hit = scene.world.hit(&r, 0.001, f64::MAX).unwrap();
+        assert_eq!(hit.t, 5.0);
+        assert!((hit.n.z.abs() - 1.0).abs() < 1e-9);
+
+        let camera = scene.camera.as_ref().unwrap();
+        let ray = camera.build(1.0).get_ray(0.5, 0.5, &mut Sampler::new(0, 0));
+        assert_eq!(ray.origin, Vec3::new(0.0, 0.0, 5.0));
+        assert!((ray.direction.make_unit_vector() - Vec3::new(0.0, 0.0, -1.0)).length() < 1e-9);
+    }
+
+    fn encode_base64(data: &[u8]) -> String {
+        const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+        let mut text = String::new();
+        for chunk in data.chunks(3) {
+            let bits = chunk
+                .iter()
+                .enumerate()
+                .fold(0u32, |acc, (i, &b)| acc | (b as u32) << (16 - 8 * i));
+            for k in 0..4 {
+                if k <= chunk.len() {
+                    text.push(ALPHABET[(bits >> (18 - 6 * k) & 63) as usize] as char);
+                } else {
+                    text.push('=');
+                }
+            }
+        }
+        text
+    }
+
+    fn embedded_uri() -> String {
+        format!(
+            r#", "uri": "data:application/octet-stream;base64,{}""#,
+            encode_base64(&buffer())
+        )
+    }
+
+    #[test]
+    fn embedded_test() {
+        let scene =
+            GltfScene::parse(document(&embedded_uri()).as_bytes(), Path::new("a.gltf")).unwrap();
+        check(&scene);
+    }
+
+    #[test]
+    fn texture_test() {
+        let image = crate::render::FrameBuffer::new(1, 1, vec![Vec3::all(0.25)]);
+        let mut png = Vec::new();
+        crate::image::write_png(&image, crate::image::Compression::Fixed, &mut png).unwrap();
+        let source = document(&embedded_uri())
+            .replace(
+                r#""metallicFactor": 0}"#,
+                r#""metallicFactor": 0, "baseColorTexture": {"index": 0}}"#,
+            )
+            .replace(
+                r#""accessors""#,
+                &format!(
+                    r#""textures": [{{"source": 0}}],
+  "images": [{{"uri": "data:image/png;base64,{}"}}],
+  "accessors""#,
+                    encode_base64(&png)
+                ),
+            );
+        let scene = GltfScene::parse(source.as_bytes(), Path::new("a.gltf")).unwrap();
+        let r = Ray::new(Vec3::new(1.5, 0.2, 0.0), Vec3::new(0.0, 0.0, -1.0));
+        let hit = scene.world.hit(&r, 0.001, f64::MAX).unwrap();
+        let sample = hit
+            .material
+            .sample(&r, &hit, &mut Sampler::new(0, 0))
+            .unwrap();
+        // the red base color factor multiplies the gray texture
+        assert!(sample.weight.x > 0.1 && sample.weight.x < 0.3);
+        assert_eq!(sample.weight.y, 0.0);
+    }
+
+    #[test]
+    fn glb_test() {
+        let mut json = document("").into_bytes();
+        while !json.len().is_multiple_of(4) {
+            json.push(b' ');
+        }
+        let bin = buffer();
+        let mut data = Vec::new();
+        data.extend_from_slice(GLB_MAGIC);
+        data.extend_from_slice(&2u32.to_le_bytes());
+        data.extend_from_slice(&((12 + 16 + json.len() + bin.len()) as u32).to_le_bytes());
+        data.extend_from_slice(&(json.len() as u32).to_le_bytes());
+        data.extend_from_slice(&GLB_JSON_CHUNK.to_le_bytes());
+        data.extend_from_slice(&json);
+        data.extend_from_slice(&(bin.len() as u32).to_le_bytes());
+        data.extend_from_slice(&GLB_BIN_CHUNK.to_le_bytes());
+        data.extend_from_slice(&bin);
+        check(&GltfScene::parse(&data, Path::new("a.glb")).unwrap());
+    }
+
+    #[test]
+    fn error_test() {
+        let error = |source: &str| {
+            GltfScene::parse(source.as_bytes(), Path::new("bad.gltf"))
+                .err()
+                .unwrap()
+                .to_string()
+        };
+        assert_eq!(
+            error(r#"{"asset": {"version": "1.0"}}"#),
+            "bad.gltf: only glTF 2.0 assets are supported"
+        );
+        assert_eq!(error("{\n\"asset\": }"), "bad.gltf:2: unexpected character");
+        assert_eq!(error(&document("")), "bad.gltf: buffers[0] has no data");
+        assert_eq!(
+            error(
+                r#"{"asset": {"version": "2.0"}, "scenes": [{"nodes": [0]}], "nodes": [{"children": [0]}]}"#
+            ),
+            "bad.gltf: the node hierarchy has a cycle"
+        );
+        let uri = r#", "uri": "data:application/octet-stream;base64,AAAA""#;
+        assert_eq!(
+            error(&document(uri)),
+            "bad.gltf: buffers[0] is shorter than its byteLength"
+        );
+        // attributes must match the positions
+        let attributes = |attributes: &str| {
+            document(&embedded_uri())
+                .replace(r#"{"POSITION": 0}"#, attributes)
+                .replace(
+                    r#""type": "SCALAR"}"#,
+                    r#""type": "SCALAR"},
+    {"bufferView": 0, "componentType": 5126, "count": 2, "type": "VEC3"}"#,
+                )
+        };
+        assert_eq!(
+            error(&attributes(r#"{"POSITION": 0, "NORMAL": 2}"#)),
+            "bad.gltf: NORMAL has 2 values, 3 vertices defined"
+        );
+        assert_eq!(
+            error(&attributes(r#"{"POSITION": 0, "TEXCOORD_0": 2}"#)),
+            "bad.gltf: TEXCOORD_0 must be a VEC2"
+        );
+        assert_eq!(
+            error(&attributes(r#"{"POSITION": 0, "TEXCOORD_0": 1}"#)),
+            "bad.gltf: TEXCOORD_0 must be a VEC2"
+        );
+        assert_eq!(
+            error(&attributes(r#"{"POSITION": 0, "COLOR_0": 1}"#)),
+            "bad.gltf: COLOR_0 must be a VEC3 or VEC4"
+        );
+        let huge = r#""count": 100000000000000"#;
+        assert_eq!(
+            error(&document(&embedded_uri()).replacen(r#""count": 3"#, huge, 1)),
+            "bad.gltf: accessors[0]: accessor out of the buffer view"
+        );
+        assert_eq!(
+            error(
+                &document(&embedded_uri())
+                    .replacen(r#""bufferView": 0, "#, "", 1)
+                    .replacen(r#""count": 3"#, huge, 1)
+            ),
+            "bad.gltf: accessors[0]: too many values without a buffer view"
+        );
+    }
+}
@@ -0,0 +1,105 @@
+//! Importers turning asset files into objects of the crate.
+mod gltf;
+pub mod json;
+mod mtl;
+mod obj;
+mod ply;
+
+pub use gltf::GltfScene;
+pub use mtl::MtlMaterial;
+pub use obj::{ObjGroup, ObjModel};
+pub use ply::PlyModel;
+
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Why a file could not be loaded
+#[derive(Debug)]
+pub enum LoadError {
+    /// the file could not be read
+    Io { path: PathBuf, error: io::Error },
+    /// the content of the file is invalid, `line` starts at 1
+    Parse {
+        path: PathBuf,
+        line: usize,
+        message: String,
+    },
+    /// the content of the file is invalid as a whole rather than at a line
+    Invalid { path: PathBuf, message: String },
+    /// nothing can load files with this extension
+    Unsupported { path: PathBuf },
+}
+
+impl LoadError {
+    pub(crate) fn parse<P: AsRef<Path>, S: Into<String>>(
+        path: P,
+        line: usize,
+        message: S,
+    ) -> LoadError {
+        LoadError::Parse {
+            path: path.as_ref().to_path_buf(),
+            line,
+            message: message.into(),
+        }
+    }
+
+    pub(crate) fn invalid<P: AsRef<Path>, S: Into<String>>(path: P, message: S) -> LoadError {
+        LoadError::Invalid {
+            path: path.as_ref().to_path_buf(),
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LoadError::Io { path, error } => write!(f, "{}: {}", path.display(), error),
+            LoadError::Parse {
+                path,
+                line,
+                message,
+            } => write!(f, "{}:{}: {}", path.display(), line, message),
+            LoadError::Invalid { path, message } => write!(f, "{}: {}", path.display(), message),
+            LoadError::Unsupported { path } => {
+                write!(f, "{}: unsupported file format", path.display())
+            }
+        }
+    }
+}
+
+impl Error for LoadError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            LoadError::Io { error, .. } => Some(error),
+            LoadError::Parse { .. } | LoadError::Invalid { .. } | LoadError::Unsupported { .. } => {
+                None
+            }
+        }
+    }
+}
+
+pub(crate) fn read_to_string<P: AsRef<Path>>(path: P) -> Result<String, LoadError> {
+    std::fs::read_to_string(&path).map_err(|error| LoadError::Io {
+        path: path.as_ref().to_path_buf(),
+        error,
+    })
+}
+
+/// Parse the whitespace separated numbers of a statement, reporting the
+/// offending token when one is not a number
+pub(crate) fn parse_floats<'a, I: Iterator<Item = &'a str>>(
+    tokens: I,
+    path: &Path,
+    line: usize,
+) -> Result<Vec<f64>, LoadError> {
+    tokens
+        .map(|token| {
+            token
+                .parse::<f64>()
+                .map_err(|_| LoadError::parse(path, line, format!("invalid number '{}'", token)))
+        })
+        .collect()
+}
@@ -0,0 +1,260 @@
+//! Wavefront material library (`.mtl`) parser.
+use super::{parse_floats, read_to_string, LoadError};
+use crate::image;
+use crate::materials::{Dielectrics, DiffuseLight, Lambertian, Materialable, Metal};
+use crate::textures::{ImageTexture, Texture};
+use crate::vec3::Vec3;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Index of refraction used for transparent materials which do not set `Ni`
+const DEFAULT_IOR: f64 = 1.5;
+
+/// The statements of one `newmtl` block of a material library
+#[derive(Clone, Debug, PartialEq)]
+pub struct MtlMaterial {
+    pub name: String,
+    /// `Kd`
+    pub diffuse: Vec3,
+    /// `Ks`
+    pub specular: Vec3,
+    /// `Ns`, the Phong exponent of the specular highlight
+    pub shininess: f64,
+    /// `Ni`
+    pub ior: Option<f64>,
+    /// `d`, or `1 - Tr`, 1 being fully opaque
+    pub dissolve: f64,
+    /// `Ke`
+    pub emission: Vec3,
+    /// `illum`
+    pub illum: Option<u32>,
+    /// `map_Kd`, resolved relative to the library
+    pub diffuse_map: Option<PathBuf>,
+}
+
+impl MtlMaterial {
+    pub fn new(name: &str) -> MtlMaterial {
+        MtlMaterial {
+            name: name.to_string(),
+            diffuse: Vec3::all(0.8),
+            specular: Vec3::zeros(),
+            shininess: 0.0,
+            ior: None,
+            dissolve: 1.0,
+            emission: Vec3::zeros(),
+            illum: None,
+            diffuse_map: None,
+        }
+    }
+
+    fn is_emitter(&self) -> bool {
+        let e = self.emission;
+        e.x.max(e.y).max(e.z) > 0.0
+    }
+
+    fn is_transparent(&self) -> bool {
+        self.dissolve < 1.0 || matches!(self.illum, Some(4) | Some(6) | Some(7) | Some(9))
+    }
+
+    fn is_metallic(&self) -> bool {
+        let max = |c: Vec3| c.x.max(c.y).max(c.z);
+        self.illum == Some(3) || max(self.specular) > max(self.diffuse)
+    }
+
+    /// Whether [`MtlMaterial::to_material`] makes a [`Lambertian`]
+    fn is_diffuse(&self) -> bool {
+        !self.is_emitter() && !self.is_transparent() && !self.is_metallic()
+    }
+
+    /// The `map_Kd` image multiplied by `Kd`, if the material is diffuse and
+    /// has one. Missing images and formats which cannot be read are skipped
+    /// so that `Kd` remains.
+    pub fn diffuse_texture(&self) -> Result<Option<Arc<dyn Texture>>, LoadError> {
+        let path = match &self.diffuse_map {
+            Some(path) if self.is_diffuse() => path,
+            _ => return Ok(None),
+        };
+        match image::load(path) {
+            Ok(image) => Ok(Some(ImageTexture::tinted(image, self.diffuse))),
+            Err(error)
+                if matches!(
+                    error.kind(),
+                    io::ErrorKind::NotFound | io::ErrorKind::InvalidInput
+                ) =>
+            {
+                Ok(None)
+            }
+            Err(error) => Err(LoadError::Io {
+                path: path.clone(),
+                error,
+            }),
+        }
+    }
+
+    /// The closest material the renderer supports.
+    ///
+    /// Emitting materials become a [`DiffuseLight`], transparent ones
+    /// [`Dielectrics`], materials dominated by their specular color [`Metal`]
+    /// whose fuzziness decreases with `Ns`, and everything else
+    /// [`Lambertian`], whose albedo is `diffuse_texture` when given, see
+    /// [`MtlMaterial::diffuse_texture`]. Other maps are ignored.
+    pub fn to_material(&self, diffuse_texture: Option<Arc<dyn Texture>>) -> Box<dyn Materialable> {
+        let e = self.emission;
+        if self.is_emitter() {
+            DiffuseLight::new(e.x, e.y, e.z)
+        } else if self.is_transparent() {
+            Dielectrics::new(self.ior.unwrap_or(DEFAULT_IOR))
+        } else if self.is_metallic() {
+            let fuzzy = (2.0 / (self.shininess.max(0.0) + 2.0)).sqrt();
+            let c = self.specular;
+            Metal::new(c.x, c.y, c.z, fuzzy)
+        } else if let Some(texture) = diffuse_texture {
+            Lambertian::textured(texture)
+        } else {
+            let c = self.diffuse;
+            Lambertian::new(c.x, c.y, c.z)
+        }
+    }
+}
+
+fn parse_color<'a, I: Iterator<Item = &'a str>>(
+    tokens: I,
+    path: &Path,
+    line: usize,
+) -> Result<Vec3, LoadError> {
+    match parse_floats(tokens, path, line)?[..] {
+        [c] => Ok(Vec3::all(c)),
+        [r, g, b] => Ok(Vec3::new(r, g, b)),
+        _ => Err(LoadError::parse(
+            path,
+            line,
+            "expected 1 or 3 color components",
+        )),
+    }
+}
+
+fn parse_scalar<'a, I: Iterator<Item = &'a str>>(
+    tokens: I,
+    path: &Path,
+    line: usize,
+) -> Result<f64, LoadError> {
+    match parse_floats(tokens, path, line)?[..] {
+        [x] => Ok(x),
+        _ => Err(LoadError::parse(path, line, "expected a single number")),
+    }
+}
+
+/// Parse the content of the library found at `path`
+pub fn parse_mtl(source: &str, path: &Path) -> Result<Vec<MtlMaterial>, LoadError> {
+    let dir = path.parent().unwrap_or_else(|| Path::new(""));
+    let mut materials: Vec<MtlMaterial> = Vec::new();
+    for (i, raw) in source.lines().enumerate() {
+        let line = i + 1;
+        let content = raw.split('#').next().unwrap_or("");
+        let mut tokens = content.split_whitespace();
+        let keyword = match tokens.next() {
+            Some(keyword) => keyword,
+            None => continue,
+        };
+        if keyword == "newmtl" {
+            let name = tokens.collect::<Vec<_>>().join(" ");
+            if name.is_empty() {
+                return Err(LoadError::parse(path, line, "newmtl without a name"));
+            }
+            materials.push(MtlMaterial::new(&name));
+            continue;
+        }
+        let material = match materials.last_mut() {
+            Some(material) => material,
+            None => {
+                return Err(LoadError::parse(
+                    path,
+                    line,
+                    format!("'{}' before any newmtl", keyword),
+                ))
+            }
+        };
+        match keyword {
+            "Kd" => material.diffuse = parse_color(tokens, path, line)?,
+            "Ks" => material.specular = parse_color(tokens, path, line)?,
+            "Ke" => material.emission = parse_color(tokens, path, line)?,
+            "Ns" => material.shininess = parse_scalar(tokens, path, line)?,
+            "Ni" => material.ior = Some(parse_scalar(tokens, path, line)?),
+            "d" => material.dissolve = parse_scalar(tokens, path, line)?,
+            "Tr" => material.dissolve = 1.0 - parse_scalar(tokens, path, line)?,
+            "illum" => {
+                let illum = tokens.next().and_then(|t| t.parse().ok());
+                if illum.is_none() {
+                    return Err(LoadError::parse(path, line, "invalid illumination model"));
+                }
+                material.illum = illum;
+            }
+            "map_Kd" => {
+                // options may precede the file name which comes last
+                match tokens.last() {
+                    Some(file) => material.diffuse_map = Some(dir.join(file)),
+                    None => return Err(LoadError::parse(path, line, "map_Kd without a file")),
+                }
+            }
+            // ambient color, other maps and vendor extensions have no equivalent
+            _ => {}
+        }
+    }
+    Ok(materials)
+}
+
+/// Read and parse a material library
+pub fn load_mtl<P: AsRef<Path>>(path: P) -> Result<Vec<MtlMaterial>, LoadError> {
+    let source = read_to_string(&path)?;
+    parse_mtl(&source, path.as_ref())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_mtl_test() {
+        let source = "\
+# two materials
+newmtl red plastic
+Kd 0.8 0.1 0.1
+Ks 0.2
+Ns 10
+map_Kd -s 1 1 1 textures/red.png
+
+newmtl glass
+d 0.2
+Ni 1.45
+
+newmtl lamp
+Ke 4 4 3
+";
+        let materials = parse_mtl(source, Path::new("assets/lib.mtl")).unwrap();
+        assert_eq!(materials.len(), 3);
+        assert_eq!(materials[0].name, "red plastic");
+        assert_eq!(materials[0].diffuse, Vec3::new(0.8, 0.1, 0.1));
+        assert_eq!(materials[0].specular, Vec3::all(0.2));
+        assert_eq!(
+            materials[0].diffuse_map,
+            Some(PathBuf::from("assets/textures/red.png"))
+        );
+        assert!(!materials[0].is_metallic());
+        assert!(materials[1].is_transparent());
+        assert_eq!(materials[1].ior, Some(1.45));
+        assert!(!materials[1].to_material(None).is_emitter());
+        assert!(materials[2].to_material(None).is_emitter());
+        // a missing image leaves the diffuse color
+        assert!(materials[0].diffuse_texture().unwrap().is_none());
+        assert!(materials[1].diffuse_texture().unwrap().is_none());
+    }
+
+    #[test]
+    fn parse_mtl_error_test() {
+        let error = parse_mtl("newmtl a\nKd 1 x 1\n", Path::new("lib.mtl")).unwrap_err();
+        assert_eq!(error.to_string(), "lib.mtl:2: invalid number 'x'");
+        assert!(parse_mtl("Kd 1 1 1\n", Path::new("lib.mtl")).is_err());
+        assert!(parse_mtl("newmtl a\nNs 1 2\n", Path::new("lib.mtl")).is_err());
+    }
+}
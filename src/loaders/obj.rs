@@ -0,0 +1,389 @@
+//! Wavefront OBJ parser.
+//!
+//! Supports vertex positions, texture coordinates and normals, polygonal
+//! faces of any size which are triangulated as fans, negative (relative)
+//! indices, groups and objects, and materials from `mtllib` libraries.
+use super::mtl::{load_mtl, MtlMaterial};
+use super::{parse_floats, read_to_string, LoadError};
+use crate::hittable::{TriangleMesh, World};
+use crate::textures::Texture;
+use crate::vec3::Vec3;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+/// The faces of one group (`g`) or object (`o`) of an OBJ file
+pub struct ObjGroup {
+    pub name: String,
+    pub mesh: Box<TriangleMesh>,
+}
+
+/// Everything an OBJ file describes
+pub struct ObjModel {
+    pub groups: Vec<ObjGroup>,
+}
+
+/// A corner of a face: indices of its position, texture coordinates and normal
+type Corner = (usize, Option<usize>, Option<usize>);
+
+/// Faces collected for a group, with their vertices renumbered so that they
+/// only reference the vertices the group uses
+struct GroupBuilder {
+    name: String,
+    corners: HashMap<Corner, usize>,
+    positions: Vec<Vec3>,
+    uvs: Vec<Option<(f64, f64)>>,
+    normals: Vec<Option<Vec3>>,
+    faces: Vec<[usize; 3]>,
+    face_materials: Vec<usize>,
+    /// the materials used by the group, as indices in the library, `None` being
+    /// the default material of faces preceding any `usemtl`
+    materials: Vec<Option<usize>>,
+}
+
+impl GroupBuilder {
+    fn new(name: &str) -> GroupBuilder {
+        GroupBuilder {
+            name: name.to_string(),
+            corners: HashMap::new(),
+            positions: Vec::new(),
+            uvs: Vec::new(),
+            normals: Vec::new(),
+            faces: Vec::new(),
+            face_materials: Vec::new(),
+            materials: Vec::new(),
+        }
+    }
+
+    fn vertex(&mut self, corner: Corner, data: &ObjData) -> usize {
+        let next = self.positions.len();
+        let index = *self.corners.entry(corner).or_insert(next);
+        if index == next {
+            let (v, vt, vn) = corner;
+            self.positions.push(data.positions[v]);
+            self.uvs.push(vt.map(|i| data.uvs[i]));
+            self.normals.push(vn.map(|i| data.normals[i]));
+        }
+        index
+    }
+
+    /// Build the mesh, `textures` being the diffuse textures of the materials
+    /// in `library`
+    fn finish(self, library: &[MtlMaterial], textures: &[Option<Arc<dyn Texture>>]) -> ObjGroup {
+        let default = MtlMaterial::new("default");
+        let materials = self
+            .materials
+            .iter()
+            .map(|m| match *m {
+                Some(i) => library[i].to_material(textures[i].clone()),
+                None => default.to_material(None),
+            })
+            .collect();
+        let has_uvs = self.uvs.iter().any(Option::is_some);
+        let normals: Option<Vec<Vec3>> = self.normals.into_iter().collect();
+        let mut mesh =
+            TriangleMesh::new(self.positions, self.faces, materials, self.face_materials);
+        if has_uvs {
+            mesh.set_uvs(
+                self.uvs
+                    .into_iter()
+                    .map(|uv| uv.unwrap_or((0.0, 0.0)))
+                    .collect(),
+            );
+        }
+        // smooth shading only makes sense when every vertex has a normal
+        if let Some(normals) = normals {
+            mesh.set_normals(normals);
+        }
+        ObjGroup {
+            name: self.name,
+            mesh,
+        }
+    }
+}
+
+/// The vertex attributes declared so far, shared by every group
+#[derive(Default)]
+struct ObjData {
+    positions: Vec<Vec3>,
+    uvs: Vec<(f64, f64)>,
+    normals: Vec<Vec3>,
+}
+
+/// Turn a 1-based, or negative for relative, OBJ index into a 0-based one
+fn resolve_index(token: &str, count: usize, path: &Path, line: usize) -> Result<usize, LoadError> {
+    let index: i64 = token
+        .parse()
+        .map_err(|_| LoadError::parse(path, line, format!("invalid index '{}'", token)))?;
+    let resolved = if index > 0 {
+        index - 1
+    } else {
+        count as i64 + index
+    };
+    if index == 0 || resolved < 0 || resolved >= count as i64 {
+        Err(LoadError::parse(
+            path,
+            line,
+            format!("index {} out of range, {} defined", index, count),
+        ))
+    } else {
+        Ok(resolved as usize)
+    }
+}
+
+fn parse_corner(
+    token: &str,
+    data: &ObjData,
+    path: &Path,
+    line: usize,
+) -> Result<Corner, LoadError> {
+    let mut parts = token.split('/');
+    let v = resolve_index(parts.next().unwrap_or(""), data.positions.len(), path, line)?;
+    let vt = match parts.next() {
+        Some("") | None => None,
+        Some(t) => Some(resolve_index(t, data.uvs.len(), path, line)?),
+    };
+    let vn = match parts.next() {
+        Some("") | None => None,
+        Some(t) => Some(resolve_index(t, data.normals.len(), path, line)?),
+    };
+    if parts.next().is_some() {
+        return Err(LoadError::parse(
+            path,
+            line,
+            format!("invalid face vertex '{}'", token),
+        ));
+    }
+    Ok((v, vt, vn))
+}
+
+impl ObjModel {
+    /// Parse the content of the OBJ file found at `path`, material libraries
+    /// being looked up relative to it
+    pub fn parse(source: &str, path: &Path) -> Result<ObjModel, LoadError> {
+        let dir = path.parent().unwrap_or_else(|| Path::new(""));
+        let mut data = ObjData::default();
+        let mut library: Vec<MtlMaterial> = Vec::new();
+        // loaded once for all the groups using them
+        let mut textures: Vec<Option<Arc<dyn Texture>>> = Vec::new();
+        let mut groups = Vec::new();
+        let mut group = GroupBuilder::new("default");
+        let mut material: Option<usize> = None;
+
+        for (i, raw) in source.lines().enumerate() {
+            let line = i + 1;
+            let content = raw.split('#').next().unwrap_or("");
+            let mut tokens = content.split_whitespace();
+            let keyword = match tokens.next() {
+                Some(keyword) => keyword,
+                None => continue,
+            };
+            match keyword {
+                "v" => match parse_floats(tokens, path, line)?[..] {
+                    // an optional weight may follow the coordinates
+                    [x, y, z] | [x, y, z, _] => data.positions.push(Vec3::new(x, y, z)),
+                    _ => return Err(LoadError::parse(path, line, "expected 3 coordinates")),
+                },
+                "vt" => match parse_floats(tokens, path, line)?[..] {
+                    [u] => data.uvs.push((u, 0.0)),
+                    [u, v] | [u, v, _] => data.uvs.push((u, v)),
+                    _ => {
+                        return Err(LoadError::parse(
+                            path,
+                            line,
+                            "expected 1 to 3 texture coordinates",
+                        ))
+                    }
+                },
+                "vn" => match parse_floats(tokens, path, line)?[..] {
+                    [x, y, z] => {
+                        let normal = Vec3::new(x, y, z);
+                        let length = normal.length();
+                        if !(length > 0.0 && length.is_finite()) {
+                            return Err(LoadError::parse(
+                                path,
+                                line,
+                                "a normal needs a finite non-zero length",
+                            ));
+                        }
+                        data.normals.push(normal / length);
+                    }
+                    _ => return Err(LoadError::parse(path, line, "expected 3 normal components")),
+                },
+                "f" => {
+                    let corners = tokens
+                        .map(|token| parse_corner(token, &data, path, line))
+                        .collect::<Result<Vec<Corner>, LoadError>>()?;
+                    if corners.len() < 3 {
+                        return Err(LoadError::parse(
+                            path,
+                            line,
+                            "a face needs at least 3 vertices",
+                        ));
+                    }
+                    let slot = match group.materials.iter().position(|m| *m == material) {
+                        Some(slot) => slot,
+                        None => {
+                            group.materials.push(material);
+                            group.materials.len() - 1
+                        }
+                    };
+                    let indices: Vec<usize> = corners
+                        .into_iter()
+                        .map(|c| group.vertex(c, &data))
+                        .collect();
+                    for k in 1..indices.len() - 1 {
+                        group.faces.push([indices[0], indices[k], indices[k + 1]]);
+                        group.face_materials.push(slot);
+                    }
+                }
+                "g" | "o" => {
+                    let name = tokens.collect::<Vec<_>>().join(" ");
+                    let previous = std::mem::replace(&mut group, GroupBuilder::new(&name));
+                    if !previous.faces.is_empty() {
+                        groups.push(previous.finish(&library, &textures));
+                    }
+                }
+                "usemtl" => {
+                    let name = tokens.collect::<Vec<_>>().join(" ");
+                    material = match library.iter().position(|m| m.name == name) {
+                        Some(index) => Some(index),
+                        None => {
+                            return Err(LoadError::parse(
+                                path,
+                                line,
+                                format!("unknown material '{}'", name),
+                            ))
+                        }
+                    };
+                }
+                "mtllib" => {
+                    for file in tokens {
+                        for material in load_mtl(dir.join(file))? {
+                            textures.push(material.diffuse_texture()?);
+                            library.push(material);
+                        }
+                    }
+                }
+                // smoothing groups, lines, points, free-form geometry and
+                // rendering attributes have no equivalent
+                _ => {}
+            }
+        }
+        if !group.faces.is_empty() {
+            groups.push(group.finish(&library, &textures));
+        }
+        Ok(ObjModel { groups })
+    }
+
+    /// Read and parse an OBJ file
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<ObjModel, LoadError> {
+        let source = read_to_string(&path)?;
+        ObjModel::parse(&source, path.as_ref())
+    }
+
+    /// Add every group of the model to `world`
+    pub fn add_to(self, world: &mut World) {
+        for group in self.groups {
+            world.add(group.mesh);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::hittable::Hittable;
+    use crate::ray::Ray;
+    use crate::sampler::Sampler;
+    use crate::util::TempDir;
+
+    const CUBE_FACES: &str = "\
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0
+vt 0 0
+vt 1 0
+vt 1 1
+vn 0 0 1
+g front
+f 1/1/1 2/2/1 3/3/1 4/1/1
+g back
+f -1 -2 -3
+";
+
+    #[test]
+    fn parse_test() {
+        let model = ObjModel::parse(CUBE_FACES, Path::new("cube.obj")).unwrap();
+        assert_eq!(model.groups.len(), 2);
+        let front = &model.groups[0];
+        assert_eq!(front.name, "front");
+        assert_eq!(front.mesh.faces(), &[[0, 1, 2], [0, 2, 3]]);
+        assert_eq!(front.mesh.normals().map(|n| n.len()), Some(4));
+        assert_eq!(front.mesh.uvs().unwrap()[2], (1.0, 1.0));
+
+        let back = &model.groups[1];
+        assert_eq!(back.mesh.positions()[0], Vec3::new(0.0, 1.0, 0.0));
+        assert!(back.mesh.normals().is_none());
+
+        let r = Ray::new(Vec3::new(0.5, 0.5, 1.0), Vec3::new(0.0, 0.0, -1.0));
+        assert_eq!(front.mesh.hit(&r, 0.001, f64::MAX).map(|h| h.t), Some(1.0));
+    }
+
+    #[test]
+    fn error_test() {
+        let error = |source: &str| {
+            ObjModel::parse(source, Path::new("bad.obj"))
+                .err()
+                .unwrap()
+                .to_string()
+        };
+        assert_eq!(
+            error("v 0 0 0\nv 1 0\n"),
+            "bad.obj:2: expected 3 coordinates"
+        );
+        assert_eq!(
+            error("v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 4\n"),
+            "bad.obj:4: index 4 out of range, 3 defined"
+        );
+        assert_eq!(
+            error("v 0 0 0\nf 1 1\n"),
+            "bad.obj:2: a face needs at least 3 vertices"
+        );
+        assert_eq!(error("usemtl gold\n"), "bad.obj:1: unknown material 'gold'");
+        assert_eq!(error("v 0 0 zero\n"), "bad.obj:1: invalid number 'zero'");
+        assert_eq!(
+            error("vn 0 0 1\nvn 0 0 0\n"),
+            "bad.obj:2: a normal needs a finite non-zero length"
+        );
+    }
+
+    #[test]
+    fn unreadable_texture_test() {
+        let temp = TempDir::new("obj_texture_test");
+        let dir = temp.path();
+        std::fs::write(dir.join("wood.jpg"), "not decoded").unwrap();
+        std::fs::write(
+            dir.join("lib.mtl"),
+            "newmtl wood\nKd 0.6 0.4 0.2\nmap_Kd wood.jpg\n\
+             newmtl lost\nKd 0.1 0.2 0.3\nmap_Kd missing.png\n",
+        )
+        .unwrap();
+        let source = "mtllib lib.mtl\nv 0 0 0\nv 1 0 0\nv 0 1 0\nv 1 1 0\n\
+                      usemtl wood\nf 1 2 3\nusemtl lost\nf 2 4 3\n";
+        let model = ObjModel::parse(source, &dir.join("model.obj")).unwrap();
+
+        // both materials keep their diffuse color
+        let mut sampler = Sampler::new(1, 0);
+        let colors: Vec<Vec3> = [0.2, 0.8]
+            .iter()
+            .map(|&x| {
+                let r = Ray::new(Vec3::new(x, x, 1.0), Vec3::new(0.0, 0.0, -1.0));
+                let hit = model.groups[0].mesh.hit(&r, 0.001, f64::MAX).unwrap();
+                hit.material.sample(&r, &hit, &mut sampler).unwrap().weight
+            })
+            .collect();
+        assert_eq!(colors, [Vec3::new(0.6, 0.4, 0.2), Vec3::new(0.1, 0.2, 0.3)]);
+    }
+}
@@ -0,0 +1,620 @@
+//! Stanford polygon file (`.ply`) parser.
+//!
+//! All three encodings are supported: `ascii`, `binary_little_endian` and
+//! `binary_big_endian`. The `vertex` element provides positions and
+//! optionally normals (`nx`, `ny`, `nz`), colors (`red`, `green`, `blue`)
+//! and texture coordinates (`u`, `v` or `s`, `t`), the `face` element lists
+//! the vertices of polygons which are triangulated as fans. Any other
+//! element or property is skipped.
+use super::LoadError;
+use crate::hittable::TriangleMesh;
+use crate::materials::{Lambertian, Materialable};
+use crate::vec3::Vec3;
+use std::path::Path;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Encoding {
+    Ascii,
+    LittleEndian,
+    BigEndian,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ScalarType {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+}
+
+impl ScalarType {
+    fn from_name(name: &str) -> Option<ScalarType> {
+        match name {
+            "char" | "int8" => Some(ScalarType::I8),
+            "uchar" | "uint8" => Some(ScalarType::U8),
+            "short" | "int16" => Some(ScalarType::I16),
+            "ushort" | "uint16" => Some(ScalarType::U16),
+            "int" | "int32" => Some(ScalarType::I32),
+            "uint" | "uint32" => Some(ScalarType::U32),
+            "float" | "float32" => Some(ScalarType::F32),
+            "double" | "float64" => Some(ScalarType::F64),
+            _ => None,
+        }
+    }
+
+    fn size(self) -> usize {
+        match self {
+            ScalarType::I8 | ScalarType::U8 => 1,
+            ScalarType::I16 | ScalarType::U16 => 2,
+            ScalarType::I32 | ScalarType::U32 | ScalarType::F32 => 4,
+            ScalarType::F64 => 8,
+        }
+    }
+
+    /// Whether values are normalized to `[0, 1]` when used as colors
+    fn is_float(self) -> bool {
+        matches!(self, ScalarType::F32 | ScalarType::F64)
+    }
+}
+
+#[derive(Debug)]
+enum PropertyKind {
+    Scalar(ScalarType),
+    List { count: ScalarType, item: ScalarType },
+}
+
+#[derive(Debug)]
+struct Property {
+    name: String,
+    kind: PropertyKind,
+}
+
+#[derive(Debug)]
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+/// The value of a property for one instance of an element
+enum Value {
+    Scalar(f64),
+    List(Vec<f64>),
+}
+
+/// Reads the values of element instances from the body of the file
+trait BodyReader {
+    fn read_scalar(&mut self, ty: ScalarType) -> Result<f64, String>;
+    /// Called after every instance of an element
+    fn end_instance(&mut self) -> Result<(), String>;
+    /// Line, or byte offset for binary files, errors are reported at
+    fn position(&self) -> usize;
+}
+
+struct AsciiReader<'a> {
+    lines: std::iter::Enumerate<std::str::Lines<'a>>,
+    line: usize,
+    tokens: Vec<&'a str>,
+    next_token: usize,
+}
+
+impl<'a> AsciiReader<'a> {
+    fn new(body: &'a str) -> AsciiReader<'a> {
+        AsciiReader {
+            lines: body.lines().enumerate(),
+            line: 0,
+            tokens: Vec::new(),
+            next_token: 0,
+        }
+    }
+}
+
+impl<'a> BodyReader for AsciiReader<'a> {
+    fn read_scalar(&mut self, _ty: ScalarType) -> Result<f64, String> {
+        // every instance starts on a new line
+        while self.next_token == self.tokens.len() {
+            if self.next_token > 0 {
+                return Err(String::from("missing values"));
+            }
+            match self.lines.next() {
+                Some((i, line)) => {
+                    self.line = self.line.max(i);
+                    self.tokens = line.split_whitespace().collect();
+                }
+                None => return Err(String::from("unexpected end of file")),
+            }
+        }
+        let token = self.tokens[self.next_token];
+        self.next_token += 1;
+        token
+            .parse()
+            .map_err(|_| format!("invalid number '{}'", token))
+    }
+
+    fn end_instance(&mut self) -> Result<(), String> {
+        if self.next_token < self.tokens.len() {
+            return Err(String::from("too many values"));
+        }
+        self.tokens.clear();
+        self.next_token = 0;
+        Ok(())
+    }
+
+    fn position(&self) -> usize {
+        self.line
+    }
+}
+
+struct BinaryReader<'a> {
+    data: &'a [u8],
+    offset: usize,
+    big_endian: bool,
+}
+
+impl<'a> BodyReader for BinaryReader<'a> {
+    fn read_scalar(&mut self, ty: ScalarType) -> Result<f64, String> {
+        let size = ty.size();
+        let bytes = self
+            .data
+            .get(self.offset..self.offset + size)
+            .ok_or_else(|| String::from("unexpected end of file"))?;
+        self.offset += size;
+        let mut buffer = [0u8; 8];
+        buffer[..size].copy_from_slice(bytes);
+        if self.big_endian {
+            buffer[..size].reverse();
+        }
+        let value = match ty {
+            ScalarType::I8 => buffer[0] as i8 as f64,
+            ScalarType::U8 => buffer[0] as f64,
+            ScalarType::I16 => i16::from_le_bytes([buffer[0], buffer[1]]) as f64,
+            ScalarType::U16 => u16::from_le_bytes([buffer[0], buffer[1]]) as f64,
+            ScalarType::I32 => {
+                i32::from_le_bytes([buffer[0], buffer[1], buffer[2], buffer[3]]) as f64
+            }
+            ScalarType::U32 => {
+                u32::from_le_bytes([buffer[0], buffer[1], buffer[2], buffer[3]]) as f64
+            }
+            ScalarType::F32 => {
+                f32::from_le_bytes([buffer[0], buffer[1], buffer[2], buffer[3]]) as f64
+            }
+            ScalarType::F64 => f64::from_le_bytes(buffer),
+        };
+        Ok(value)
+    }
+
+    fn end_instance(&mut self) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn position(&self) -> usize {
+        self.offset
+    }
+}
+
+/// The geometry found in a PLY file
+pub struct PlyModel {
+    pub positions: Vec<Vec3>,
+    pub normals: Option<Vec<Vec3>>,
+    /// per vertex colors in `[0, 1]`
+    pub colors: Option<Vec<Vec3>>,
+    pub uvs: Option<Vec<(f64, f64)>>,
+    pub faces: Vec<[usize; 3]>,
+}
+
+struct Header {
+    encoding: Encoding,
+    elements: Vec<Element>,
+    /// number of lines of the header, `end_header` included
+    lines: usize,
+    /// number of bytes of the header
+    size: usize,
+}
+
+fn parse_header(data: &[u8], path: &Path) -> Result<Header, LoadError> {
+    let mut encoding = None;
+    let mut elements: Vec<Element> = Vec::new();
+    let mut offset = 0;
+    let mut line = 0;
+    loop {
+        let end = data[offset..]
+            .iter()
+            .position(|&b| b == b'\n')
+            .ok_or_else(|| LoadError::parse(path, line + 1, "missing end_header"))?;
+        let raw = String::from_utf8_lossy(&data[offset..offset + end]).into_owned();
+        offset += end + 1;
+        line += 1;
+
+        let tokens: Vec<&str> = raw.split_whitespace().collect();
+        let error = |message: &str| Err(LoadError::parse(path, line, message));
+        if line == 1 {
+            if tokens != ["ply"] {
+                return error("not a PLY file");
+            }
+            continue;
+        }
+        match tokens[..] {
+            [] => {}
+            ["comment", ..] | ["obj_info", ..] => {}
+            ["format", format, _version] => {
+                encoding = Some(match format {
+                    "ascii" => Encoding::Ascii,
+                    "binary_little_endian" => Encoding::LittleEndian,
+                    "binary_big_endian" => Encoding::BigEndian,
+                    _ => return error("unknown format"),
+                })
+            }
+            ["element", name, count] => match count.parse() {
+                Ok(count) => elements.push(Element {
+                    name: name.to_string(),
+                    count,
+                    properties: Vec::new(),
+                }),
+                Err(_) => return error("invalid element count"),
+            },
+            ["property", "list", count, item, name] => {
+                let kind = match (ScalarType::from_name(count), ScalarType::from_name(item)) {
+                    (Some(count), Some(item)) if !count.is_float() => {
+                        PropertyKind::List { count, item }
+                    }
+                    _ => return error("invalid list property type"),
+                };
+                match elements.last_mut() {
+                    Some(element) => element.properties.push(Property {
+                        name: name.to_string(),
+                        kind,
+                    }),
+                    None => return error("property before any element"),
+                }
+            }
+            ["property", ty, name] => {
+                let kind = match ScalarType::from_name(ty) {
+                    Some(ty) => PropertyKind::Scalar(ty),
+                    None => return error("invalid property type"),
+                };
+                match elements.last_mut() {
+                    Some(element) => element.properties.push(Property {
+                        name: name.to_string(),
+                        kind,
+                    }),
+                    None => return error("property before any element"),
+                }
+            }
+            ["end_header"] => break,
+            _ => return error(&format!("unexpected header line '{}'", raw.trim())),
+        }
+    }
+    match encoding {
+        Some(encoding) => Ok(Header {
+            encoding,
+            elements,
+            lines: line,
+            size: offset,
+        }),
+        None => Err(LoadError::parse(path, line, "missing format")),
+    }
+}
+
+fn read_instance(element: &Element, reader: &mut dyn BodyReader) -> Result<Vec<Value>, String> {
+    let mut values = Vec::with_capacity(element.properties.len());
+    for property in &element.properties {
+        values.push(match property.kind {
+            PropertyKind::Scalar(ty) => Value::Scalar(reader.read_scalar(ty)?),
+            PropertyKind::List { count, item } => {
+                let n = reader.read_scalar(count)?;
+                if n < 0.0 {
+                    return Err(String::from("negative list length"));
+                }
+                let items = (0..n as usize)
+                    .map(|_| reader.read_scalar(item))
+                    .collect::<Result<Vec<f64>, String>>()?;
+                Value::List(items)
+            }
+        });
+    }
+    reader.end_instance()?;
+    Ok(values)
+}
+
+/// Index of the first scalar property named like one of `names`
+fn find_scalar(element: &Element, names: &[&str]) -> Option<usize> {
+    element
+        .properties
+        .iter()
+        .position(|p| matches!(p.kind, PropertyKind::Scalar(_)) && names.contains(&p.name.as_str()))
+}
+
+impl PlyModel {
+    /// Parse the content of the PLY file found at `path`
+    pub fn parse(data: &[u8], path: &Path) -> Result<PlyModel, LoadError> {
+        let header = parse_header(data, path)?;
+        let body = &data[header.size..];
+        let text;
+        let mut ascii;
+        let mut binary;
+        let reader: &mut dyn BodyReader = match header.encoding {
+            Encoding::Ascii => {
+                text = String::from_utf8_lossy(body);
+                ascii = AsciiReader::new(&text);
+                &mut ascii
+            }
+            Encoding::LittleEndian | Encoding::BigEndian => {
+                binary = BinaryReader {
+                    data: body,
+                    offset: 0,
+                    big_endian: header.encoding == Encoding::BigEndian,
+                };
+                &mut binary
+            }
+        };
+        let error_at = |reader: &dyn BodyReader, message: String| match header.encoding {
+            Encoding::Ascii => {
+                LoadError::parse(path, header.lines + reader.position() + 1, message)
+            }
+            _ => LoadError::parse(
+                path,
+                header.lines + 1,
+                format!("{} at byte {}", message, header.size + reader.position()),
+            ),
+        };
+
+        let mut model = PlyModel {
+            positions: Vec::new(),
+            normals: None,
+            colors: None,
+            uvs: None,
+            faces: Vec::new(),
+        };
+        for element in &header.elements {
+            let scalar_slots = |names: &[&[&str]]| -> Option<Vec<usize>> {
+                names.iter().map(|n| find_scalar(element, n)).collect()
+            };
+            let position = scalar_slots(&[&["x"], &["y"], &["z"]]);
+            let normal = scalar_slots(&[&["nx"], &["ny"], &["nz"]]);
+            let color = scalar_slots(&[
+                &["red", "r", "diffuse_red"],
+                &["green", "g", "diffuse_green"],
+                &["blue", "b", "diffuse_blue"],
+            ]);
+            let uv = scalar_slots(&[&["u", "s", "texture_u"], &["v", "t", "texture_v"]]);
+            let indices = element.properties.iter().position(|p| {
+                matches!(p.kind, PropertyKind::List { .. })
+                    && (p.name == "vertex_indices" || p.name == "vertex_index")
+            });
+            let color_scale = match &color {
+                Some(slots) => match element.properties[slots[0]].kind {
+                    PropertyKind::Scalar(ty) if !ty.is_float() => 1.0 / 255.0,
+                    _ => 1.0,
+                },
+                None => 1.0,
+            };
+
+            let is_vertex = element.name == "vertex";
+            if is_vertex && position.is_none() {
+                return Err(LoadError::parse(
+                    path,
+                    header.lines,
+                    "vertex element without x, y and z",
+                ));
+            }
+            let mut normals = Vec::new();
+            let mut colors = Vec::new();
+            let mut uvs = Vec::new();
+            for _ in 0..element.count {
+                let values = read_instance(element, reader).map_err(|e| error_at(reader, e))?;
+                let scalar = |slot: usize| match values[slot] {
+                    Value::Scalar(x) => x,
+                    Value::List(_) => unreachable!("slots only point to scalar properties"),
+                };
+                let vector = |slots: &[usize]| {
+                    Vec3::new(scalar(slots[0]), scalar(slots[1]), scalar(slots[2]))
+                };
+                if is_vertex {
+                    model.positions.push(vector(position.as_ref().unwrap()));
+                    if let Some(slots) = &normal {
+                        normals.push(vector(slots).make_unit_vector());
+                    }
+                    if let Some(slots) = &color {
+                        colors.push(color_scale * vector(slots));
+                    }
+                    if let Some(slots) = &uv {
+                        uvs.push((scalar(slots[0]), scalar(slots[1])));
+                    }
+                } else if element.name == "face" {
+                    let polygon = match indices.map(|slot| &values[slot]) {
+                        Some(Value::List(polygon)) => polygon,
+                        _ => {
+                            return Err(error_at(
+                                reader,
+                                String::from("face without vertex_indices"),
+                            ))
+                        }
+                    };
+                    if polygon.len() < 3 {
+                        return Err(error_at(
+                            reader,
+                            String::from("a face needs at least 3 vertices"),
+                        ));
+                    }
+                    if let Some(index) = polygon.iter().find(|i| **i < 0.0 || i.fract() != 0.0) {
+                        return Err(error_at(reader, format!("invalid vertex index {}", index)));
+                    }
+                    for k in 1..polygon.len() - 1 {
+                        model.faces.push([
+                            polygon[0] as usize,
+                            polygon[k] as usize,
+                            polygon[k + 1] as usize,
+                        ]);
+                    }
+                }
+            }
+            if is_vertex {
+                model.normals = normal.map(|_| normals);
+                model.colors = color.map(|_| colors);
+                model.uvs = uv.map(|_| uvs);
+            }
+        }
+
+        let vertex_count = model.positions.len();
+        if let Some(face) = model
+            .faces
+            .iter()
+            .find(|f| f.iter().any(|&i| i >= vertex_count))
+        {
+            return Err(LoadError::parse(
+                path,
+                header.lines,
+                format!(
+                    "face {:?} references a missing vertex, {} defined",
+                    face, vertex_count
+                ),
+            ));
+        }
+        Ok(model)
+    }
+
+    /// Read and parse a PLY file
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<PlyModel, LoadError> {
+        let data = std::fs::read(&path).map_err(|error| LoadError::Io {
+            path: path.as_ref().to_path_buf(),
+            error,
+        })?;
+        PlyModel::parse(&data, path.as_ref())
+    }
+
+    /// Build a mesh made of `material`, colors are kept as vertex colors
+    pub fn into_mesh(self, material: Box<dyn Materialable>) -> Box<TriangleMesh> {
+        let face_materials = vec![0; self.faces.len()];
+        let mut mesh =
+            TriangleMesh::new(self.positions, self.faces, vec![material], face_materials);
+        if let Some(normals) = self.normals {
+            mesh.set_normals(normals);
+        }
+        if let Some(colors) = self.colors {
+            mesh.set_colors(colors);
+        }
+        if let Some(uvs) = self.uvs {
+            mesh.set_uvs(uvs);
+        }
+        mesh
+    }
+
+    /// Build a diffuse mesh whose albedo is given by the vertex colors, or a
+    /// light grey when the file has none
+    pub fn into_vertex_colored_mesh(self) -> Box<TriangleMesh> {
+        self.into_mesh(Lambertian::vertex_colored(0.8, 0.8, 0.8))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const ASCII: &str = "\
+ply
+format ascii 1.0
+comment a colored quad
+element vertex 4
+property float x
+property float y
+property float z
+property uchar red
+property uchar green
+property uchar blue
+element face 1
+property list uchar int vertex_indices
+end_header
+0 0 0 255 0 0
+1 0 0 0 255 0
+1 1 0 0 0 255
+0 1 0 255 255 255
+4 0 1 2 3
+";
+
+    fn binary(big_endian: bool) -> Vec<u8> {
+        let format = if big_endian {
+            "binary_big_endian"
+        } else {
+            "binary_little_endian"
+        };
+        let mut data = format!(
+            "ply\nformat {} 1.0\nelement vertex 3\nproperty float x\nproperty float y\n\
+             property float z\nproperty float nx\nproperty float ny\nproperty float nz\n\
+             element face 1\nproperty list uchar uint vertex_indices\nend_header\n",
+            format
+        )
+        .into_bytes();
+        let vertices = [[0.0f32, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]];
+        for v in &vertices {
+            for x in v.iter().chain(&[0.0, 0.0, 1.0]) {
+                data.extend_from_slice(&if big_endian {
+                    x.to_be_bytes()
+                } else {
+                    x.to_le_bytes()
+                });
+            }
+        }
+        data.push(3);
+        for i in 0..3u32 {
+            data.extend_from_slice(&if big_endian {
+                i.to_be_bytes()
+            } else {
+                i.to_le_bytes()
+            });
+        }
+        data
+    }
+
+    #[test]
+    fn ascii_test() {
+        let model = PlyModel::parse(ASCII.as_bytes(), Path::new("quad.ply")).unwrap();
+        assert_eq!(model.positions.len(), 4);
+        assert_eq!(model.faces, vec![[0, 1, 2], [0, 2, 3]]);
+        assert_eq!(model.colors.unwrap()[1], Vec3::new(0.0, 1.0, 0.0));
+        assert!(model.normals.is_none());
+    }
+
+    #[test]
+    fn binary_test() {
+        for &big_endian in &[false, true] {
+            let model = PlyModel::parse(&binary(big_endian), Path::new("tri.ply")).unwrap();
+            assert_eq!(model.positions[1], Vec3::new(1.0, 0.0, 0.0));
+            assert_eq!(model.normals.unwrap()[2], Vec3::new(0.0, 0.0, 1.0));
+            assert_eq!(model.faces, vec![[0, 1, 2]]);
+        }
+    }
+
+    #[test]
+    fn error_test() {
+        let error = |data: &[u8]| {
+            PlyModel::parse(data, Path::new("bad.ply"))
+                .err()
+                .unwrap()
+                .to_string()
+        };
+        assert_eq!(
+            error(ASCII.replace("1 1 0 0 0 255", "1 1 0 0 x 255").as_bytes()),
+            "bad.ply:16: invalid number 'x'"
+        );
+        assert_eq!(
+            error(b"ply\nformat ascii 1.0\nelement vertex two\n"),
+            "bad.ply:3: invalid element count"
+        );
+        let mut truncated = binary(false);
+        truncated.truncate(truncated.len() - 2);
+        assert!(error(&truncated).starts_with("bad.ply:13: unexpected end of file"));
+        assert!(error(ASCII.replace("4 0 1 2 3", "3 0 1 7").as_bytes()).contains("missing vertex"));
+        assert_eq!(
+            error(ASCII.replace("4 0 1 2 3", "3 -1 1 2").as_bytes()),
+            "bad.ply:18: invalid vertex index -1"
+        );
+        assert_eq!(
+            error(ASCII.replace("4 0 1 2 3", "3 0 1.7 2").as_bytes()),
+            "bad.ply:18: invalid vertex index 1.7"
+        );
+    }
+}
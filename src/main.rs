@@ -1,138 +1,125 @@
-mod camera;
-mod hittable;
-mod materials;
-mod ray;
-mod scenes;
-mod util;
-mod vec3;
+mod cli;
 
-use camera::Camera;
-use hittable::World;
+use cli::CliError;
+#[cfg(feature = "window")]
 use minifb::{Key, Window, WindowOptions};
-use num_cpus;
-use rand::prelude::*;
-use ray::Ray;
-use std::sync::Arc;
+use rustracer::{image, scenes, Renderer};
+use std::process;
 use std::time;
-use vec3::Vec3;
 
-const WIDTH: usize = 800;
-const HEIGHT: usize = 600;
-
-fn color(r: &Ray, world: &World, depth: u32, rng: &mut ThreadRng) -> Vec3 {
-    if let Some(record) = world.hit(r, 0.001, std::f64::MAX) {
-        if depth >= 50 {
-            return Vec3::zeros();
-        }
-        if let Some(scatter) = record.material.scatter(r, &record, rng) {
-            scatter.attenuation * color(&scatter.ray, world, depth + 1, rng)
-        } else {
-            Vec3::zeros()
-        }
-    } else {
-        // blue background
-        let unit_direction = r.direction.make_unit_vector();
-        let t = 0.5 * (unit_direction.y + 1.0);
-        util::lerp(Vec3::all(1.0), Vec3::new(0.5, 0.7, 1.0), t)
-    }
-}
-
-fn main() {
-    let timer = time::Instant::now();
+#[cfg(feature = "window")]
+fn open_window(width: usize, height: usize) -> Window {
     let mut window = Window::new(
         "Test - EST to exit",
-        WIDTH,
-        HEIGHT,
+        width,
+        height,
         WindowOptions::default(),
     )
     .unwrap_or_else(|e| {
-        panic!("{}", e);
+        eprintln!("error: could not open a window ({}), try --headless", e);
+        process::exit(1);
     });
 
     window.limit_update_rate(Some(std::time::Duration::from_micros(16600)));
+    window
+}
 
-    // let world = scenes::simple::load();
-    let world = scenes::sphere_sea::load();
+/// Render one sample per pixel at a time and show the refined image after each
+/// pass, until every sample is done or the window is closed.
+#[cfg(feature = "window")]
+fn render_progressively(renderer: &mut Renderer, window: &mut Window, samples_per_pixel: u32) {
+    while !renderer.is_done() && window.is_open() && !window.is_key_down(Key::Escape) {
+        renderer.render_pass(1);
+        window.set_title(&format!(
+            "{}/{} spp - ESC to exit",
+            renderer.samples(),
+            samples_per_pixel
+        ));
+        let frame = renderer.frame();
+        window
+            .update_with_buffer(&frame.to_packed_rgb(), frame.width, frame.height)
+            .unwrap();
+    }
+}
 
-    let aspect_ratio = WIDTH as f64 / HEIGHT as f64;
-    let eye = Vec3::new(13.0, 2.0, 3.0);
-    // let eye = Vec3::zeros();
-    let lookto = Vec3::new(0.0, 0.0, 0.0);
-    // let focus_dist = (eye - lookto).length();
-    let camera = Camera::new(
-        eye,
-        lookto,
-        Vec3::new(0.0, 1.0, 0.0),
-        20.0,
-        aspect_ratio,
-        0.5,
-        10.0,
-    );
+fn exit_with(e: CliError) -> ! {
+    match e {
+        CliError::Help => {
+            print!("{}", e);
+            process::exit(0);
+        }
+        CliError::Invalid(_) => {
+            eprintln!("{}\n\n{}", e, cli::USAGE);
+            process::exit(2);
+        }
+    }
+}
 
-    // split the image into num_rows rows each with height row_height
-    let (row_height, num_row) = {
-        let num_cores = num_cpus::get();
-        let threads_per_core = 2;
-        let num_row = num_cores * threads_per_core;
-        let row_height = HEIGHT / num_row;
-        (row_height, num_row)
+fn main() {
+    let arguments = cli::parse(std::env::args().skip(1)).unwrap_or_else(|e| exit_with(e));
+
+    let timer = time::Instant::now();
+    let scene = match scenes::load(&arguments.scene) {
+        Some(scene) => scene,
+        None => scenes::load_file(&arguments.scene).unwrap_or_else(|e| {
+            eprintln!("error: {}", e);
+            process::exit(1);
+        }),
     };
-    println!(
-        "The image would be rendered with {} threads each with height {}.",
-        num_row, row_height
-    );
 
-    let (sender, receiver) = std::sync::mpsc::channel();
-    let world_ptr = Arc::new(world);
-    let camera_ptr = Arc::new(camera);
-    for i in 0..num_row {
-        let upper_bound = HEIGHT - i * row_height;
-        let lower_bound = {
-            let y = HEIGHT - (i + 1) * row_height;
-            if y < row_height {
-                0
-            } else {
-                y
-            }
-        };
+    // the command line overrides the settings and camera of the scene
+    let defaults = cli::Options {
+        settings: scene.settings,
+        camera: scene.camera.unwrap_or_default(),
+        ..cli::Options::default()
+    };
+    let options = arguments.apply(defaults).unwrap_or_else(|e| exit_with(e));
+    let settings = &options.settings;
+    let world = scene.world;
+    let camera = options.camera.build(settings.aspect_ratio());
 
-        let sx = sender.clone();
-        let world_arc = world_ptr.clone();
-        let camera_arc = camera_ptr.clone();
-        let ns = 100;
-        let task_number = i;
-        std::thread::spawn(move || {
-            let mut buffer: Vec<u32> = Vec::new();
-            let mut rng = thread_rng();
-            for j in (lower_bound..upper_bound).rev() {
-                for i in 0..WIDTH {
-                    let mut c = Vec3::zeros();
-                    for _ in 0..ns {
-                        let v: f64 = (j as f64 + rng.gen_range(0.0, 1.0)) / HEIGHT as f64;
-                        let u: f64 = (i as f64 + rng.gen_range(0.0, 1.0)) / WIDTH as f64;
-                        let ray = camera_arc.get_ray(u, v, &mut rng);
-                        let lc = color(&ray, &world_arc, 0, &mut rng);
-                        c += lc;
-                    }
-                    c /= ns as f64;
-                    // gamma correction
-                    c = Vec3::new(c.x.sqrt(), c.y.sqrt(), c.z.sqrt());
-                    buffer.push(util::from_u8_rgb(c));
-                }
-            }
-            sx.send((task_number, buffer)).unwrap();
-        });
-    }
+    #[cfg(feature = "window")]
+    let mut window = if options.headless {
+        None
+    } else {
+        Some(open_window(settings.width, settings.height))
+    };
 
-    let mut buffs: Vec<Vec<u32>> = vec![vec![]; num_row];
-    for (i, buf) in receiver.iter().take(num_row) {
-        buffs[i] = buf;
+    let mut renderer = Renderer::new(&world, &camera, settings);
+    #[cfg(feature = "window")]
+    match window.as_mut() {
+        Some(window) => render_progressively(&mut renderer, window, settings.samples_per_pixel),
+        None => renderer.render_pass(settings.samples_per_pixel),
     }
+    #[cfg(not(feature = "window"))]
+    renderer.render_pass(settings.samples_per_pixel);
+
+    let frame = renderer.frame();
+    println!(
+        "Image rendered with {} samples per pixel in {}s",
+        renderer.samples(),
+        timer.elapsed().as_secs()
+    );
 
-    let buffer = buffs.concat();
-    println!("Image rendered in {}s", timer.elapsed().as_secs());
+    if let Some(path) = &options.output {
+        let written = match options.format {
+            Some(format) => image::save_as(&frame, path, format),
+            None => image::save(&frame, path),
+        };
+        if let Err(e) = written {
+            eprintln!("error: could not write {}: {}", path.display(), e);
+            process::exit(1);
+        }
+        println!("Image written to {}", path.display());
+    }
 
-    while window.is_open() && !window.is_key_down(Key::Escape) {
-        window.update_with_buffer(&buffer, WIDTH, HEIGHT).unwrap();
+    #[cfg(feature = "window")]
+    if let Some(mut window) = window {
+        let buffer = frame.to_packed_rgb();
+        while window.is_open() && !window.is_key_down(Key::Escape) {
+            window
+                .update_with_buffer(&buffer, frame.width, frame.height)
+                .unwrap();
+        }
     }
 }
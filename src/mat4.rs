@@ -0,0 +1,237 @@
+use crate::vec3::Vec3;
+use std::ops::Mul;
+
+/// A 4x4 matrix acting on points and vectors in homogeneous coordinates,
+/// stored row by row
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Mat4 {
+    pub m: [[f64; 4]; 4],
+}
+
+impl Mat4 {
+    pub fn new(m: [[f64; 4]; 4]) -> Mat4 {
+        Mat4 { m }
+    }
+
+    pub fn identity() -> Mat4 {
+        let mut m = [[0.0; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            row[i] = 1.0;
+        }
+        Mat4 { m }
+    }
+
+    /// Build a matrix from its values listed column by column, as glTF does
+    pub fn from_column_major(values: &[f64; 16]) -> Mat4 {
+        let mut m = [[0.0; 4]; 4];
+        for (i, x) in values.iter().enumerate() {
+            m[i % 4][i / 4] = *x;
+        }
+        Mat4 { m }
+    }
+
+    pub fn translation(t: Vec3) -> Mat4 {
+        let mut m = Mat4::identity();
+        m.m[0][3] = t.x;
+        m.m[1][3] = t.y;
+        m.m[2][3] = t.z;
+        m
+    }
+
+    pub fn scaling(s: Vec3) -> Mat4 {
+        let mut m = Mat4::identity();
+        m.m[0][0] = s.x;
+        m.m[1][1] = s.y;
+        m.m[2][2] = s.z;
+        m
+    }
+
+    /// Counter-clockwise rotation of `degrees` around `axis`
+    pub fn rotation(axis: Vec3, degrees: f64) -> Mat4 {
+        let a = axis.make_unit_vector();
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        let k = 1.0 - cos;
+        Mat4::new([
+            [
+                cos + a.x * a.x * k,
+                a.x * a.y * k - a.z * sin,
+                a.x * a.z * k + a.y * sin,
+                0.0,
+            ],
+            [
+                a.y * a.x * k + a.z * sin,
+                cos + a.y * a.y * k,
+                a.y * a.z * k - a.x * sin,
+                0.0,
+            ],
+            [
+                a.z * a.x * k - a.y * sin,
+                a.z * a.y * k + a.x * sin,
+                cos + a.z * a.z * k,
+                0.0,
+            ],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    /// The rotation described by the unit quaternion `(x, y, z, w)`
+    pub fn from_quaternion(x: f64, y: f64, z: f64, w: f64) -> Mat4 {
+        Mat4::new([
+            [
+                1.0 - 2.0 * (y * y + z * z),
+                2.0 * (x * y - z * w),
+                2.0 * (x * z + y * w),
+                0.0,
+            ],
+            [
+                2.0 * (x * y + z * w),
+                1.0 - 2.0 * (x * x + z * z),
+                2.0 * (y * z - x * w),
+                0.0,
+            ],
+            [
+                2.0 * (x * z - y * w),
+                2.0 * (y * z + x * w),
+                1.0 - 2.0 * (x * x + y * y),
+                0.0,
+            ],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    pub fn transpose(&self) -> Mat4 {
+        let mut m = [[0.0; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, x) in row.iter_mut().enumerate() {
+                *x = self.m[j][i];
+            }
+        }
+        Mat4 { m }
+    }
+
+    /// The inverse computed by Gauss-Jordan elimination, `None` when the
+    /// matrix is singular
+    pub fn inverse(&self) -> Option<Mat4> {
+        let mut a = self.m;
+        let mut inv = Mat4::identity().m;
+        for col in 0..4 {
+            // partial pivoting keeps the elimination stable
+            let pivot = (col..4).max_by(|&i, &j| a[i][col].abs().total_cmp(&a[j][col].abs()))?;
+            if a[pivot][col].abs() < 1e-12 {
+                return None;
+            }
+            a.swap(col, pivot);
+            inv.swap(col, pivot);
+            let p = a[col][col];
+            for j in 0..4 {
+                a[col][j] /= p;
+                inv[col][j] /= p;
+            }
+            for row in 0..4 {
+                if row != col {
+                    let f = a[row][col];
+                    for j in 0..4 {
+                        a[row][j] -= f * a[col][j];
+                        inv[row][j] -= f * inv[col][j];
+                    }
+                }
+            }
+        }
+        Some(Mat4 { m: inv })
+    }
+
+    /// The determinant of the upper left 3x3 part, negative for transforms
+    /// which mirror space
+    pub fn determinant3(&self) -> f64 {
+        let m = &self.m;
+        m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+            - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+            + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+    }
+
+    pub fn transform_point(&self, p: Vec3) -> Vec3 {
+        self.transform_vector(p) + Vec3::new(self.m[0][3], self.m[1][3], self.m[2][3])
+    }
+
+    /// Transform a direction, ignoring the translation
+    pub fn transform_vector(&self, v: Vec3) -> Vec3 {
+        let m = &self.m;
+        Vec3::new(
+            m[0][0] * v.x + m[0][1] * v.y + m[0][2] * v.z,
+            m[1][0] * v.x + m[1][1] * v.y + m[1][2] * v.z,
+            m[2][0] * v.x + m[2][1] * v.y + m[2][2] * v.z,
+        )
+    }
+}
+
+impl Default for Mat4 {
+    fn default() -> Mat4 {
+        Mat4::identity()
+    }
+}
+
+impl Mul for Mat4 {
+    type Output = Mat4;
+
+    fn mul(self, rhs: Mat4) -> Mat4 {
+        let mut m = [[0.0; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, x) in row.iter_mut().enumerate() {
+                *x = (0..4).map(|k| self.m[i][k] * rhs.m[k][j]).sum();
+            }
+        }
+        Mat4 { m }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn assert_close(a: Vec3, b: Vec3) {
+        assert!((a - b).length() < 1e-9, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn transform_test() {
+        let m = Mat4::translation(Vec3::new(1.0, 2.0, 3.0)) * Mat4::scaling(Vec3::all(2.0));
+        assert_eq!(m.transform_point(Vec3::all(1.0)), Vec3::new(3.0, 4.0, 5.0));
+        assert_eq!(m.transform_vector(Vec3::all(1.0)), Vec3::all(2.0));
+
+        let r = Mat4::rotation(Vec3::new(0.0, 0.0, 1.0), 90.0);
+        assert_close(
+            r.transform_point(Vec3::new(1.0, 0.0, 0.0)),
+            Vec3::new(0.0, 1.0, 0.0),
+        );
+        let half = std::f64::consts::FRAC_PI_4;
+        let q = Mat4::from_quaternion(0.0, 0.0, half.sin(), half.cos());
+        assert_close(
+            q.transform_point(Vec3::new(1.0, 0.0, 0.0)),
+            Vec3::new(0.0, 1.0, 0.0),
+        );
+    }
+
+    #[test]
+    fn inverse_test() {
+        let m = Mat4::translation(Vec3::new(1.0, -2.0, 3.0))
+            * Mat4::rotation(Vec3::new(1.0, 1.0, 0.0), 30.0)
+            * Mat4::scaling(Vec3::new(2.0, 3.0, -1.0));
+        let p = Vec3::new(0.3, -0.7, 1.1);
+        let inverse = m.inverse().unwrap();
+        assert_close(inverse.transform_point(m.transform_point(p)), p);
+        assert!(m.determinant3() < 0.0);
+        assert_eq!(Mat4::scaling(Vec3::new(1.0, 0.0, 1.0)).inverse(), None);
+        assert_eq!(m.transpose().transpose(), m);
+    }
+
+    #[test]
+    fn column_major_test() {
+        let values: [f64; 16] = std::array::from_fn(|i| i as f64);
+        let m = Mat4::from_column_major(&values);
+        assert_eq!(m.m[0], [0.0, 4.0, 8.0, 12.0]);
+        assert_eq!(
+            m.transform_point(Vec3::zeros()),
+            Vec3::new(12.0, 13.0, 14.0)
+        );
+    }
+}
@@ -1,11 +1,12 @@
 use crate::hittable::HitRecord;
 use crate::ray::Ray;
+use crate::sampler::Sampler;
 use crate::util;
 use crate::vec3::Vec3;
 
-use super::material::{Materialable, Scatter};
-use rand::prelude::*;
+use super::material::{BsdfSample, Materialable};
 
+/// A clear material such as glass, which reflects or refracts rays
 pub struct Dielectrics {
     /// reflected index
     ref_index: f64,
@@ -24,45 +25,83 @@ fn schlick(cosine: f64, ref_index: f64) -> f64 {
 }
 
 impl Materialable for Dielectrics {
-    fn scatter(&self, r: &Ray, hit: &HitRecord, rng: &mut ThreadRng) -> Option<Scatter> {
+    /// Reflect with the probability given by the Fresnel equations, refract
+    /// otherwise
+    fn sample(&self, r: &Ray, hit: &HitRecord, sampler: &mut Sampler) -> Option<BsdfSample> {
+        // rays hitting the back of the surface leave the material
         let (normal, ni_over_nt, cosine) = {
-            if r.direction.dot(hit.n) > 0.0 {
-                (
-                    -hit.n,
-                    self.ref_index,
-                    self.ref_index * r.direction.dot(hit.n) / r.direction.length(),
-                )
-            } else {
+            if hit.front_face {
                 (
                     hit.n,
                     1.0 / self.ref_index,
                     -r.direction.dot(hit.n) / r.direction.length(),
                 )
+            } else {
+                (
+                    -hit.n,
+                    self.ref_index,
+                    self.ref_index * r.direction.dot(hit.n) / r.direction.length(),
+                )
             }
         };
 
-        let reflected = Some(Scatter {
-            ray: Ray::new(hit.p, util::reflect(r.direction, normal)),
-            attenuation: Vec3::all(1.0),
-        });
+        let (refracted, prob) = match util::refract(r.direction, normal, ni_over_nt) {
+            Some(refract) => (Some(refract), schlick(cosine, self.ref_index)),
+            None => (None, 1.0),
+        };
 
-        let (refracted, prob) =
-            if let Some(refract) = util::refract(r.direction, normal, ni_over_nt) {
-                (
-                    Some(Scatter {
-                        ray: Ray::new(hit.p, refract),
-                        attenuation: Vec3::all(1.0),
-                    }),
-                    schlick(cosine, self.ref_index),
-                )
-            } else {
-                (None, 1.0)
-            };
+        let (direction, pdf) = match refracted {
+            Some(refract) if util::random_double(sampler) >= prob => (refract, 1.0 - prob),
+            _ => (util::reflect(r.direction, normal), prob),
+        };
+        Some(BsdfSample {
+            direction: direction.make_unit_vector(),
+            weight: Vec3::all(1.0),
+            pdf,
+            is_delta: true,
+        })
+    }
 
-        if util::random_double(rng) < prob {
-            reflected
-        } else {
-            refracted
-        }
+    fn is_delta(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::hittable::{Hittable, Quad};
+
+    #[test]
+    fn front_face_test() {
+        let quad = Quad::new(
+            Vec3::new(-1.0, -1.0, 0.0),
+            Vec3::new(2.0, 0.0, 0.0),
+            Vec3::new(0.0, 2.0, 0.0),
+            Dielectrics::new(1.5),
+        );
+        let mut sampler = Sampler::new(2, 0);
+        // 60 degrees from the normal, beyond the critical angle inside glass
+        let slope = 3.0_f64.sqrt();
+        let mut sides = |z: f64| {
+            let r = Ray::new(Vec3::new(-slope, 0.0, z), Vec3::new(slope, 0.0, -z));
+            let hit = quad.hit(&r, 0.001, f64::MAX).unwrap();
+            assert_eq!(hit.front_face, z > 0.0);
+            let mut sides = (0, 0);
+            for _ in 0..200 {
+                let sample = hit.material.sample(&r, &hit, &mut sampler).unwrap();
+                if sample.direction.z > 0.0 {
+                    sides.0 += 1;
+                } else {
+                    sides.1 += 1;
+                }
+            }
+            sides
+        };
+        // from outside both reflection and refraction happen
+        let (above, below) = sides(1.0);
+        assert!(above > 0 && below > above);
+        // from inside every ray is reflected back
+        assert_eq!(sides(-1.0), (0, 200));
     }
 }
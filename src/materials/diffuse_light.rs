@@ -0,0 +1,45 @@
+use crate::hittable::HitRecord;
+use crate::ray::Ray;
+use crate::sampler::Sampler;
+use crate::textures::{Constant, Texture};
+use crate::vec3::Vec3;
+use std::sync::Arc;
+
+use super::material::{BsdfSample, Materialable};
+
+/// A light source emitting the same radiance in every direction, from both
+/// sides of the surface
+pub struct DiffuseLight {
+    emit: Arc<dyn Texture>,
+}
+
+impl DiffuseLight {
+    pub fn new(r: f64, g: f64, b: f64) -> Box<DiffuseLight> {
+        DiffuseLight::textured(Constant::new(Vec3::new(r, g, b)))
+    }
+
+    /// A light whose radiance varies over the surface
+    pub fn textured(emit: Arc<dyn Texture>) -> Box<DiffuseLight> {
+        Box::new(DiffuseLight { emit })
+    }
+}
+
+impl Materialable for DiffuseLight {
+    fn sample(
+        &self,
+        _r: &Ray,
+        _hit_record: &HitRecord,
+        _sampler: &mut Sampler,
+    ) -> Option<BsdfSample> {
+        None
+    }
+
+    fn emitted(&self, _r: &Ray, hit_record: &HitRecord) -> Vec3 {
+        let (u, v) = hit_record.uv;
+        self.emit.value(u, v, hit_record.p)
+    }
+
+    fn is_emitter(&self) -> bool {
+        true
+    }
+}
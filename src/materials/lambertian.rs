@@ -1,29 +1,129 @@
 use crate::hittable::HitRecord;
 use crate::ray::Ray;
+use crate::sampler::Sampler;
+use crate::textures::{Constant, Texture};
 use crate::util;
 use crate::vec3::Vec3;
+use std::f64::consts::PI;
+use std::sync::Arc;
 
-use super::material::{Materialable, Scatter};
-use rand::prelude::*;
+use super::material::{facing_normal, BsdfSample, Materialable};
 
+/// A perfectly diffuse surface, reflecting light equally in every direction
+/// of the side it is lit from
 pub struct Lambertian {
-    albedo: Vec3,
+    albedo: Arc<dyn Texture>,
+    /// take the albedo from the vertex colors of the mesh when it has some
+    vertex_colors: bool,
 }
 
 impl Lambertian {
     pub fn new(a: f64, b: f64, c: f64) -> Box<Lambertian> {
+        Lambertian::textured(Constant::new(Vec3::new(a, b, c)))
+    }
+
+    /// A material whose albedo varies over the surface
+    pub fn textured(albedo: Arc<dyn Texture>) -> Box<Lambertian> {
+        Box::new(Lambertian {
+            albedo,
+            vertex_colors: false,
+        })
+    }
+
+    /// A material whose albedo is the vertex color of the hit mesh, falling
+    /// back to `(a, b, c)` on objects without vertex colors
+    pub fn vertex_colored(a: f64, b: f64, c: f64) -> Box<Lambertian> {
         Box::new(Lambertian {
-            albedo: Vec3::new(a, b, c),
+            albedo: Constant::new(Vec3::new(a, b, c)),
+            vertex_colors: true,
         })
     }
+
+    fn albedo(&self, hit_record: &HitRecord) -> Vec3 {
+        match hit_record.color {
+            Some(color) if self.vertex_colors => color,
+            _ => {
+                let (u, v) = hit_record.uv;
+                self.albedo.value(u, v, hit_record.p)
+            }
+        }
+    }
 }
 
 impl Materialable for Lambertian {
-    fn scatter(&self, _r: &Ray, hit_record: &HitRecord, rng: &mut ThreadRng) -> Option<Scatter> {
-        let target = hit_record.p + hit_record.n + util::random_in_unit_sphere(rng);
-        Some(Scatter {
-            ray: Ray::new(hit_record.p, target - hit_record.p),
-            attenuation: self.albedo,
+    /// Directions are picked with a density proportional to their cosine
+    /// with the normal, which cancels out the cosine of the BSDF
+    fn sample(
+        &self,
+        _r: &Ray,
+        hit_record: &HitRecord,
+        sampler: &mut Sampler,
+    ) -> Option<BsdfSample> {
+        let n = facing_normal(hit_record);
+        let (u, v) = util::orthonormal_basis(n);
+        // a point uniformly distributed on the disk, lifted onto the hemisphere
+        let squared_radius = sampler.next_f64();
+        let phi = 2.0 * PI * sampler.next_f64();
+        let radius = squared_radius.sqrt();
+        let height = (1.0 - squared_radius).max(0.0).sqrt();
+        let direction = radius * phi.cos() * u + radius * phi.sin() * v + height * n;
+        Some(BsdfSample {
+            direction,
+            weight: self.albedo(hit_record),
+            pdf: height / PI,
+            is_delta: false,
         })
     }
+
+    fn eval(&self, _r: &Ray, hit_record: &HitRecord, direction: Vec3) -> Vec3 {
+        if direction.dot(facing_normal(hit_record)) > 0.0 {
+            self.albedo(hit_record) / PI
+        } else {
+            Vec3::zeros()
+        }
+    }
+
+    fn pdf(&self, _r: &Ray, hit_record: &HitRecord, direction: Vec3) -> f64 {
+        direction.dot(facing_normal(hit_record)).max(0.0) / PI
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn sample_test() {
+        let material = Lambertian::new(0.5, 0.6, 0.7);
+        let hit = HitRecord {
+            t: 1.0,
+            p: Vec3::zeros(),
+            n: Vec3::new(0.0, 1.0, 0.0),
+            geometric_normal: Vec3::new(0.0, 1.0, 0.0),
+            front_face: false,
+            material: material.as_ref(),
+            color: None,
+            uv: (0.0, 0.0),
+            dpdu: Vec3::new(1.0, 0.0, 0.0),
+            dpdv: Vec3::new(0.0, 0.0, -1.0),
+            object_id: 0,
+            primitive_id: 0,
+        };
+        // lit from below, the surface reflects on that side
+        let r = Ray::new(Vec3::new(0.0, -1.0, 1.0), Vec3::new(0.0, 1.0, -1.0));
+        let mut sampler = Sampler::new(9, 0);
+        for _ in 0..100 {
+            let sample = material.sample(&r, &hit, &mut sampler).unwrap();
+            assert!((sample.direction.length() - 1.0).abs() < 1e-12);
+            assert!(sample.direction.y < 0.0);
+            let cosine = -sample.direction.y;
+            let f = material.eval(&r, &hit, sample.direction);
+            let weight = (cosine / material.pdf(&r, &hit, sample.direction)) * f;
+            assert!((weight - sample.weight).length() < 1e-9);
+        }
+        assert_eq!(
+            material.eval(&r, &hit, Vec3::new(0.0, 1.0, 0.0)),
+            Vec3::zeros()
+        );
+    }
 }
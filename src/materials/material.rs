@@ -1,14 +1,69 @@
 use crate::hittable::HitRecord;
 use crate::ray::Ray;
+use crate::sampler::Sampler;
 use crate::vec3::Vec3;
-use rand::prelude::*;
 
-pub struct Scatter {
-    pub ray: Ray,
-    pub attenuation: Vec3,
+/// A direction picked by [`Materialable::sample`] to continue a path
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BsdfSample {
+    /// unit vector from the hit point towards where the light comes from
+    pub direction: Vec3,
+    /// the BSDF times the cosine with the normal over the density, the
+    /// fraction of the light from `direction` scattered along the ray
+    pub weight: Vec3,
+    /// density with respect to solid angle, or for delta lobes the
+    /// probability of having picked this lobe
+    pub pdf: f64,
+    /// whether the direction is the only one the material scatters the ray
+    /// into, like a mirror reflection, which light sampling cannot find
+    pub is_delta: bool,
 }
 
+/// How a surface scatters and emits light, as a bidirectional scattering
+/// distribution function.
+///
+/// Directions are unit vectors pointing away from the hit point, the ray
+/// which reached the surface giving the direction light leaves it towards.
 pub trait Materialable: Send + Sync {
-    fn scatter(&self, r: &Ray, hit_record: &HitRecord, rng: &mut ThreadRng) -> Option<Scatter>;
+    /// Pick a direction to continue the path which reached the surface along
+    /// `r`, `None` when the light is absorbed
+    fn sample(&self, r: &Ray, hit_record: &HitRecord, sampler: &mut Sampler) -> Option<BsdfSample>;
+
+    /// The BSDF for light coming from `direction` and leaving back along `r`,
+    /// zero for delta lobes
+    fn eval(&self, _r: &Ray, _hit_record: &HitRecord, _direction: Vec3) -> Vec3 {
+        Vec3::zeros()
+    }
+
+    /// The density of [`Materialable::sample`] picking `direction`, zero for
+    /// delta lobes
+    fn pdf(&self, _r: &Ray, _hit_record: &HitRecord, _direction: Vec3) -> f64 {
+        0.0
+    }
+
+    /// Whether the material only scatters into delta lobes, such materials
+    /// are not lit by sampling the lights
+    fn is_delta(&self) -> bool {
+        false
+    }
+
+    /// The radiance the surface emits towards the origin of `r`, nothing for
+    /// materials which are not light sources
+    fn emitted(&self, _r: &Ray, _hit_record: &HitRecord) -> Vec3 {
+        Vec3::zeros()
+    }
+
+    /// Whether [`Materialable::emitted`] may return some light
+    fn is_emitter(&self) -> bool {
+        false
+    }
 }
 
+/// The shading normal on the side of the surface the ray comes from
+pub(super) fn facing_normal(hit_record: &HitRecord) -> Vec3 {
+    if hit_record.front_face {
+        hit_record.n
+    } else {
+        -hit_record.n
+    }
+}
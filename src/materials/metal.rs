@@ -1,40 +1,144 @@
 use crate::hittable::HitRecord;
 use crate::ray::Ray;
+use crate::sampler::Sampler;
+use crate::textures::{Constant, Texture};
 use crate::util;
 use crate::vec3::Vec3;
+use std::f64::consts::PI;
+use std::sync::Arc;
 
-use super::material::{Materialable, Scatter};
-use rand::prelude::*;
+use super::material::{facing_normal, BsdfSample, Materialable};
 
+/// A reflective surface, blurred by `fuzzy`.
+///
+/// The mirror direction is offset by a random point of a ball of radius
+/// `fuzzy`, which gives a glossy lobe whose density has a closed form.
 pub struct Metal {
     fuzzy: f64,
-    albedo: Vec3,
+    albedo: Arc<dyn Texture>,
 }
 
 impl Metal {
     pub fn new(a: f64, b: f64, c: f64, fuzzy: f64) -> Box<Metal> {
+        Metal::textured(Constant::new(Vec3::new(a, b, c)), fuzzy)
+    }
+
+    /// A metal whose color varies over the surface
+    pub fn textured(albedo: Arc<dyn Texture>, fuzzy: f64) -> Box<Metal> {
         Box::new(Metal {
             fuzzy: if fuzzy < 1.0 { fuzzy } else { 1.0 },
-            albedo: Vec3::new(a, b, c),
+            albedo,
         })
     }
+
+    fn albedo(&self, hit_record: &HitRecord) -> Vec3 {
+        let (u, v) = hit_record.uv;
+        self.albedo.value(u, v, hit_record.p)
+    }
+
+    fn reflected(r: &Ray, hit_record: &HitRecord) -> Vec3 {
+        util::reflect(r.direction.make_unit_vector(), facing_normal(hit_record))
+    }
 }
 
 impl Materialable for Metal {
-    fn scatter(&self, r: &Ray, hit: &HitRecord, rng: &mut ThreadRng) -> Option<Scatter> {
-        let v = r.direction.make_unit_vector();
-        let reflected = util::reflect(v, hit.n);
-        let ray = Ray::new(
-            hit.p,
-            reflected + self.fuzzy * util::random_in_unit_sphere(rng),
-        );
-        if ray.direction.dot(hit.n) > 0.0 {
-            Some(Scatter {
-                ray,
-                attenuation: self.albedo,
-            })
-        } else {
-            None
+    /// Directions pointing into the surface are absorbed
+    fn sample(&self, r: &Ray, hit_record: &HitRecord, sampler: &mut Sampler) -> Option<BsdfSample> {
+        let reflected = Metal::reflected(r, hit_record);
+        let fuzzy = self.fuzzy.max(0.0);
+        let direction =
+            (reflected + fuzzy * util::random_in_unit_sphere(sampler)).make_unit_vector();
+        if direction.dot(facing_normal(hit_record)) <= 0.0 {
+            return None;
+        }
+        Some(BsdfSample {
+            direction,
+            weight: self.albedo(hit_record),
+            pdf: if self.is_delta() {
+                1.0
+            } else {
+                self.pdf(r, hit_record, direction)
+            },
+            is_delta: self.is_delta(),
+        })
+    }
+
+    /// The BSDF making every sample weigh the albedo
+    fn eval(&self, r: &Ray, hit_record: &HitRecord, direction: Vec3) -> Vec3 {
+        let cosine = direction.dot(facing_normal(hit_record));
+        if self.is_delta() || cosine <= 0.0 {
+            return Vec3::zeros();
+        }
+        self.pdf(r, hit_record, direction) / cosine * self.albedo(hit_record)
+    }
+
+    /// The points `t * direction` inside the ball of radius `fuzzy` around
+    /// the unit mirror direction `m` lie between the roots of
+    /// `t² - 2 t (direction · m) + 1 - fuzzy²`, which bound the integral of
+    /// the uniform density of the ball along the ray.
+    fn pdf(&self, r: &Ray, hit_record: &HitRecord, direction: Vec3) -> f64 {
+        if self.is_delta() {
+            return 0.0;
+        }
+        let cosine = direction.dot(Metal::reflected(r, hit_record));
+        let fuzzy = self.fuzzy;
+        let discriminant = cosine * cosine - 1.0 + fuzzy * fuzzy;
+        if discriminant < 0.0 {
+            return 0.0;
+        }
+        let far = cosine + discriminant.sqrt();
+        let near = (cosine - discriminant.sqrt()).max(0.0);
+        if far <= 0.0 {
+            return 0.0;
+        }
+        (far.powi(3) - near.powi(3)) / (4.0 * PI * fuzzy.powi(3))
+    }
+
+    fn is_delta(&self) -> bool {
+        self.fuzzy <= 0.0
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn pdf_test() {
+        let material = Metal::new(0.9, 0.9, 0.9, 0.3);
+        let hit = HitRecord {
+            t: 1.0,
+            p: Vec3::zeros(),
+            n: Vec3::new(0.0, 1.0, 0.0),
+            geometric_normal: Vec3::new(0.0, 1.0, 0.0),
+            front_face: true,
+            material: material.as_ref(),
+            color: None,
+            uv: (0.0, 0.0),
+            dpdu: Vec3::new(1.0, 0.0, 0.0),
+            dpdv: Vec3::new(0.0, 0.0, -1.0),
+            object_id: 0,
+            primitive_id: 0,
+        };
+        let r = Ray::new(Vec3::new(-1.0, 1.0, 0.0), Vec3::new(1.0, -1.0, 0.0));
+
+        // the density integrates to one over the sphere of directions
+        let mut sampler = Sampler::new(4, 0);
+        let count = 200_000;
+        let mut total = 0.0;
+        for _ in 0..count {
+            let direction = util::random_unit_vector(&mut sampler);
+            total += material.pdf(&r, &hit, direction) * 4.0 * PI / count as f64;
+        }
+        assert!((total - 1.0).abs() < 0.02, "{}", total);
+
+        for _ in 0..100 {
+            if let Some(sample) = material.sample(&r, &hit, &mut sampler) {
+                let cosine = sample.direction.y;
+                let f = material.eval(&r, &hit, sample.direction);
+                assert!(((cosine / sample.pdf) * f - sample.weight).length() < 1e-9);
+            }
         }
+        assert!(Metal::new(0.9, 0.9, 0.9, 0.0).is_delta());
     }
 }
@@ -1,9 +1,11 @@
 mod dielectrics;
+mod diffuse_light;
 mod lambertian;
 mod material;
 mod metal;
 
 pub use dielectrics::Dielectrics;
+pub use diffuse_light::DiffuseLight;
 pub use lambertian::Lambertian;
-pub use material::Materialable;
+pub use material::{BsdfSample, Materialable};
 pub use metal::Metal;
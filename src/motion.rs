@@ -0,0 +1,237 @@
+use crate::aabb::Aabb;
+use crate::transform::Transform;
+use crate::util;
+use crate::vec3::Vec3;
+
+/// Values which can be blended, `t` going from 0 for `self` to 1 for `other`
+pub trait Interpolate: Clone {
+    fn interpolate(&self, other: &Self, t: f64) -> Self;
+}
+
+impl Interpolate for Vec3 {
+    fn interpolate(&self, other: &Vec3, t: f64) -> Vec3 {
+        util::lerp(*self, *other, t)
+    }
+}
+
+/// A value changing over time, interpolated between keyframes and constant
+/// before the first one and after the last one
+#[derive(Clone, Debug, PartialEq)]
+pub struct Keyframes<T> {
+    /// `(time, value)` sorted by time
+    frames: Vec<(f64, T)>,
+}
+
+impl<T: Interpolate> Keyframes<T> {
+    /// The keyframes in any order, there must be at least one
+    pub fn new(mut frames: Vec<(f64, T)>) -> Keyframes<T> {
+        assert!(!frames.is_empty(), "keyframes need at least one value");
+        frames.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Keyframes { frames }
+    }
+
+    /// A value which does not change
+    pub fn constant(value: T) -> Keyframes<T> {
+        Keyframes {
+            frames: vec![(0.0, value)],
+        }
+    }
+
+    /// A linear move from `from` at `t0` to `to` at `t1`
+    pub fn linear(t0: f64, from: T, t1: f64, to: T) -> Keyframes<T> {
+        Keyframes::new(vec![(t0, from), (t1, to)])
+    }
+
+    pub fn is_constant(&self) -> bool {
+        self.frames.len() == 1
+    }
+
+    pub fn frames(&self) -> &[(f64, T)] {
+        &self.frames
+    }
+
+    /// The value at `time`
+    pub fn at(&self, time: f64) -> T {
+        let next = self.frames.partition_point(|(t, _)| *t <= time);
+        if next == 0 {
+            return self.frames[0].1.clone();
+        }
+        if next == self.frames.len() {
+            return self.frames[next - 1].1.clone();
+        }
+        let (t0, v0) = &self.frames[next - 1];
+        let (t1, v1) = &self.frames[next];
+        v0.interpolate(v1, (time - t0) / (t1 - t0))
+    }
+}
+
+/// Where an instance stands: scaled, then rotated by a unit quaternion, then
+/// translated. Unlike matrices, placements interpolate into rigid motions.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Placement {
+    pub scale: Vec3,
+    /// `(x, y, z, w)`
+    pub rotation: [f64; 4],
+    pub translation: Vec3,
+}
+
+impl Placement {
+    /// Rotate `degrees` counter-clockwise around `axis`
+    pub fn new(scale: Vec3, axis: Vec3, degrees: f64, translation: Vec3) -> Placement {
+        let a = axis.make_unit_vector();
+        let (sin, cos) = (0.5 * degrees.to_radians()).sin_cos();
+        Placement {
+            scale,
+            rotation: [a.x * sin, a.y * sin, a.z * sin, cos],
+            translation,
+        }
+    }
+
+    pub fn translation(translation: Vec3) -> Placement {
+        Placement {
+            scale: Vec3::all(1.0),
+            rotation: [0.0, 0.0, 0.0, 1.0],
+            translation,
+        }
+    }
+
+    pub fn transform(&self) -> Transform {
+        let [x, y, z, w] = self.rotation;
+        // the angle and axis of the quaternion
+        let degrees = 2.0 * w.clamp(-1.0, 1.0).acos().to_degrees();
+        let axis = Vec3::new(x, y, z);
+        let rotation = if axis.squared_length() > 0.0 {
+            Transform::rotation(axis, degrees)
+        } else {
+            Transform::identity()
+        };
+        Transform::scaling(self.scale)
+            .then(&rotation)
+            .then(&Transform::translation(self.translation))
+    }
+
+    /// The angle in radians between the rotations of two placements
+    fn angle_to(&self, other: &Placement) -> f64 {
+        let dot: f64 = (0..4).map(|i| self.rotation[i] * other.rotation[i]).sum();
+        2.0 * dot.abs().min(1.0).acos()
+    }
+}
+
+impl Interpolate for Placement {
+    fn interpolate(&self, other: &Placement, t: f64) -> Placement {
+        // spherical interpolation along the shortest arc
+        let mut dot: f64 = (0..4).map(|i| self.rotation[i] * other.rotation[i]).sum();
+        let mut to = other.rotation;
+        if dot < 0.0 {
+            dot = -dot;
+            to = to.map(|x| -x);
+        }
+        let (a, b) = if dot > 0.9995 {
+            (1.0 - t, t)
+        } else {
+            let theta = dot.acos();
+            let sin = theta.sin();
+            (((1.0 - t) * theta).sin() / sin, (t * theta).sin() / sin)
+        };
+        let mut rotation = [0.0; 4];
+        for (i, q) in rotation.iter_mut().enumerate() {
+            *q = a * self.rotation[i] + b * to[i];
+        }
+        let norm = rotation.iter().map(|q| q * q).sum::<f64>().sqrt();
+        Placement {
+            scale: self.scale.interpolate(&other.scale, t),
+            rotation: rotation.map(|q| q / norm),
+            translation: self.translation.interpolate(&other.translation, t),
+        }
+    }
+}
+
+impl Keyframes<Placement> {
+    /// The box enclosing `bounds` moved along the keyframes
+    pub fn bounding_box(&self, bounds: &Aabb) -> Aabb {
+        let corners = (0..8).map(|i| {
+            Vec3::new(
+                if i & 1 == 0 {
+                    bounds.min.x
+                } else {
+                    bounds.max.x
+                },
+                if i & 2 == 0 {
+                    bounds.min.y
+                } else {
+                    bounds.max.y
+                },
+                if i & 4 == 0 {
+                    bounds.min.z
+                } else {
+                    bounds.max.z
+                },
+            )
+        });
+        let radius = corners.map(|c| c.length()).fold(0.0, f64::max);
+        let mut result = Aabb::empty();
+        for (i, (t0, p0)) in self.frames.iter().enumerate() {
+            result = result.union(&p0.transform().bounding_box(bounds));
+            let (t1, p1) = match self.frames.get(i + 1) {
+                Some(next) => next,
+                None => break,
+            };
+            // without rotation points move linearly between keyframes, a
+            // rotation may carry them out of the boxes at the keyframes by at
+            // most the arc travelled between two samples
+            let angle = p0.angle_to(p1);
+            if angle == 0.0 {
+                continue;
+            }
+            let steps = 16;
+            let scale = p0.scale.max(p1.scale);
+            let max_scale = scale.x.abs().max(scale.y.abs()).max(scale.z.abs());
+            let margin = Vec3::all(max_scale * radius * angle / steps as f64);
+            for k in 0..=steps {
+                let time = t0 + (t1 - t0) * k as f64 / steps as f64;
+                let b = self.at(time).transform().bounding_box(bounds);
+                result = result.union(&Aabb::new(b.min - margin, b.max + margin));
+            }
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn keyframes_test() {
+        let path = Keyframes::new(vec![
+            (1.0, Vec3::new(0.0, 2.0, 0.0)),
+            (0.0, Vec3::zeros()),
+            (2.0, Vec3::new(4.0, 2.0, 0.0)),
+        ]);
+        assert_eq!(path.at(-1.0), Vec3::zeros());
+        assert_eq!(path.at(0.5), Vec3::new(0.0, 1.0, 0.0));
+        assert_eq!(path.at(1.5), Vec3::new(2.0, 2.0, 0.0));
+        assert_eq!(path.at(3.0), Vec3::new(4.0, 2.0, 0.0));
+        assert!(Keyframes::constant(Vec3::zeros()).is_constant());
+    }
+
+    #[test]
+    fn placement_test() {
+        let y = Vec3::new(0.0, 1.0, 0.0);
+        let start = Placement::new(Vec3::all(1.0), y, 0.0, Vec3::zeros());
+        let end = Placement::new(Vec3::all(1.0), y, 180.0, Vec3::new(2.0, 0.0, 0.0));
+        let half = start.interpolate(&end, 0.5).transform();
+        let p = half.point(Vec3::new(1.0, 0.0, 0.0));
+        assert!((p - Vec3::new(1.0, 0.0, -1.0)).length() < 1e-9);
+
+        // a full half turn sweeps the point around, beyond the keyframe boxes
+        let motion = Keyframes::linear(0.0, start, 1.0, end);
+        let unit = Aabb::new(Vec3::new(1.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0));
+        let bounds = motion.bounding_box(&unit);
+        for k in 0..=100 {
+            let p = motion.at(k as f64 / 100.0).transform().point(unit.min);
+            assert!(bounds.min.x <= p.x && p.x <= bounds.max.x);
+            assert!(bounds.min.z <= p.z && p.z <= bounds.max.z);
+        }
+    }
+}
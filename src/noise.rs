@@ -0,0 +1,166 @@
+//! Gradient noise for procedural textures.
+//!
+//! [`Perlin`] implements Ken Perlin's improved noise: pseudo random gradients
+//! on the integer lattice, picked through a permutation table which is
+//! shuffled from a seed, and blended with a quintic curve so that the noise
+//! is smooth to the second derivative. Summing octaves of it at doubling
+//! frequencies gives fractal Brownian motion ([`Perlin::fbm`]), and summing
+//! their absolute values the sharper creases of [`Perlin::turbulence`].
+use crate::sampler::Sampler;
+use crate::vec3::Vec3;
+
+const SIZE: usize = 256;
+
+/// Seeded three dimensional gradient noise
+pub struct Perlin {
+    /// a permutation of `0..256`, repeated to avoid wrapping indices
+    permutation: [u8; 2 * SIZE],
+}
+
+/// The dot product of `(x, y, z)` with one of the 12 vectors from the center
+/// of a cube to the middle of its edges, picked by `hash`
+fn gradient(hash: u8, x: f64, y: f64, z: f64) -> f64 {
+    let h = hash & 15;
+    let u = if h < 8 { x } else { y };
+    let v = match h {
+        0..=3 => y,
+        12 | 14 => x,
+        _ => z,
+    };
+    (if h & 1 == 0 { u } else { -u }) + (if h & 2 == 0 { v } else { -v })
+}
+
+/// `6t⁵ - 15t⁴ + 10t³`, whose first and second derivatives vanish at 0 and 1
+fn fade(t: f64) -> f64 {
+    t * t * t * (t * (6.0 * t - 15.0) + 10.0)
+}
+
+fn lerp(t: f64, a: f64, b: f64) -> f64 {
+    a + t * (b - a)
+}
+
+impl Perlin {
+    pub fn new(seed: u64) -> Perlin {
+        let mut table = [0u8; SIZE];
+        for (i, entry) in table.iter_mut().enumerate() {
+            *entry = i as u8;
+        }
+        // Fisher–Yates
+        let mut sampler = Sampler::new(seed, 0);
+        for i in (1..SIZE).rev() {
+            let j = (sampler.next_u32() as usize) % (i + 1);
+            table.swap(i, j);
+        }
+        let mut permutation = [0u8; 2 * SIZE];
+        for (i, entry) in permutation.iter_mut().enumerate() {
+            *entry = table[i % SIZE];
+        }
+        Perlin { permutation }
+    }
+
+    /// The noise at `p`, within about `[-1, 1]` and zero on the lattice
+    pub fn noise(&self, p: Vec3) -> f64 {
+        let (fx, fy, fz) = (p.x.floor(), p.y.floor(), p.z.floor());
+        // the lattice repeats every 256 units
+        let cell = |f: f64| (f as i64).rem_euclid(SIZE as i64) as usize;
+        let (xi, yi, zi) = (cell(fx), cell(fy), cell(fz));
+        let (x, y, z) = (p.x - fx, p.y - fy, p.z - fz);
+        let (u, v, w) = (fade(x), fade(y), fade(z));
+
+        let perm = &self.permutation;
+        let a = perm[xi] as usize + yi;
+        let (aa, ab) = (perm[a] as usize + zi, perm[a + 1] as usize + zi);
+        let b = perm[xi + 1] as usize + yi;
+        let (ba, bb) = (perm[b] as usize + zi, perm[b + 1] as usize + zi);
+
+        lerp(
+            w,
+            lerp(
+                v,
+                lerp(
+                    u,
+                    gradient(perm[aa], x, y, z),
+                    gradient(perm[ba], x - 1.0, y, z),
+                ),
+                lerp(
+                    u,
+                    gradient(perm[ab], x, y - 1.0, z),
+                    gradient(perm[bb], x - 1.0, y - 1.0, z),
+                ),
+            ),
+            lerp(
+                v,
+                lerp(
+                    u,
+                    gradient(perm[aa + 1], x, y, z - 1.0),
+                    gradient(perm[ba + 1], x - 1.0, y, z - 1.0),
+                ),
+                lerp(
+                    u,
+                    gradient(perm[ab + 1], x, y - 1.0, z - 1.0),
+                    gradient(perm[bb + 1], x - 1.0, y - 1.0, z - 1.0),
+                ),
+            ),
+        )
+    }
+
+    /// Fractal Brownian motion: `octaves` layers of noise, each of twice the
+    /// frequency and half the amplitude of the previous one
+    pub fn fbm(&self, p: Vec3, octaves: u32) -> f64 {
+        let mut sum = 0.0;
+        let (mut frequency, mut amplitude) = (1.0, 0.5);
+        for _ in 0..octaves {
+            sum += amplitude * self.noise(frequency * p);
+            frequency *= 2.0;
+            amplitude *= 0.5;
+        }
+        sum
+    }
+
+    /// Like [`Perlin::fbm`] with the absolute value of every octave, which
+    /// is never negative and creased where the noise changes sign
+    pub fn turbulence(&self, p: Vec3, octaves: u32) -> f64 {
+        let mut sum = 0.0;
+        let (mut frequency, mut amplitude) = (1.0, 0.5);
+        for _ in 0..octaves {
+            sum += amplitude * self.noise(frequency * p).abs();
+            frequency *= 2.0;
+            amplitude *= 0.5;
+        }
+        sum
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn noise_test() {
+        let perlin = Perlin::new(7);
+        assert_eq!(perlin.noise(Vec3::new(3.0, -2.0, 5.0)), 0.0);
+        let mut sampler = Sampler::new(1, 0);
+        let (mut min, mut max) = (0.0_f64, 0.0_f64);
+        for _ in 0..10_000 {
+            let p = Vec3::new(
+                sampler.gen_range(-50.0, 50.0),
+                sampler.gen_range(-50.0, 50.0),
+                sampler.gen_range(-50.0, 50.0),
+            );
+            let n = perlin.noise(p);
+            min = min.min(n);
+            max = max.max(n);
+            // continuous
+            assert!((perlin.noise(p + Vec3::all(1e-6)) - n).abs() < 1e-4);
+            let turbulence = perlin.turbulence(p, 7);
+            assert!((0.0..1.1).contains(&turbulence));
+            assert!(perlin.fbm(p, 7).abs() <= turbulence + 1e-12);
+        }
+        assert!(min > -1.1 && min < -0.5 && max < 1.1 && max > 0.5);
+
+        // the seed picks the pattern
+        let p = Vec3::new(0.3, 0.6, 0.9);
+        assert_eq!(Perlin::new(7).noise(p), perlin.noise(p));
+        assert_ne!(Perlin::new(8).noise(p), perlin.noise(p));
+    }
+}
@@ -3,13 +3,20 @@ use super::vec3::Vec3;
 pub struct Ray {
     pub origin: Vec3,
     pub direction: Vec3,
+    /// the instant the ray is cast at, within the shutter interval of the camera
+    pub time: f64,
 }
 
 impl Ray {
     pub fn new(a: Vec3, b: Vec3) -> Ray {
+        Ray::with_time(a, b, 0.0)
+    }
+
+    pub fn with_time(a: Vec3, b: Vec3, time: f64) -> Ray {
         Ray {
             origin: a,
             direction: b,
+            time,
         }
     }
 
@@ -0,0 +1,483 @@
+use crate::camera::Camera;
+use crate::hittable::{HitRecord, Hittable, World};
+use crate::materials::BsdfSample;
+use crate::ray::Ray;
+use crate::sampler::Sampler;
+use crate::task::{Task, TaskQueue};
+use crate::util;
+use crate::vec3::Vec3;
+
+/// Settings controlling how a frame is rendered.
+#[derive(Clone, Debug)]
+pub struct RenderSettings {
+    /// width of the image in pixels
+    pub width: usize,
+    /// height of the image in pixels
+    pub height: usize,
+    /// number of rays traced through every pixel
+    pub samples_per_pixel: u32,
+    /// maximum number of bounces of a single ray
+    pub max_depth: u32,
+    /// number of worker threads, at least 1
+    pub threads: usize,
+    /// width and height in pixels of the tiles the image is split into, at
+    /// least 1
+    pub tile_size: usize,
+    /// seed of the random numbers, equal seeds and settings give identical images
+    pub seed: u64,
+    /// weight the light reached by sampling the materials against the light
+    /// sources sampled directly, instead of only relying on the latter
+    pub mis: bool,
+}
+
+impl RenderSettings {
+    pub fn aspect_ratio(&self) -> f64 {
+        self.width as f64 / self.height as f64
+    }
+}
+
+impl Default for RenderSettings {
+    fn default() -> RenderSettings {
+        RenderSettings {
+            width: 800,
+            height: 600,
+            samples_per_pixel: 100,
+            max_depth: 50,
+            threads: num_cpus::get(),
+            tile_size: 32,
+            seed: 0,
+            mis: true,
+        }
+    }
+}
+
+/// The rendered image.
+///
+/// Pixels are stored row by row from the top of the image as linear radiance,
+/// conversions to displayable 8 bit colors apply gamma correction and clamp
+/// whatever exceeds the displayable range.
+pub struct FrameBuffer {
+    pub width: usize,
+    pub height: usize,
+    pixels: Vec<Vec3>,
+}
+
+impl FrameBuffer {
+    /// An image of `width` by `height` pixels given row by row from the top
+    pub fn new(width: usize, height: usize, pixels: Vec<Vec3>) -> FrameBuffer {
+        assert_eq!(pixels.len(), width * height, "wrong number of pixels");
+        FrameBuffer {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    /// The linear radiance of every pixel
+    pub fn pixels(&self) -> &[Vec3] {
+        &self.pixels
+    }
+
+    fn display_colors(&self) -> impl Iterator<Item = Vec3> + '_ {
+        self.pixels.iter().map(|c| {
+            // gamma correction
+            let gamma = |x: f64| x.max(0.0).sqrt().min(1.0);
+            Vec3::new(gamma(c.x), gamma(c.y), gamma(c.z))
+        })
+    }
+
+    /// The pixels packed as `0RGB` which is the layout `minifb` expects
+    pub fn to_packed_rgb(&self) -> Vec<u32> {
+        self.display_colors().map(util::from_u8_rgb).collect()
+    }
+
+    /// The pixels as consecutive 8 bit red, green and blue values
+    pub fn to_rgb8(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.pixels.len() * 3);
+        for pixel in self.display_colors().map(util::from_u8_rgb) {
+            bytes.push((pixel >> 16) as u8);
+            bytes.push((pixel >> 8) as u8);
+            bytes.push(pixel as u8);
+        }
+        bytes
+    }
+}
+
+/// Estimates the radiance along camera rays by tracing paths through the
+/// world.
+///
+/// At every surface which does not only scatter into delta lobes the path is
+/// lit by sampling a light source directly, then continues in a direction
+/// sampled from the material. Both can reach the same light: with `mis` their
+/// contributions are weighted by the power heuristic, otherwise the light
+/// reached by the material sample is ignored after a light was sampled.
+struct PathTracer<'a> {
+    world: &'a World,
+    max_depth: u32,
+    mis: bool,
+}
+
+impl<'a> PathTracer<'a> {
+    /// The radiance coming along `ray`
+    fn radiance(&self, mut ray: Ray, sampler: &mut Sampler) -> Vec3 {
+        let mut radiance = Vec3::zeros();
+        // the fraction of the light along `ray` reaching the camera
+        let mut throughput = Vec3::all(1.0);
+        // how the direction of `ray` was sampled, `None` for camera rays
+        let mut previous: Option<(Vec3, BsdfSample)> = None;
+        let mut depth = 0;
+        loop {
+            let record = self.world.hit(&ray, 0.001, f64::MAX);
+            // emitters are all sampled, the environment may not be
+            let is_sampled = record.is_some() || self.world.samples_environment();
+            let weight = match previous {
+                Some((origin, sample)) if !sample.is_delta && is_sampled => {
+                    if self.mis {
+                        let light_pdf = self.world.pdf_towards(origin, ray.direction, ray.time);
+                        power_heuristic(sample.pdf, light_pdf)
+                    } else {
+                        0.0
+                    }
+                }
+                _ => 1.0,
+            };
+            let record = match record {
+                Some(record) => record,
+                None => {
+                    radiance += weight * throughput * self.world.background(ray.direction);
+                    break;
+                }
+            };
+            if weight > 0.0 {
+                radiance += weight * throughput * record.material.emitted(&ray, &record);
+            }
+            if depth >= self.max_depth {
+                break;
+            }
+            if !record.material.is_delta() {
+                radiance += throughput * self.direct_light(&ray, &record, sampler);
+            }
+            let sample = match record.material.sample(&ray, &record, sampler) {
+                Some(sample) => sample,
+                None => break,
+            };
+            throughput *= sample.weight;
+            if throughput == Vec3::zeros() {
+                break;
+            }
+            ray = Ray::with_time(record.p, sample.direction, ray.time);
+            previous = Some((record.p, sample));
+            depth += 1;
+        }
+        radiance
+    }
+
+    /// The light the surface of `record` reflects along `r` from one direction
+    /// sampled towards the light sources
+    fn direct_light(&self, r: &Ray, record: &HitRecord, sampler: &mut Sampler) -> Vec3 {
+        let sample = match self.world.sample_towards(record.p, r.time, sampler) {
+            Some(sample) => sample,
+            None => return Vec3::zeros(),
+        };
+        let f = record.material.eval(r, record, sample.direction);
+        if f == Vec3::zeros() {
+            return Vec3::zeros();
+        }
+        let weight = if self.mis {
+            let bsdf_pdf = record.material.pdf(r, record, sample.direction);
+            power_heuristic(sample.pdf, bsdf_pdf)
+        } else {
+            1.0
+        };
+        let cosine = sample.direction.dot(record.n).abs();
+        // whatever emitter is seen first, the density covers every light
+        let shadow = Ray::with_time(record.p, sample.direction, r.time);
+        match self.world.hit(&shadow, 0.001, f64::MAX) {
+            Some(hit) => (weight * cosine / sample.pdf) * f * hit.material.emitted(&shadow, &hit),
+            None if self.world.samples_environment() => {
+                (weight * cosine / sample.pdf) * f * self.world.background(shadow.direction)
+            }
+            None => Vec3::zeros(),
+        }
+    }
+}
+
+/// The weight of a sample drawn with density `pdf` which the other strategy
+/// would have drawn with density `other_pdf`, from Veach's power heuristic
+/// with an exponent of 2
+fn power_heuristic(pdf: f64, other_pdf: f64) -> f64 {
+    let (f, g) = (pdf * pdf, other_pdf * other_pdf);
+    if f + g > 0.0 {
+        f / (f + g)
+    } else {
+        0.0
+    }
+}
+
+/// Renders an image progressively.
+///
+/// Every call to [`Renderer::render_pass`] traces more samples through every
+/// pixel and adds them to the ones accumulated so far, so the current estimate
+/// can be displayed between passes while it keeps getting refined.
+pub struct Renderer<'a> {
+    world: &'a World,
+    camera: &'a Camera,
+    settings: &'a RenderSettings,
+    /// sum of the radiance of all samples traced through each pixel
+    accumulated: Vec<Vec3>,
+    samples: u32,
+}
+
+impl<'a> Renderer<'a> {
+    /// # Panics
+    ///
+    /// Panics if `settings.tile_size` or `settings.threads` is 0.
+    pub fn new(world: &'a World, camera: &'a Camera, settings: &'a RenderSettings) -> Renderer<'a> {
+        assert!(settings.tile_size > 0, "the tile size must be at least 1");
+        assert!(
+            settings.threads > 0,
+            "the number of threads must be at least 1"
+        );
+        world.build_bvh();
+        Renderer {
+            world,
+            camera,
+            settings,
+            accumulated: vec![Vec3::zeros(); settings.width * settings.height],
+            samples: 0,
+        }
+    }
+
+    /// Number of samples per pixel accumulated so far
+    pub fn samples(&self) -> u32 {
+        self.samples
+    }
+
+    /// Whether `settings.samples_per_pixel` samples have been accumulated
+    pub fn is_done(&self) -> bool {
+        self.samples >= self.settings.samples_per_pixel
+    }
+
+    /// Trace `samples` more samples through every pixel.
+    ///
+    /// The image is cut into square tiles of `settings.tile_size` pixels which a
+    /// pool of `settings.threads` workers takes from a shared queue. The call
+    /// blocks until the pass is over.
+    pub fn render_pass(&mut self, samples: u32) {
+        let width = self.settings.width;
+        let height = self.settings.height;
+        let tracer = PathTracer {
+            world: self.world,
+            max_depth: self.settings.max_depth,
+            mis: self.settings.mis,
+        };
+        let seed = self.settings.seed;
+        let first_sample = self.samples;
+        let camera = self.camera;
+        let queue = TaskQueue::new(Task::split(width, height, self.settings.tile_size));
+        let num_workers = self.settings.threads.min(queue.len());
+
+        let accumulated = &mut self.accumulated;
+        std::thread::scope(|s| {
+            let (sender, receiver) = std::sync::mpsc::channel();
+            for _ in 0..num_workers {
+                let sx = sender.clone();
+                let queue = &queue;
+                let tracer = &tracer;
+                s.spawn(move || {
+                    while let Some(task) = queue.pop() {
+                        let mut buffer: Vec<Vec3> = Vec::with_capacity(task.area());
+                        for row in task.y..task.y + task.height {
+                            // the camera expects v to grow towards the top of the image
+                            let j = height - 1 - row;
+                            for i in task.x..task.x + task.width {
+                                let mut c = Vec3::zeros();
+                                for index in first_sample..first_sample + samples {
+                                    let mut sampler = Sampler::for_pixel(seed, i, row, index);
+                                    let v: f64 = (j as f64 + sampler.next_f64()) / height as f64;
+                                    let u: f64 = (i as f64 + sampler.next_f64()) / width as f64;
+                                    let ray = camera.get_ray(u, v, &mut sampler);
+                                    c += tracer.radiance(ray, &mut sampler);
+                                }
+                                buffer.push(c);
+                            }
+                        }
+                        sx.send((task, buffer)).unwrap();
+                    }
+                });
+            }
+            drop(sender);
+
+            // tiles arrive in whatever order the workers finish them
+            for (task, buffer) in receiver {
+                for (row, tile_row) in buffer.chunks(task.width).enumerate() {
+                    let start = (task.y + row) * width + task.x;
+                    for (sum, c) in accumulated[start..start + task.width]
+                        .iter_mut()
+                        .zip(tile_row)
+                    {
+                        *sum += *c;
+                    }
+                }
+            }
+        });
+        self.samples += samples;
+    }
+
+    /// The image as estimated from the samples accumulated so far
+    pub fn frame(&self) -> FrameBuffer {
+        let n = self.samples.max(1) as f64;
+        FrameBuffer {
+            width: self.settings.width,
+            height: self.settings.height,
+            pixels: self.accumulated.iter().map(|c| *c / n).collect(),
+        }
+    }
+}
+
+/// Render `world` as seen by `camera` with `settings.samples_per_pixel`
+/// samples per pixel in a single pass.
+///
+/// # Panics
+///
+/// Panics if `settings.tile_size` or `settings.threads` is 0.
+pub fn render(world: &World, camera: &Camera, settings: &RenderSettings) -> FrameBuffer {
+    let mut renderer = Renderer::new(world, camera, settings);
+    renderer.render_pass(settings.samples_per_pixel);
+    renderer.frame()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::hittable::Quad;
+    use crate::materials::{DiffuseLight, Metal};
+    use crate::scenes;
+
+    #[test]
+    fn deterministic_test() {
+        let world = scenes::load("simple").unwrap().world;
+        let camera = Camera::new(
+            Vec3::zeros(),
+            Vec3::new(0.0, 0.0, -1.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            90.0,
+            4.0 / 3.0,
+            0.1,
+            1.0,
+        );
+        let settings = RenderSettings {
+            width: 24,
+            height: 18,
+            samples_per_pixel: 4,
+            threads: 1,
+            tile_size: 5,
+            ..RenderSettings::default()
+        };
+        let single = render(&world, &camera, &settings);
+
+        let threaded = RenderSettings {
+            threads: 3,
+            ..settings.clone()
+        };
+        let mut renderer = Renderer::new(&world, &camera, &threaded);
+        while !renderer.is_done() {
+            renderer.render_pass(1);
+        }
+        assert_eq!(single.pixels(), renderer.frame().pixels());
+
+        let reseeded = RenderSettings {
+            seed: 1,
+            ..settings
+        };
+        assert_ne!(single.pixels(), render(&world, &camera, &reseeded).pixels());
+    }
+
+    /// Render an empty 4 x 4 image with `settings`
+    fn render_empty(settings: RenderSettings) {
+        let camera = Camera::new(
+            Vec3::zeros(),
+            Vec3::new(0.0, 0.0, -1.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            90.0,
+            1.0,
+            0.0,
+            1.0,
+        );
+        let settings = RenderSettings {
+            width: 4,
+            height: 4,
+            ..settings
+        };
+        render(&World::new(), &camera, &settings);
+    }
+
+    #[test]
+    #[should_panic(expected = "tile size")]
+    fn empty_tile_test() {
+        render_empty(RenderSettings {
+            tile_size: 0,
+            ..RenderSettings::default()
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "number of threads")]
+    fn no_threads_test() {
+        render_empty(RenderSettings {
+            threads: 0,
+            ..RenderSettings::default()
+        });
+    }
+
+    #[test]
+    fn mis_test() {
+        // a glossy floor under a large light, which both strategies can find
+        let mut world = World::new();
+        world.set_background(Vec3::zeros());
+        world.add(Quad::new(
+            Vec3::new(-3.0, 0.0, -3.0),
+            Vec3::new(0.0, 0.0, 6.0),
+            Vec3::new(6.0, 0.0, 0.0),
+            Metal::new(0.8, 0.8, 0.8, 0.3),
+        ));
+        world.add(Quad::new(
+            Vec3::new(-2.0, 3.0, -1.0),
+            Vec3::new(4.0, 0.0, 0.0),
+            Vec3::new(0.0, 0.0, 2.0),
+            DiffuseLight::new(4.0, 4.0, 4.0),
+        ));
+        let camera = Camera::new(
+            Vec3::new(0.0, 2.0, 6.0),
+            Vec3::zeros(),
+            Vec3::new(0.0, 1.0, 0.0),
+            40.0,
+            4.0 / 3.0,
+            0.0,
+            6.0,
+        );
+        let settings = RenderSettings {
+            width: 16,
+            height: 12,
+            samples_per_pixel: 64,
+            max_depth: 4,
+            ..RenderSettings::default()
+        };
+        let mean = |settings: &RenderSettings| {
+            let frame = render(&world, &camera, settings);
+            frame.pixels().iter().map(|c| c.x).sum::<f64>() / frame.pixels().len() as f64
+        };
+        let weighted = mean(&settings);
+        let unweighted = mean(&RenderSettings {
+            mis: false,
+            ..settings
+        });
+        assert!(weighted > 0.0);
+        assert!(
+            (weighted - unweighted).abs() < 0.05 * weighted,
+            "{} {}",
+            weighted,
+            unweighted
+        );
+    }
+}
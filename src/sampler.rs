@@ -0,0 +1,95 @@
+//! Deterministic random numbers for the renderer.
+//!
+//! Every sample of every pixel draws its random numbers from its own
+//! [`Sampler`] derived from the render seed, the pixel coordinates and the
+//! index of the sample. A render therefore only depends on its seed and
+//! settings, not on how the work was spread over threads or passes.
+
+/// A PCG32 (XSH RR variant) pseudo random number generator
+#[derive(Clone, Debug)]
+pub struct Sampler {
+    state: u64,
+    increment: u64,
+}
+
+const MULTIPLIER: u64 = 6_364_136_223_846_793_005;
+
+/// SplitMix64 finalizer, used to turn related keys into unrelated seeds
+fn mix(mut z: u64) -> u64 {
+    z = z.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+impl Sampler {
+    /// Create a generator from a seed and a stream selector, generators with
+    /// different streams produce independent sequences even for equal seeds
+    pub fn new(seed: u64, stream: u64) -> Sampler {
+        let mut sampler = Sampler {
+            state: 0,
+            increment: (stream << 1) | 1,
+        };
+        sampler.next_u32();
+        sampler.state = sampler.state.wrapping_add(seed);
+        sampler.next_u32();
+        sampler
+    }
+
+    /// The generator used for sample `index` of the pixel at column `x` and row `y`
+    pub fn for_pixel(seed: u64, x: usize, y: usize, index: u32) -> Sampler {
+        let pixel = mix(seed ^ mix(((x as u64) << 32) | y as u64));
+        Sampler::new(mix(pixel ^ index as u64), pixel)
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        let old = self.state;
+        self.state = old.wrapping_mul(MULTIPLIER).wrapping_add(self.increment);
+        let xorshifted = (((old >> 18) ^ old) >> 27) as u32;
+        let rot = (old >> 59) as u32;
+        xorshifted.rotate_right(rot)
+    }
+
+    /// A number uniformly distributed in `[0, 1)`
+    pub fn next_f64(&mut self) -> f64 {
+        let bits = ((self.next_u32() as u64) << 32) | self.next_u32() as u64;
+        // keep the 53 bits a f64 mantissa can hold
+        (bits >> 11) as f64 * (1.0 / (1u64 << 53) as f64)
+    }
+
+    /// A number uniformly distributed in `[min, max)`
+    pub fn gen_range(&mut self, min: f64, max: f64) -> f64 {
+        min + (max - min) * self.next_f64()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn pcg32_reference_test() {
+        // first outputs of the reference pcg32 demo seeded with (42, 54)
+        let mut sampler = Sampler::new(42, 54);
+        let outputs: Vec<u32> = (0..3).map(|_| sampler.next_u32()).collect();
+        assert_eq!(outputs, vec![0xa15c_02b7, 0x7b47_f409, 0xba1d_3330]);
+    }
+
+    #[test]
+    fn for_pixel_test() {
+        let first: Vec<f64> = {
+            let mut s = Sampler::for_pixel(7, 3, 4, 0);
+            (0..4).map(|_| s.next_f64()).collect()
+        };
+        let again: Vec<f64> = {
+            let mut s = Sampler::for_pixel(7, 3, 4, 0);
+            (0..4).map(|_| s.next_f64()).collect()
+        };
+        let other_sample = Sampler::for_pixel(7, 3, 4, 1).next_f64();
+        let other_seed = Sampler::for_pixel(8, 3, 4, 0).next_f64();
+        assert_eq!(first, again);
+        assert_ne!(first[0], other_sample);
+        assert_ne!(first[0], other_seed);
+        assert!(first.iter().all(|x| (0.0..1.0).contains(x)));
+    }
+}
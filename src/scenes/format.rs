@@ -0,0 +1,1205 @@
+//! A human-editable description of scenes, in `.scene` files.
+//!
+//! Every line is a statement: a keyword followed by attributes, each being a
+//! name and its values. `#` starts a comment.
+//!
+//! ```text
+//! # optional, see CameraSettings and RenderSettings for the defaults
+//! camera lookfrom 13 2 3 lookat 0 0 0 vup 0 1 0 vfov 20 aperture 0.1 focus_dist 10
+//! render width 800 height 600 spp 100 max_depth 50 seed 0
+//!
+//! # optional, rays leaving the scene see a sky gradient by default
+//! background color 0 0 0
+//! # or an equirectangular .hdr or .pfm image relative to this file, turned
+//! # in degrees around the vertical axis and scaled in intensity
+//! background map studio.hdr rotation 90 intensity 2
+//! # or daylight, the sun being 30 degrees high and 120 degrees from -z
+//! # towards +x, under a sky from very clear (turbidity 2) to hazy (10)
+//! background sun 30 120 turbidity 3 intensity 1
+//!
+//! # named textures: checker cubes of a side, an image file relative to this
+//! # file mapped on the texture coordinates, and a gradient between two points
+//! texture checks checker size 1 even 0.2 0.3 0.1 odd 0.9 0.9 0.9
+//! texture earth image file earth.png
+//! texture fade gradient start 0 0 0 end 0 2 0 from 1 1 1 to 0.5 0.7 1
+//! # and noise, whose seed picks the pattern and scale its frequency, the
+//! # colors being optional
+//! texture stone marble seed 1 scale 4 base 0.9 0.9 0.9 vein 0.2 0.2 0.25
+//! texture oak wood seed 2 scale 6 light 0.75 0.55 0.33 dark 0.45 0.28 0.14
+//! texture sky clouds seed 3 scale 1 sky 0.3 0.5 0.9 cloud 1 1 1
+//! # the cells of a checkerboard may be textures as well
+//! texture tiles checker size 0.5 even texture stone odd 0.1 0.1 0.1
+//!
+//! # named materials: lambertian, metal, dielectric and light, the albedo
+//! # being either a color or a texture, as is the emission of lights
+//! material ground lambertian albedo 0.5 0.5 0.5
+//! material floor lambertian texture checks
+//! material gold metal albedo 0.8 0.6 0.2 fuzz 0.3
+//! material glass dielectric ior 1.5
+//! material lamp light emit 4 4 4
+//! material glow light emit texture sky
+//!
+//! sphere center 0 -1000 0 radius 1000 material ground
+//! triangle a 0 0 0 b 1 0 0 c 0 1 0 material gold
+//! # the parallelogram with edges u and v from corner
+//! quad corner -1 3 -1 u 2 0 0 v 0 0 2 material lamp
+//! # a material can also be given inline, ending the statement
+//! sphere center 0 1 0 radius 1 dielectric ior 1.5
+//! # a negative radius points the normals inwards, making a hollow bubble
+//! sphere center 0 1 0 radius -0.9 dielectric ior 1.5
+//!
+//! # meshes from .obj, .ply, .gltf or .glb files, relative to this file,
+//! # optionally scaled, rotated in degrees around x, y and z, and translated
+//! include teapot.obj
+//! include teapot.obj scale 0.5 0.5 0.5 rotate 0 90 0 translate 2 0 0
+//!
+//! # spheres and included files may move by an offset between the instants
+//! # 0 and 1, blurred when the camera has a shutter such as `shutter 0 1`
+//! sphere center 2 1 0 radius 0.5 material gold move 0 0.5 0
+//! include teapot.obj translate -2 0 0 move 1 0 0
+//! ```
+use super::Scene;
+use crate::camera::CameraSettings;
+use crate::environment::{Environment, EnvironmentMap, SunSky, Uniform};
+use crate::hittable::{Quad, Sphere, Transformed, Triangle, World};
+use crate::loaders::LoadError;
+use crate::materials::{Dielectrics, DiffuseLight, Lambertian, Materialable, Metal};
+use crate::motion::{Keyframes, Placement};
+use crate::render::RenderSettings;
+use crate::textures::{Checker, Clouds, Constant, Gradient, ImageTexture, Marble, Texture, Wood};
+use crate::transform::Transform;
+use crate::vec3::Vec3;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// What follows the name of an attribute
+#[derive(Clone, Copy)]
+enum Arity {
+    Numbers(usize),
+    Name,
+    /// 3 numbers or `texture` and the name of one
+    Color,
+}
+
+enum Value<'a> {
+    Numbers(Vec<f64>),
+    Name(&'a str),
+    Texture(&'a str),
+}
+
+const CAMERA: &[(&str, Arity)] = &[
+    ("lookfrom", Arity::Numbers(3)),
+    ("lookat", Arity::Numbers(3)),
+    ("vup", Arity::Numbers(3)),
+    ("vfov", Arity::Numbers(1)),
+    ("aperture", Arity::Numbers(1)),
+    ("focus_dist", Arity::Numbers(1)),
+    ("shutter", Arity::Numbers(2)),
+];
+const RENDER: &[(&str, Arity)] = &[
+    ("width", Arity::Numbers(1)),
+    ("height", Arity::Numbers(1)),
+    ("spp", Arity::Numbers(1)),
+    ("max_depth", Arity::Numbers(1)),
+    ("tile_size", Arity::Numbers(1)),
+    ("seed", Arity::Numbers(1)),
+];
+const SPHERE: &[(&str, Arity)] = &[
+    ("center", Arity::Numbers(3)),
+    ("radius", Arity::Numbers(1)),
+    ("material", Arity::Name),
+    ("move", Arity::Numbers(3)),
+];
+const TRIANGLE: &[(&str, Arity)] = &[
+    ("a", Arity::Numbers(3)),
+    ("b", Arity::Numbers(3)),
+    ("c", Arity::Numbers(3)),
+    ("material", Arity::Name),
+];
+const QUAD: &[(&str, Arity)] = &[
+    ("corner", Arity::Numbers(3)),
+    ("u", Arity::Numbers(3)),
+    ("v", Arity::Numbers(3)),
+    ("material", Arity::Name),
+];
+const BACKGROUND: &[(&str, Arity)] = &[
+    ("color", Arity::Numbers(3)),
+    ("map", Arity::Name),
+    ("rotation", Arity::Numbers(1)),
+    ("sun", Arity::Numbers(2)),
+    ("turbidity", Arity::Numbers(1)),
+    ("intensity", Arity::Numbers(1)),
+];
+const INCLUDE: &[(&str, Arity)] = &[
+    ("scale", Arity::Numbers(3)),
+    ("rotate", Arity::Numbers(3)),
+    ("translate", Arity::Numbers(3)),
+    ("move", Arity::Numbers(3)),
+];
+const CHECKER: &[(&str, Arity)] = &[
+    ("size", Arity::Numbers(1)),
+    ("even", Arity::Color),
+    ("odd", Arity::Color),
+];
+const IMAGE: &[(&str, Arity)] = &[("file", Arity::Name)];
+const GRADIENT: &[(&str, Arity)] = &[
+    ("start", Arity::Numbers(3)),
+    ("end", Arity::Numbers(3)),
+    ("from", Arity::Numbers(3)),
+    ("to", Arity::Numbers(3)),
+];
+const MARBLE: &[(&str, Arity)] = &[
+    ("seed", Arity::Numbers(1)),
+    ("scale", Arity::Numbers(1)),
+    ("base", Arity::Numbers(3)),
+    ("vein", Arity::Numbers(3)),
+];
+const WOOD: &[(&str, Arity)] = &[
+    ("seed", Arity::Numbers(1)),
+    ("scale", Arity::Numbers(1)),
+    ("light", Arity::Numbers(3)),
+    ("dark", Arity::Numbers(3)),
+];
+const CLOUDS: &[(&str, Arity)] = &[
+    ("seed", Arity::Numbers(1)),
+    ("scale", Arity::Numbers(1)),
+    ("sky", Arity::Numbers(3)),
+    ("cloud", Arity::Numbers(3)),
+];
+const LAMBERTIAN: &[(&str, Arity)] = &[("albedo", Arity::Numbers(3)), ("texture", Arity::Name)];
+const METAL: &[(&str, Arity)] = &[
+    ("albedo", Arity::Numbers(3)),
+    ("texture", Arity::Name),
+    ("fuzz", Arity::Numbers(1)),
+];
+const DIELECTRIC: &[(&str, Arity)] = &[("ior", Arity::Numbers(1))];
+const LIGHT: &[(&str, Arity)] = &[("emit", Arity::Color)];
+
+/// The material types, see [`MaterialSpec::parse`]
+const MATERIAL_TYPES: [&str; 4] = ["lambertian", "metal", "dielectric", "light"];
+
+/// The keywords starting a statement, see [`parse`]
+const STATEMENTS: [&str; 9] = [
+    "camera",
+    "render",
+    "background",
+    "texture",
+    "material",
+    "sphere",
+    "triangle",
+    "quad",
+    "include",
+];
+
+/// The attributes of one statement
+struct Attributes<'a> {
+    keyword: &'a str,
+    values: HashMap<&'static str, Value<'a>>,
+    path: &'a Path,
+    line: usize,
+}
+
+impl<'a> Attributes<'a> {
+    /// Parse the attributes of `keyword`, stopping at the first token which
+    /// is not the name of one when `stop` accepts it
+    fn parse(
+        keyword: &'a str,
+        tokens: &mut std::iter::Peekable<std::str::SplitWhitespace<'a>>,
+        allowed: &[(&'static str, Arity)],
+        stop: fn(&str) -> bool,
+        path: &'a Path,
+        line: usize,
+    ) -> Result<Attributes<'a>, LoadError> {
+        let mut values = HashMap::new();
+        while let Some(&token) = tokens.peek() {
+            let (name, arity) = match allowed.iter().find(|(name, _)| *name == token) {
+                Some(&attribute) => attribute,
+                None if stop(token) => break,
+                None => {
+                    let names: Vec<&str> = allowed.iter().map(|(name, _)| *name).collect();
+                    return Err(LoadError::parse(
+                        path,
+                        line,
+                        format!(
+                            "unknown attribute '{}' for {}, expected one of: {}",
+                            token,
+                            keyword,
+                            names.join(", ")
+                        ),
+                    ));
+                }
+            };
+            tokens.next();
+            let value = match arity {
+                Arity::Color if tokens.peek() == Some(&"texture") => {
+                    tokens.next();
+                    match tokens.next() {
+                        Some(value) => Value::Texture(value),
+                        None => {
+                            return Err(LoadError::parse(
+                                path,
+                                line,
+                                format!("'{} texture' expects a name", name),
+                            ))
+                        }
+                    }
+                }
+                Arity::Numbers(_) | Arity::Color => {
+                    let n = match arity {
+                        Arity::Numbers(n) => n,
+                        _ => 3,
+                    };
+                    let mut numbers = Vec::with_capacity(n);
+                    for _ in 0..n {
+                        let number = tokens
+                            .next()
+                            .and_then(|t| t.parse::<f64>().ok())
+                            .filter(|x| x.is_finite());
+                        match number {
+                            Some(number) => numbers.push(number),
+                            None => {
+                                return Err(LoadError::parse(
+                                    path,
+                                    line,
+                                    format!(
+                                        "'{}' expects {} number{}{}",
+                                        name,
+                                        n,
+                                        if n > 1 { "s" } else { "" },
+                                        if let Arity::Color = arity {
+                                            " or a texture"
+                                        } else {
+                                            ""
+                                        }
+                                    ),
+                                ))
+                            }
+                        }
+                    }
+                    Value::Numbers(numbers)
+                }
+                Arity::Name => match tokens.next() {
+                    Some(value) => Value::Name(value),
+                    None => {
+                        return Err(LoadError::parse(
+                            path,
+                            line,
+                            format!("'{}' expects a name", name),
+                        ))
+                    }
+                },
+            };
+            if values.insert(name, value).is_some() {
+                return Err(LoadError::parse(
+                    path,
+                    line,
+                    format!("'{}' is given twice", name),
+                ));
+            }
+        }
+        Ok(Attributes {
+            keyword,
+            values,
+            path,
+            line,
+        })
+    }
+
+    fn error<S: Into<String>>(&self, message: S) -> LoadError {
+        LoadError::parse(self.path, self.line, message)
+    }
+
+    fn numbers(&self, name: &str) -> Option<&[f64]> {
+        match self.values.get(name) {
+            Some(Value::Numbers(numbers)) => Some(numbers),
+            _ => None,
+        }
+    }
+
+    fn number(&self, name: &str) -> Option<f64> {
+        self.numbers(name).map(|n| n[0])
+    }
+
+    fn vec3(&self, name: &str) -> Option<Vec3> {
+        self.numbers(name).map(|n| Vec3::new(n[0], n[1], n[2]))
+    }
+
+    fn name(&self, name: &str) -> Option<&'a str> {
+        match self.values.get(name) {
+            Some(Value::Name(value)) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// A color attribute, either constant or a texture among `textures`
+    fn color(
+        &self,
+        name: &str,
+        textures: &HashMap<&str, Arc<dyn Texture>>,
+    ) -> Result<Option<Arc<dyn Texture>>, LoadError> {
+        match self.values.get(name) {
+            Some(Value::Numbers(n)) => Ok(Some(Constant::new(Vec3::new(n[0], n[1], n[2])))),
+            Some(Value::Texture(texture)) => match textures.get(texture) {
+                Some(texture) => Ok(Some(Arc::clone(texture))),
+                None => Err(self.error(format!("unknown texture '{}'", texture))),
+            },
+            _ => Ok(None),
+        }
+    }
+
+    fn required<T>(&self, name: &str, value: Option<T>) -> Result<T, LoadError> {
+        value.ok_or_else(|| self.error(format!("{} needs '{}'", self.keyword, name)))
+    }
+
+    /// An integer setting, which must be positive unless it may be zero
+    fn integer(&self, name: &str, may_be_zero: bool) -> Result<Option<u64>, LoadError> {
+        match self.number(name) {
+            None => Ok(None),
+            Some(x) if x.fract() == 0.0 && x >= 0.0 && (may_be_zero || x > 0.0) => {
+                Ok(Some(x as u64))
+            }
+            Some(_) => Err(self.error(format!(
+                "'{}' must be a {} integer",
+                name,
+                if may_be_zero {
+                    "non-negative"
+                } else {
+                    "positive"
+                }
+            ))),
+        }
+    }
+}
+
+/// The texture types, see [`texture`]
+const TEXTURE_TYPES: [&str; 6] = ["checker", "image", "gradient", "marble", "wood", "clouds"];
+
+/// A checkerboard, an image loaded relative to `dir`, a gradient or noise,
+/// the cells of a checkerboard being colors or other `textures`
+fn texture<'a>(
+    tokens: &mut std::iter::Peekable<std::str::SplitWhitespace<'a>>,
+    textures: &HashMap<&str, Arc<dyn Texture>>,
+    dir: &Path,
+    path: &'a Path,
+    line: usize,
+) -> Result<Arc<dyn Texture>, LoadError> {
+    let kind = tokens.next().unwrap_or("");
+    let allowed = match kind {
+        "checker" => CHECKER,
+        "image" => IMAGE,
+        "gradient" => GRADIENT,
+        "marble" => MARBLE,
+        "wood" => WOOD,
+        "clouds" => CLOUDS,
+        _ => {
+            return Err(LoadError::parse(
+                path,
+                line,
+                format!(
+                    "unknown texture type '{}', expected one of: {}",
+                    kind,
+                    TEXTURE_TYPES.join(", ")
+                ),
+            ))
+        }
+    };
+    let attributes = Attributes::parse(kind, tokens, allowed, |_| false, path, line)?;
+    let seed = attributes.integer("seed", true)?.unwrap_or(0);
+    let scale = attributes.number("scale").unwrap_or(1.0);
+    if scale <= 0.0 {
+        return Err(attributes.error("'scale' must be positive"));
+    }
+    let color = |name: &str, default: Vec3| attributes.vec3(name).unwrap_or(default);
+    match kind {
+        "checker" => {
+            let size = attributes.number("size").unwrap_or(1.0);
+            if size <= 0.0 {
+                return Err(attributes.error("'size' must be positive"));
+            }
+            let even = attributes.required("even", attributes.color("even", textures)?)?;
+            let odd = attributes.required("odd", attributes.color("odd", textures)?)?;
+            Ok(Checker::new(size, even, odd))
+        }
+        "image" => {
+            let file = dir.join(attributes.required("file", attributes.name("file"))?);
+            let image = ImageTexture::load(&file).map_err(|error| LoadError::Io {
+                path: file.clone(),
+                error,
+            })?;
+            Ok(image)
+        }
+        "marble" => Ok(Marble::new(
+            seed,
+            scale,
+            color("base", Vec3::all(0.9)),
+            color("vein", Vec3::new(0.2, 0.2, 0.25)),
+        )),
+        "wood" => Ok(Wood::new(
+            seed,
+            scale,
+            color("light", Vec3::new(0.75, 0.55, 0.33)),
+            color("dark", Vec3::new(0.45, 0.28, 0.14)),
+        )),
+        "clouds" => Ok(Clouds::new(
+            seed,
+            scale,
+            color("sky", Vec3::new(0.3, 0.5, 0.9)),
+            color("cloud", Vec3::all(1.0)),
+        )),
+        _ => {
+            let start = attributes.required("start", attributes.vec3("start"))?;
+            let end = attributes.required("end", attributes.vec3("end"))?;
+            if start == end {
+                return Err(attributes.error("'start' and 'end' must be different points"));
+            }
+            Ok(Gradient::new(
+                start,
+                end,
+                attributes.required("from", attributes.vec3("from"))?,
+                attributes.required("to", attributes.vec3("to"))?,
+            ))
+        }
+    }
+}
+
+/// A material definition, instantiated for every object using it
+#[derive(Clone)]
+enum MaterialSpec {
+    Lambertian(Arc<dyn Texture>),
+    Metal(Arc<dyn Texture>, f64),
+    Dielectric(f64),
+    Light(Arc<dyn Texture>),
+}
+
+impl MaterialSpec {
+    fn is_type(token: &str) -> bool {
+        MATERIAL_TYPES.contains(&token)
+    }
+
+    fn parse<'a>(
+        tokens: &mut std::iter::Peekable<std::str::SplitWhitespace<'a>>,
+        textures: &HashMap<&str, Arc<dyn Texture>>,
+        path: &'a Path,
+        line: usize,
+    ) -> Result<MaterialSpec, LoadError> {
+        let kind = tokens.next().unwrap_or("");
+        let allowed = match kind {
+            "lambertian" => LAMBERTIAN,
+            "metal" => METAL,
+            "dielectric" => DIELECTRIC,
+            "light" => LIGHT,
+            _ => {
+                return Err(LoadError::parse(
+                    path,
+                    line,
+                    format!(
+                        "unknown material type '{}', expected one of: {}",
+                        kind,
+                        MATERIAL_TYPES.join(", ")
+                    ),
+                ))
+            }
+        };
+        let attributes = Attributes::parse(kind, tokens, allowed, |_| false, path, line)?;
+        // the albedo is a color or the name of a texture
+        let albedo = || match (attributes.vec3("albedo"), attributes.name("texture")) {
+            (Some(color), None) => Ok(Constant::new(color) as Arc<dyn Texture>),
+            (None, Some(name)) => match textures.get(name) {
+                Some(texture) => Ok(Arc::clone(texture)),
+                None => Err(attributes.error(format!("unknown texture '{}'", name))),
+            },
+            _ => Err(attributes.error(format!("{} needs one of 'albedo' or 'texture'", kind))),
+        };
+        let spec = match kind {
+            "lambertian" => MaterialSpec::Lambertian(albedo()?),
+            "metal" => MaterialSpec::Metal(albedo()?, attributes.number("fuzz").unwrap_or(0.0)),
+            "light" => {
+                if let Some(emit) = attributes.vec3("emit") {
+                    if emit.x < 0.0 || emit.y < 0.0 || emit.z < 0.0 {
+                        return Err(attributes.error("'emit' must not be negative"));
+                    }
+                }
+                let emit = attributes.required("emit", attributes.color("emit", textures)?)?;
+                MaterialSpec::Light(emit)
+            }
+            _ => {
+                let ior = attributes.number("ior").unwrap_or(1.5);
+                if ior <= 0.0 {
+                    return Err(attributes.error("'ior' must be positive"));
+                }
+                MaterialSpec::Dielectric(ior)
+            }
+        };
+        Ok(spec)
+    }
+
+    fn build(&self) -> Box<dyn Materialable> {
+        match self {
+            MaterialSpec::Lambertian(albedo) => Lambertian::textured(Arc::clone(albedo)),
+            MaterialSpec::Metal(albedo, fuzz) => Metal::textured(Arc::clone(albedo), *fuzz),
+            MaterialSpec::Dielectric(ior) => Dielectrics::new(*ior),
+            MaterialSpec::Light(emit) => DiffuseLight::textured(Arc::clone(emit)),
+        }
+    }
+}
+
+/// The material of a primitive: a reference by name or an inline definition
+/// closing the statement
+fn primitive_material<'a>(
+    attributes: &Attributes<'a>,
+    tokens: &mut std::iter::Peekable<std::str::SplitWhitespace<'a>>,
+    materials: &HashMap<&str, MaterialSpec>,
+    textures: &HashMap<&str, Arc<dyn Texture>>,
+) -> Result<Box<dyn Materialable>, LoadError> {
+    if tokens.peek().is_some() {
+        if attributes.name("material").is_some() {
+            return Err(attributes.error("a material is given twice"));
+        }
+        return Ok(
+            MaterialSpec::parse(tokens, textures, attributes.path, attributes.line)?.build(),
+        );
+    }
+    let name = attributes.required("material", attributes.name("material"))?;
+    match materials.get(name) {
+        Some(spec) => Ok(spec.build()),
+        None => Err(attributes.error(format!("unknown material '{}'", name))),
+    }
+}
+
+fn camera(attributes: &Attributes) -> Result<CameraSettings, LoadError> {
+    let defaults = CameraSettings::default();
+    let shutter = match attributes.numbers("shutter") {
+        Some(shutter) => (shutter[0], shutter[1]),
+        None => (defaults.shutter_open, defaults.shutter_close),
+    };
+    let camera = CameraSettings {
+        lookfrom: attributes.vec3("lookfrom").unwrap_or(defaults.lookfrom),
+        lookat: attributes.vec3("lookat").unwrap_or(defaults.lookat),
+        vup: attributes.vec3("vup").unwrap_or(defaults.vup),
+        vfov: attributes.number("vfov").unwrap_or(defaults.vfov),
+        aperture: attributes.number("aperture").unwrap_or(defaults.aperture),
+        focus_dist: attributes
+            .number("focus_dist")
+            .unwrap_or(defaults.focus_dist),
+        shutter_open: shutter.0,
+        shutter_close: shutter.1,
+    };
+    if camera.lookfrom == camera.lookat {
+        return Err(attributes.error("'lookfrom' and 'lookat' must be different points"));
+    }
+    if camera
+        .vup
+        .cross(camera.lookat - camera.lookfrom)
+        .squared_length()
+        == 0.0
+    {
+        return Err(attributes.error("'vup' must not be parallel to the viewing direction"));
+    }
+    if !(camera.vfov > 0.0 && camera.vfov < 180.0) {
+        return Err(attributes.error("'vfov' must be between 0 and 180 degrees"));
+    }
+    if camera.aperture < 0.0 {
+        return Err(attributes.error("'aperture' must not be negative"));
+    }
+    if camera.focus_dist <= 0.0 {
+        return Err(attributes.error("'focus_dist' must be positive"));
+    }
+    if camera.shutter_open > camera.shutter_close {
+        return Err(attributes.error("'shutter' must not close before it opens"));
+    }
+    Ok(camera)
+}
+
+/// Scale, then rotate around x, y and z in turn, then translate
+fn placement_transform(attributes: &Attributes) -> Result<Transform, LoadError> {
+    let mut transform = Transform::identity();
+    if let Some(scale) = attributes.vec3("scale") {
+        if scale.x == 0.0 || scale.y == 0.0 || scale.z == 0.0 {
+            return Err(attributes.error("'scale' must not be zero"));
+        }
+        transform = transform.then(&Transform::scaling(scale));
+    }
+    if let Some(degrees) = attributes.vec3("rotate") {
+        transform = transform
+            .then(&Transform::rotation(Vec3::new(1.0, 0.0, 0.0), degrees.x))
+            .then(&Transform::rotation(Vec3::new(0.0, 1.0, 0.0), degrees.y))
+            .then(&Transform::rotation(Vec3::new(0.0, 0.0, 1.0), degrees.z));
+    }
+    if let Some(offset) = attributes.vec3("translate") {
+        transform = transform.then(&Transform::translation(offset));
+    }
+    Ok(transform)
+}
+
+fn render_settings(
+    attributes: &Attributes,
+    settings: &mut RenderSettings,
+) -> Result<(), LoadError> {
+    if let Some(width) = attributes.integer("width", false)? {
+        settings.width = width as usize;
+    }
+    if let Some(height) = attributes.integer("height", false)? {
+        settings.height = height as usize;
+    }
+    if let Some(spp) = attributes.integer("spp", false)? {
+        settings.samples_per_pixel = spp as u32;
+    }
+    if let Some(max_depth) = attributes.integer("max_depth", false)? {
+        settings.max_depth = max_depth as u32;
+    }
+    if let Some(tile_size) = attributes.integer("tile_size", false)? {
+        settings.tile_size = tile_size as usize;
+    }
+    if let Some(seed) = attributes.integer("seed", true)? {
+        settings.seed = seed;
+    }
+    Ok(())
+}
+
+/// A uniform color, a map loaded relative to `dir`, or the sun and the sky
+fn environment(attributes: &Attributes, dir: &Path) -> Result<Box<dyn Environment>, LoadError> {
+    let has = |name: &str| attributes.values.contains_key(name);
+    let kinds: Vec<&str> = ["color", "map", "sun"]
+        .iter()
+        .copied()
+        .filter(|kind| has(kind))
+        .collect();
+    let kind = match kinds[..] {
+        [kind] => kind,
+        _ => return Err(attributes.error("background needs one of 'color', 'map' or 'sun'")),
+    };
+    for (name, applies_to) in [("rotation", "map"), ("turbidity", "sun")] {
+        if has(name) && kind != applies_to {
+            return Err(attributes.error(format!("'{}' only applies to '{}'", name, applies_to)));
+        }
+    }
+    if has("intensity") && kind == "color" {
+        return Err(attributes.error("'intensity' does not apply to 'color'"));
+    }
+    let intensity = attributes.number("intensity").unwrap_or(1.0);
+    if intensity < 0.0 {
+        return Err(attributes.error("'intensity' must not be negative"));
+    }
+
+    match kind {
+        "color" => Ok(Uniform::new(
+            attributes.required("color", attributes.vec3("color"))?,
+        )),
+        "map" => {
+            let file = dir.join(attributes.required("map", attributes.name("map"))?);
+            let mut map = EnvironmentMap::load(&file).map_err(|error| LoadError::Io {
+                path: file.clone(),
+                error,
+            })?;
+            map.set_rotation(attributes.number("rotation").unwrap_or(0.0));
+            map.set_intensity(intensity);
+            Ok(map)
+        }
+        _ => {
+            let sun = attributes.required("sun", attributes.numbers("sun"))?;
+            let (elevation, azimuth) = (sun[0], sun[1]);
+            if !(0.0..=90.0).contains(&elevation) {
+                return Err(attributes.error("the elevation of the sun must be within [0, 90]"));
+            }
+            let turbidity = attributes.number("turbidity").unwrap_or(3.0);
+            if !(1.7..=10.0).contains(&turbidity) {
+                return Err(attributes.error("'turbidity' must be within [1.7, 10]"));
+            }
+            let mut sky = SunSky::new(elevation, azimuth, turbidity);
+            sky.set_intensity(intensity);
+            Ok(sky)
+        }
+    }
+}
+
+/// Parse the description of a scene found at `path`, included files being
+/// looked up relative to it
+pub fn parse(source: &str, path: &Path) -> Result<Scene, LoadError> {
+    let dir = path.parent().unwrap_or_else(|| Path::new(""));
+    let mut scene = Scene {
+        world: World::new(),
+        camera: None,
+        settings: RenderSettings::default(),
+    };
+    let mut materials: HashMap<&str, MaterialSpec> = HashMap::new();
+    let mut textures: HashMap<&str, Arc<dyn Texture>> = HashMap::new();
+    let mut has_background = false;
+    // files included several times are loaded once and shared
+    let mut included: HashMap<PathBuf, Arc<World>> = HashMap::new();
+
+    for (i, raw) in source.lines().enumerate() {
+        let line = i + 1;
+        let content = raw.split('#').next().unwrap_or("");
+        let mut tokens = content.split_whitespace().peekable();
+        let keyword = match tokens.next() {
+            Some(keyword) => keyword,
+            None => continue,
+        };
+        let attributes = |tokens: &mut _, allowed, stop| {
+            Attributes::parse(keyword, tokens, allowed, stop, path, line)
+        };
+        match keyword {
+            "camera" => {
+                if scene.camera.is_some() {
+                    return Err(LoadError::parse(path, line, "the camera is given twice"));
+                }
+                scene.camera = Some(camera(&attributes(&mut tokens, CAMERA, |_| false)?)?);
+            }
+            "render" => render_settings(
+                &attributes(&mut tokens, RENDER, |_| false)?,
+                &mut scene.settings,
+            )?,
+            "material" => {
+                let name = match tokens.next() {
+                    Some(name) if !MaterialSpec::is_type(name) => name,
+                    _ => {
+                        return Err(LoadError::parse(
+                            path,
+                            line,
+                            "a material needs a name before its type",
+                        ))
+                    }
+                };
+                let spec = MaterialSpec::parse(&mut tokens, &textures, path, line)?;
+                if materials.insert(name, spec).is_some() {
+                    return Err(LoadError::parse(
+                        path,
+                        line,
+                        format!("material '{}' is defined twice", name),
+                    ));
+                }
+            }
+            "texture" => {
+                let name = match tokens.next() {
+                    Some(name) if !TEXTURE_TYPES.contains(&name) => name,
+                    _ => {
+                        return Err(LoadError::parse(
+                            path,
+                            line,
+                            "a texture needs a name before its type",
+                        ))
+                    }
+                };
+                let texture = texture(&mut tokens, &textures, dir, path, line)?;
+                if textures.insert(name, texture).is_some() {
+                    return Err(LoadError::parse(
+                        path,
+                        line,
+                        format!("texture '{}' is defined twice", name),
+                    ));
+                }
+            }
+            "sphere" => {
+                let sphere = attributes(&mut tokens, SPHERE, MaterialSpec::is_type)?;
+                let center = sphere.required("center", sphere.vec3("center"))?;
+                let radius = sphere.required("radius", sphere.number("radius"))?;
+                if radius == 0.0 {
+                    return Err(sphere.error("'radius' must not be 0"));
+                }
+                let material = primitive_material(&sphere, &mut tokens, &materials, &textures)?;
+                match sphere.vec3("move") {
+                    Some(offset) => scene.world.add(Sphere::moving(
+                        Keyframes::linear(0.0, center, 1.0, center + offset),
+                        radius,
+                        material,
+                    )),
+                    None => scene
+                        .world
+                        .add(Sphere::new(center.x, center.y, center.z, radius, material)),
+                }
+            }
+            "triangle" => {
+                let triangle = attributes(&mut tokens, TRIANGLE, MaterialSpec::is_type)?;
+                let a = triangle.required("a", triangle.vec3("a"))?;
+                let b = triangle.required("b", triangle.vec3("b"))?;
+                let c = triangle.required("c", triangle.vec3("c"))?;
+                if (b - a).cross(c - a).squared_length() == 0.0 {
+                    return Err(triangle.error("'a', 'b' and 'c' must not be collinear"));
+                }
+                let material = primitive_material(&triangle, &mut tokens, &materials, &textures)?;
+                scene.world.add(Triangle::new(a, b, c, material));
+            }
+            "quad" => {
+                let quad = attributes(&mut tokens, QUAD, MaterialSpec::is_type)?;
+                let corner = quad.required("corner", quad.vec3("corner"))?;
+                let u = quad.required("u", quad.vec3("u"))?;
+                let v = quad.required("v", quad.vec3("v"))?;
+                if u.cross(v).squared_length() == 0.0 {
+                    return Err(quad.error("'u' and 'v' must not be parallel"));
+                }
+                let material = primitive_material(&quad, &mut tokens, &materials, &textures)?;
+                scene.world.add(Quad::new(corner, u, v, material));
+            }
+            "background" => {
+                if has_background {
+                    return Err(LoadError::parse(
+                        path,
+                        line,
+                        "the background is given twice",
+                    ));
+                }
+                has_background = true;
+                let background = attributes(&mut tokens, BACKGROUND, |_| false)?;
+                scene.world.set_environment(environment(&background, dir)?);
+            }
+            "include" => {
+                let file = match tokens.next() {
+                    Some(file) => dir.join(file),
+                    None => return Err(LoadError::parse(path, line, "include needs a file")),
+                };
+                // scene files cannot include each other, which could loop
+                let is_scene = super::extension(&file).as_deref() == Some("scene");
+                if !super::is_scene_file(&file) || is_scene {
+                    return Err(LoadError::parse(
+                        path,
+                        line,
+                        format!(
+                            "cannot include {}, expected a .obj, .ply, .gltf or .glb file",
+                            file.display()
+                        ),
+                    ));
+                }
+                let placement = attributes(&mut tokens, INCLUDE, |_| false)?;
+                let object = match included.entry(file) {
+                    Entry::Occupied(entry) => Arc::clone(entry.get()),
+                    Entry::Vacant(entry) => {
+                        let world = super::load_file(entry.key())?.world;
+                        Arc::clone(entry.insert(Arc::new(world)))
+                    }
+                };
+                let instance = Transformed::new(object, placement_transform(&placement)?);
+                match placement.vec3("move") {
+                    Some(offset) => scene.world.add(Transformed::moving(
+                        Arc::<Transformed<World>>::from(instance),
+                        Keyframes::linear(
+                            0.0,
+                            Placement::translation(Vec3::zeros()),
+                            1.0,
+                            Placement::translation(offset),
+                        ),
+                    )),
+                    None => scene.world.add(instance),
+                }
+            }
+            _ => {
+                return Err(LoadError::parse(
+                    path,
+                    line,
+                    format!(
+                        "unknown statement '{}', expected one of: {}",
+                        keyword,
+                        STATEMENTS.join(", ")
+                    ),
+                ))
+            }
+        }
+    }
+    Ok(scene)
+}
+
+/// Read and parse a scene file
+pub fn load<P: AsRef<Path>>(path: P) -> Result<Scene, LoadError> {
+    let source = crate::loaders::read_to_string(&path)?;
+    parse(&source, path.as_ref())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ray::Ray;
+    use crate::util::TempDir;
+
+    const SCENE: &str = "\
+# a test scene
+camera lookfrom 0 0 5 lookat 0 0 0 vfov 40 aperture 0
+render width 64 height 32 spp 4
+
+material red lambertian albedo 0.8 0.1 0.1
+sphere center 0 0 0 radius 1 material red
+triangle a -1 -1 -3 b 1 -1 -3 c 0 1 -3 metal albedo 0.5 0.5 0.5 fuzz 0.1
+";
+
+    #[test]
+    fn parse_test() {
+        let scene = parse(SCENE, Path::new("test.scene")).unwrap();
+        let camera = scene.camera.unwrap();
+        assert_eq!(camera.lookfrom, Vec3::new(0.0, 0.0, 5.0));
+        assert_eq!(camera.vfov, 40.0);
+        assert_eq!(camera.focus_dist, CameraSettings::default().focus_dist);
+        assert_eq!(scene.settings.width, 64);
+        assert_eq!(scene.settings.samples_per_pixel, 4);
+        assert_eq!(
+            scene.settings.max_depth,
+            RenderSettings::default().max_depth
+        );
+
+        let r = Ray::new(Vec3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0));
+        assert_eq!(scene.world.hit(&r, 0.001, f64::MAX).map(|h| h.t), Some(4.0));
+        let r = Ray::new(Vec3::new(0.0, 0.9, -2.0), Vec3::new(0.0, 0.0, -1.0));
+        assert_eq!(scene.world.hit(&r, 0.001, f64::MAX).map(|h| h.t), Some(1.0));
+
+        // a hollow sphere, whose normals point inwards
+        let bubble = "sphere center 0 0 0 radius -1 dielectric ior 1.5";
+        let scene = parse(bubble, Path::new("test.scene")).unwrap();
+        let r = Ray::new(Vec3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0));
+        let hit = scene.world.hit(&r, 0.001, f64::MAX).unwrap();
+        assert_eq!(hit.t, 4.0);
+        assert_eq!(hit.geometric_normal, Vec3::new(0.0, 0.0, -1.0));
+        assert!(!hit.front_face);
+    }
+
+    #[test]
+    fn light_test() {
+        let source = "\
+background color 0 0 0
+material lamp light emit 4 4 4
+quad corner -1 2 -1 u 2 0 0 v 0 0 2 material lamp
+sphere center 0 0 0 radius 1 light emit 1 2 3
+";
+        let scene = parse(source, Path::new("test.scene")).unwrap();
+        let up = Vec3::new(0.0, 1.0, 0.0);
+        assert_eq!(scene.world.background(up), Vec3::zeros());
+
+        let r = Ray::new(Vec3::new(0.5, 5.0, 0.5), -up);
+        let hit = scene.world.hit(&r, 0.001, f64::MAX).unwrap();
+        assert_eq!(hit.t, 3.0);
+        assert_eq!(hit.material.emitted(&r, &hit), Vec3::all(4.0));
+        let r = Ray::new(Vec3::new(0.0, -5.0, 0.0), up);
+        let hit = scene.world.hit(&r, 0.001, f64::MAX).unwrap();
+        assert_eq!(hit.material.emitted(&r, &hit), Vec3::new(1.0, 2.0, 3.0));
+
+        let flat = "quad corner 0 0 0 u 1 0 0 v 2 0 0 light emit 1 1 1";
+        assert!(parse(flat, Path::new("test.scene")).is_err());
+        assert!(parse("background", Path::new("test.scene")).is_err());
+    }
+
+    #[test]
+    fn motion_test() {
+        let source = "\
+camera lookfrom 0 0 5 lookat 0 0 0 shutter 0 1
+sphere center 0 0 0 radius 1 move 4 0 0 lambertian albedo 0.5 0.5 0.5
+";
+        let scene = parse(source, Path::new("test.scene")).unwrap();
+        let camera = scene.camera.unwrap();
+        assert_eq!((camera.shutter_open, camera.shutter_close), (0.0, 1.0));
+
+        let start = Ray::new(Vec3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0));
+        assert!(scene.world.hit(&start, 0.001, f64::MAX).is_some());
+        let end = Ray::with_time(start.origin, start.direction, 1.0);
+        assert!(scene.world.hit(&end, 0.001, f64::MAX).is_none());
+        let moved = Ray::with_time(Vec3::new(4.0, 0.0, 5.0), start.direction, 1.0);
+        assert!(scene.world.hit(&moved, 0.001, f64::MAX).is_some());
+
+        let backwards = "camera lookfrom 0 0 5 lookat 0 0 0 shutter 1 0";
+        assert!(parse(backwards, Path::new("test.scene")).is_err());
+    }
+
+    #[test]
+    fn error_test() {
+        let error = |source: &str| {
+            parse(source, Path::new("bad.scene"))
+                .err()
+                .unwrap()
+                .to_string()
+        };
+        assert_eq!(
+            error("\nsphere center 0 0 0 material red"),
+            "bad.scene:2: sphere needs 'radius'"
+        );
+        assert_eq!(
+            error("sphere center 0 0 0 radius 1 material red"),
+            "bad.scene:1: unknown material 'red'"
+        );
+        assert_eq!(
+            error("sphere center 0 0 radius 1"),
+            "bad.scene:1: 'center' expects 3 numbers"
+        );
+        assert_eq!(
+            error("sphere center 0 0 0 radius 0 dielectric ior 1.5"),
+            "bad.scene:1: 'radius' must not be 0"
+        );
+        assert_eq!(
+            error("triangle a 0 0 0 b 1 1 1 c 2 2 2 dielectric ior 1.5"),
+            "bad.scene:1: 'a', 'b' and 'c' must not be collinear"
+        );
+        assert_eq!(
+            error("sphere center 0 0 0 radius nan material red"),
+            "bad.scene:1: 'radius' expects 1 number"
+        );
+        assert_eq!(
+            error("material glass dielectric ior inf"),
+            "bad.scene:1: 'ior' expects 1 number"
+        );
+        assert_eq!(
+            error("sphere centre 0 0 0"),
+            "bad.scene:1: unknown attribute 'centre' for sphere, expected one of: center, radius, material, move"
+        );
+        assert_eq!(
+            error("material red plastic"),
+            "bad.scene:1: unknown material type 'plastic', expected one of: lambertian, metal, dielectric, light"
+        );
+        assert_eq!(
+            error("render width 0"),
+            "bad.scene:1: 'width' must be a positive integer"
+        );
+        assert_eq!(
+            error("camera lookfrom 0 0 0"),
+            "bad.scene:1: 'lookfrom' and 'lookat' must be different points"
+        );
+        assert!(error("cube size 1").starts_with("bad.scene:1: unknown statement 'cube'"));
+        assert!(error("include other.scene").starts_with("bad.scene:1: cannot include"));
+    }
+
+    #[test]
+    fn include_test() {
+        let temp = TempDir::new("include_test");
+        let dir = temp.path();
+        std::fs::write(
+            dir.join("quad.obj"),
+            "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nf 1 2 3 4\n",
+        )
+        .unwrap();
+        let source =
+            "include quad.obj\ninclude quad.obj scale 2 2 2 rotate 0 0 90 translate 0 0 -1\n";
+        let scene = parse(source, &dir.join("test.scene")).unwrap();
+
+        let r = Ray::new(Vec3::new(0.5, 0.5, 5.0), Vec3::new(0.0, 0.0, -1.0));
+        assert_eq!(scene.world.hit(&r, 0.001, f64::MAX).map(|h| h.t), Some(5.0));
+        // the second copy covers x in [-2, 0] and y in [0, 2]
+        let r = Ray::new(Vec3::new(-1.5, 1.5, 5.0), Vec3::new(0.0, 0.0, -1.0));
+        let t = scene.world.hit(&r, 0.001, f64::MAX).map(|h| h.t).unwrap();
+        assert!((t - 6.0).abs() < 1e-9);
+        assert!(parse("include quad.obj spin 1 2 3", &dir.join("test.scene")).is_err());
+    }
+
+    #[test]
+    fn environment_map_test() {
+        let temp = TempDir::new("environment_test");
+        let dir = temp.path();
+        // the top half is brighter than the bottom one
+        let frame = crate::render::FrameBuffer::new(
+            2,
+            2,
+            vec![
+                Vec3::all(2.0),
+                Vec3::all(2.0),
+                Vec3::all(1.0),
+                Vec3::all(1.0),
+            ],
+        );
+        crate::image::save(&frame, dir.join("sky.pfm")).unwrap();
+        let scene = parse(
+            "background map sky.pfm rotation 90 intensity 3",
+            &dir.join("test.scene"),
+        )
+        .unwrap();
+        let up = Vec3::new(0.0, 1.0, 0.0);
+        assert_eq!(scene.world.background(up), Vec3::all(6.0));
+        assert_eq!(scene.world.background(-up), Vec3::all(3.0));
+        assert!(scene.world.samples_environment());
+
+        let error = |source| {
+            parse(source, &dir.join("test.scene"))
+                .err()
+                .unwrap()
+                .to_string()
+        };
+        assert!(error("background map missing.hdr").contains("missing.hdr"));
+        assert!(error("background color 0 0 0 map sky.pfm").ends_with("'color', 'map' or 'sun'"));
+        assert!(error("background color 0 0 0 intensity 2").ends_with("does not apply to 'color'"));
+        assert!(error("background map sky.pfm turbidity 2").ends_with("only applies to 'sun'"));
+
+        let scene = parse(
+            "background sun 45 90 turbidity 2.5",
+            &dir.join("test.scene"),
+        )
+        .unwrap();
+        let towards_sun = Vec3::new(1.0, 1.0, 0.0);
+        assert!(scene.world.background(towards_sun).x > 100.0 * scene.world.background(up).x);
+        assert!(error("background sun -10 0").contains("elevation"));
+        assert!(error("background sun 10 0 turbidity 20").contains("turbidity"));
+    }
+
+    #[test]
+    fn texture_test() {
+        let temp = TempDir::new("texture_test");
+        let dir = temp.path();
+        let image = crate::render::FrameBuffer::new(1, 1, vec![Vec3::all(1.0)]);
+        crate::image::save(&image, dir.join("white.png")).unwrap();
+        let source = "\
+texture checks checker size 2 even 1 1 1 odd 0 0 0
+texture white image file white.png
+texture fade gradient start 0 0 0 end 0 1 0 from 0 0 0 to 1 1 1
+material floor lambertian texture checks
+quad corner -4 0.5 -4 u 8 0 0 v 0 0 8 material floor
+sphere center 0 5 0 radius 1 metal texture white fuzz 0
+sphere center 0 -5 0 radius 1 lambertian texture fade
+texture nested checker size 2 even texture fade odd 0.5 0.5 0.5
+sphere center 10 0 0 radius 1 light emit texture checks
+sphere center 20 0 0 radius 1 lambertian texture nested
+";
+        let scene = parse(source, &dir.join("test.scene")).unwrap();
+        let albedo = |origin: Vec3, direction: Vec3| {
+            let r = Ray::new(origin, direction);
+            let hit = scene.world.hit(&r, 0.001, f64::MAX).unwrap();
+            let mut sampler = crate::sampler::Sampler::new(0, 0);
+            hit.material.sample(&r, &hit, &mut sampler).unwrap().weight
+        };
+        let down = Vec3::new(0.0, -1.0, 0.0);
+        // the squares of side 2 alternate along x and z
+        assert_eq!(albedo(Vec3::new(0.5, 1.0, 0.5), down), Vec3::all(1.0));
+        assert_eq!(albedo(Vec3::new(2.5, 1.0, 0.5), down), Vec3::zeros());
+        assert_eq!(albedo(Vec3::new(-0.5, 1.0, 0.5), down), Vec3::zeros());
+        assert_eq!(albedo(Vec3::new(0.0, 10.0, 0.0), down), Vec3::all(1.0));
+        // the bottom of the lower sphere is below the start of the gradient
+        assert_eq!(albedo(Vec3::new(0.0, -10.0, 0.0), -down), Vec3::zeros());
+        // textures also drive emission and the cells of a checkerboard
+        let forward = Vec3::new(0.0, 0.0, -1.0);
+        let emitted = |origin: Vec3| {
+            let r = Ray::new(origin, forward);
+            let hit = scene.world.hit(&r, 0.001, f64::MAX).unwrap();
+            hit.material.emitted(&r, &hit)
+        };
+        assert_eq!(emitted(Vec3::new(9.5, 0.0, 5.0)), Vec3::all(1.0));
+        assert_eq!(emitted(Vec3::new(10.5, 0.0, 5.0)), Vec3::zeros());
+        assert_eq!(albedo(Vec3::new(19.5, 0.0, 5.0), forward), Vec3::all(0.5));
+        let fade = albedo(Vec3::new(20.5, 0.25, 5.0), forward);
+        assert!((fade - Vec3::all(0.25)).length() < 1e-9);
+
+        let error = |source| {
+            parse(source, &dir.join("test.scene"))
+                .err()
+                .unwrap()
+                .to_string()
+        };
+        assert!(error("texture checks granite").contains("unknown texture type 'granite'"));
+        assert!(error("texture a marble scale 0").ends_with("'scale' must be positive"));
+        assert!(error("texture a wood seed 1.5").ends_with("non-negative integer"));
+        for noise in ["marble seed 3 scale 2", "wood light 1 1 1", "clouds"] {
+            let source = format!(
+                "texture a {}\nsphere center 0 0 0 radius 1 lambertian texture a",
+                noise
+            );
+            assert!(parse(&source, &dir.join("test.scene")).is_ok());
+        }
+        assert!(error("texture checker size 1").ends_with("a name before its type"));
+        assert!(error("texture a checker size 0 even 1 1 1 odd 0 0 0").ends_with("positive"));
+        assert!(error("texture a image file missing.png").contains("missing.png"));
+        assert!(error("material a lambertian texture b").ends_with("unknown texture 'b'"));
+        assert!(error("material a lambertian").ends_with("needs one of 'albedo' or 'texture'"));
+        assert!(error("material a light emit texture b").ends_with("unknown texture 'b'"));
+        assert!(error("material a light emit texture").ends_with("'emit texture' expects a name"));
+        assert!(error("material a light emit 1 1").ends_with("expects 3 numbers or a texture"));
+        assert!(error("texture a checker even 1 1 1").ends_with("checker needs 'odd'"));
+    }
+
+    #[test]
+    fn builtin_test() {
+        for name in super::super::NAMES.iter() {
+            let scene = super::super::load(name).unwrap();
+            assert!(scene.camera.is_some());
+        }
+    }
+}
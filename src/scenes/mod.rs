@@ -1,2 +1,70 @@
-pub mod simple;
-pub mod sphere_sea;
+pub mod format;
+
+use crate::camera::CameraSettings;
+use crate::hittable::World;
+use crate::loaders::{GltfScene, LoadError, ObjModel, PlyModel};
+use crate::render::RenderSettings;
+use std::path::Path;
+
+/// Names of the scenes shipped with the crate
+pub const NAMES: [&str; 3] = ["simple", "sphere_sea", "cornell_box"];
+
+/// A world with the camera and render settings it was described with
+pub struct Scene {
+    pub world: World,
+    /// the camera of the file, if it has one
+    pub camera: Option<CameraSettings>,
+    /// the defaults updated with the settings of the file
+    pub settings: RenderSettings,
+}
+
+/// Load one of the built-in scenes by its name
+pub fn load(name: &str) -> Option<Scene> {
+    let source = match name {
+        "simple" => include_str!("simple.scene"),
+        "sphere_sea" => include_str!("sphere_sea.scene"),
+        "cornell_box" => include_str!("cornell_box.scene"),
+        _ => return None,
+    };
+    let path = format!("{}.scene", name);
+    Some(format::parse(source, Path::new(&path)).expect("built-in scenes are valid"))
+}
+
+/// Extensions of the files [`load_file`] understands
+pub const FILE_EXTENSIONS: [&str; 5] = ["scene", "obj", "ply", "gltf", "glb"];
+
+/// Whether `path` names a file [`load_file`] knows how to read
+pub fn is_scene_file<P: AsRef<Path>>(path: P) -> bool {
+    extension(path.as_ref()).is_some_and(|e| FILE_EXTENSIONS.contains(&e.as_str()))
+}
+
+fn extension(path: &Path) -> Option<String> {
+    Some(path.extension()?.to_str()?.to_ascii_lowercase())
+}
+
+/// Build a scene from a file, the loader being picked by the extension
+pub fn load_file<P: AsRef<Path>>(path: P) -> Result<Scene, LoadError> {
+    let path = path.as_ref();
+    let mut world = World::new();
+    let mut camera = None;
+    match extension(path).as_deref() {
+        Some("scene") => return format::load(path),
+        Some("obj") => ObjModel::load(path)?.add_to(&mut world),
+        Some("ply") => world.add(PlyModel::load(path)?.into_vertex_colored_mesh()),
+        Some("gltf") | Some("glb") => {
+            let scene = GltfScene::load(path)?;
+            world = scene.world;
+            camera = scene.camera;
+        }
+        _ => {
+            return Err(LoadError::Unsupported {
+                path: path.to_path_buf(),
+            })
+        }
+    }
+    Ok(Scene {
+        world,
+        camera,
+        settings: RenderSettings::default(),
+    })
+}
@@ -1 +1,103 @@
-struct Task {}
+//! Splitting a frame into tiles and handing them out to worker threads.
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// A rectangular region of the image rendered as one unit of work.
+///
+/// `x` and `y` locate the top left pixel of the tile, rows being counted from
+/// the top of the image like in [`FrameBuffer`](crate::render::FrameBuffer).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Task {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Task {
+    /// Cover a `width` x `height` image with tiles of `tile_size` x `tile_size`
+    /// pixels, the tiles on the right and bottom edges being cropped to the image.
+    pub fn split(width: usize, height: usize, tile_size: usize) -> Vec<Task> {
+        let mut tasks = Vec::new();
+        for y in (0..height).step_by(tile_size) {
+            for x in (0..width).step_by(tile_size) {
+                tasks.push(Task {
+                    x,
+                    y,
+                    width: tile_size.min(width - x),
+                    height: tile_size.min(height - y),
+                });
+            }
+        }
+        tasks
+    }
+
+    /// Number of pixels covered by the tile
+    pub fn area(&self) -> usize {
+        self.width * self.height
+    }
+}
+
+/// A queue of tasks shared by every worker.
+///
+/// Workers keep taking the next task until the queue is empty, so a thread
+/// which got cheap tiles simply ends up rendering more of them.
+pub struct TaskQueue {
+    tasks: Vec<Task>,
+    next: AtomicUsize,
+}
+
+impl TaskQueue {
+    pub fn new(tasks: Vec<Task>) -> TaskQueue {
+        TaskQueue {
+            tasks,
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    /// Take the next task, `None` once every task has been handed out
+    pub fn pop(&self) -> Option<Task> {
+        let i = self.next.fetch_add(1, Ordering::Relaxed);
+        self.tasks.get(i).copied()
+    }
+
+    /// Total number of tasks in the queue, including the ones already taken
+    pub fn len(&self) -> usize {
+        self.tasks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tasks.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn split_test() {
+        let tasks = Task::split(70, 33, 32);
+        assert_eq!(tasks.len(), 6);
+        assert_eq!(tasks.iter().map(Task::area).sum::<usize>(), 70 * 33);
+        assert_eq!(
+            tasks[5],
+            Task {
+                x: 64,
+                y: 32,
+                width: 6,
+                height: 1
+            }
+        );
+    }
+
+    #[test]
+    fn queue_test() {
+        let queue = TaskQueue::new(Task::split(4, 4, 2));
+        let mut taken = 0;
+        while queue.pop().is_some() {
+            taken += 1;
+        }
+        assert_eq!(taken, queue.len());
+        assert_eq!(queue.pop(), None);
+    }
+}
@@ -0,0 +1,75 @@
+use super::Texture;
+use crate::image;
+use crate::render::FrameBuffer;
+use crate::vec3::Vec3;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+
+/// An image wrapped around the texture coordinates, `(0, 0)` being the
+/// bottom left corner of the image and `(1, 1)` the top right one.
+///
+/// Pixels are filtered bilinearly and the image repeats beyond `[0, 1]`.
+pub struct ImageTexture {
+    image: FrameBuffer,
+    /// multiplies every pixel
+    tint: Vec3,
+}
+
+impl ImageTexture {
+    pub fn new(image: FrameBuffer) -> Arc<ImageTexture> {
+        ImageTexture::tinted(image, Vec3::all(1.0))
+    }
+
+    /// A texture whose pixels are multiplied by `tint`
+    pub fn tinted(image: FrameBuffer, tint: Vec3) -> Arc<ImageTexture> {
+        Arc::new(ImageTexture { image, tint })
+    }
+
+    /// Read the texture from an image file, see [`image::load`]
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Arc<ImageTexture>> {
+        Ok(ImageTexture::new(image::load(path)?))
+    }
+
+    fn pixel(&self, x: i64, y: i64) -> Vec3 {
+        let (width, height) = (self.image.width as i64, self.image.height as i64);
+        let x = x.rem_euclid(width) as usize;
+        // rows are stored from the top
+        let y = (height - 1 - y.rem_euclid(height)) as usize;
+        self.image.pixels()[y * self.image.width + x]
+    }
+}
+
+impl Texture for ImageTexture {
+    fn value(&self, u: f64, v: f64, _p: Vec3) -> Vec3 {
+        if !(u.is_finite() && v.is_finite()) {
+            return Vec3::zeros();
+        }
+        // pixel centers lie at half integer coordinates
+        let x = u * self.image.width as f64 - 0.5;
+        let y = v * self.image.height as f64 - 0.5;
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+        let (x0, y0) = (x0 as i64, y0 as i64);
+        let bottom = (1.0 - fx) * self.pixel(x0, y0) + fx * self.pixel(x0 + 1, y0);
+        let top = (1.0 - fx) * self.pixel(x0, y0 + 1) + fx * self.pixel(x0 + 1, y0 + 1);
+        self.tint * ((1.0 - fy) * bottom + fy * top)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn value_test() {
+        // a black top row above a white bottom row
+        let pixels = vec![Vec3::zeros(), Vec3::zeros(), Vec3::all(1.0), Vec3::all(1.0)];
+        let texture = ImageTexture::tinted(FrameBuffer::new(2, 2, pixels), Vec3::all(0.5));
+        assert_eq!(texture.value(0.25, 0.25, Vec3::zeros()), Vec3::all(0.5));
+        assert_eq!(texture.value(0.75, 0.75, Vec3::zeros()), Vec3::zeros());
+        assert_eq!(texture.value(0.5, 0.5, Vec3::zeros()), Vec3::all(0.25));
+        // the image repeats, blending its top and bottom rows at the edge
+        assert_eq!(texture.value(1.25, 0.0, Vec3::zeros()), Vec3::all(0.25));
+    }
+}
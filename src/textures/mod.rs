@@ -0,0 +1,126 @@
+//! Colors varying over surfaces.
+//!
+//! A [`Texture`] is evaluated at the texture coordinates `(u, v)` of a hit
+//! point and at the point itself, so that it can either be mapped onto the
+//! surface like an image or be solid like a checkerboard filling space.
+//! Marble, wood and clouds are solid textures built on [`crate::noise`].
+//! Textures are shared by the materials using them.
+mod image;
+mod procedural;
+
+pub use self::image::ImageTexture;
+pub use procedural::{Clouds, Marble, Wood};
+
+use crate::vec3::Vec3;
+use std::sync::Arc;
+
+pub trait Texture: Send + Sync {
+    /// The color at texture coordinates `(u, v)` of the point `p`
+    fn value(&self, u: f64, v: f64, p: Vec3) -> Vec3;
+}
+
+/// The same color everywhere
+pub struct Constant {
+    color: Vec3,
+}
+
+impl Constant {
+    pub fn new(color: Vec3) -> Arc<Constant> {
+        Arc::new(Constant { color })
+    }
+}
+
+impl Texture for Constant {
+    fn value(&self, _u: f64, _v: f64, _p: Vec3) -> Vec3 {
+        self.color
+    }
+}
+
+/// Cubes of side `size` filling space, alternately `even` and `odd`.
+///
+/// A surface lying on a face between cells, such as the plane `y = 0`,
+/// flickers from one color to the other and should be moved slightly.
+pub struct Checker {
+    size: f64,
+    even: Arc<dyn Texture>,
+    odd: Arc<dyn Texture>,
+}
+
+impl Checker {
+    pub fn new(size: f64, even: Arc<dyn Texture>, odd: Arc<dyn Texture>) -> Arc<Checker> {
+        Arc::new(Checker { size, even, odd })
+    }
+}
+
+impl Texture for Checker {
+    fn value(&self, u: f64, v: f64, p: Vec3) -> Vec3 {
+        let cell = |x: f64| (x / self.size).floor() as i64;
+        if (cell(p.x) + cell(p.y) + cell(p.z)).rem_euclid(2) == 0 {
+            self.even.value(u, v, p)
+        } else {
+            self.odd.value(u, v, p)
+        }
+    }
+}
+
+/// A linear blend from color `from` at point `start` to `to` at point `end`,
+/// constant beyond both ends
+pub struct Gradient {
+    start: Vec3,
+    end: Vec3,
+    from: Vec3,
+    to: Vec3,
+}
+
+impl Gradient {
+    pub fn new(start: Vec3, end: Vec3, from: Vec3, to: Vec3) -> Arc<Gradient> {
+        Arc::new(Gradient {
+            start,
+            end,
+            from,
+            to,
+        })
+    }
+}
+
+impl Texture for Gradient {
+    fn value(&self, _u: f64, _v: f64, p: Vec3) -> Vec3 {
+        let axis = self.end - self.start;
+        let t = (p - self.start).dot(axis) / axis.squared_length();
+        (1.0 - t.clamp(0.0, 1.0)) * self.from + t.clamp(0.0, 1.0) * self.to
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn checker_test() {
+        let checker = Checker::new(
+            2.0,
+            Constant::new(Vec3::all(1.0)),
+            Constant::new(Vec3::zeros()),
+        );
+        let at = |x, z| checker.value(0.0, 0.0, Vec3::new(x, -0.5, z)).x;
+        // the cell below the origin is odd
+        assert_eq!(at(0.5, 0.5), 0.0);
+        assert_eq!(at(2.5, 0.5), 1.0);
+        assert_eq!(at(-0.5, 0.5), 1.0);
+        assert_eq!(at(-0.5, -0.5), 0.0);
+    }
+
+    #[test]
+    fn gradient_test() {
+        let gradient = Gradient::new(
+            Vec3::zeros(),
+            Vec3::new(0.0, 4.0, 0.0),
+            Vec3::new(1.0, 0.0, 0.0),
+            Vec3::new(0.0, 0.0, 1.0),
+        );
+        let at = |y| gradient.value(0.0, 0.0, Vec3::new(3.0, y, 0.0));
+        assert_eq!(at(-1.0), Vec3::new(1.0, 0.0, 0.0));
+        assert_eq!(at(1.0), Vec3::new(0.75, 0.0, 0.25));
+        assert_eq!(at(5.0), Vec3::new(0.0, 0.0, 1.0));
+    }
+}
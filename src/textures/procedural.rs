@@ -0,0 +1,133 @@
+use super::Texture;
+use crate::noise::Perlin;
+use crate::util;
+use crate::vec3::Vec3;
+use std::sync::Arc;
+
+/// Octaves summed by the procedural textures
+const OCTAVES: u32 = 7;
+
+/// Veins of `vein` color through `base`, following bands across the x axis
+/// bent by turbulence. `scale` is the frequency of the noise.
+pub struct Marble {
+    noise: Perlin,
+    scale: f64,
+    base: Vec3,
+    vein: Vec3,
+}
+
+impl Marble {
+    pub fn new(seed: u64, scale: f64, base: Vec3, vein: Vec3) -> Arc<Marble> {
+        Arc::new(Marble {
+            noise: Perlin::new(seed),
+            scale,
+            base,
+            vein,
+        })
+    }
+}
+
+impl Texture for Marble {
+    fn value(&self, _u: f64, _v: f64, p: Vec3) -> Vec3 {
+        let p = self.scale * p;
+        let phase = 2.0 * p.x + 6.0 * self.noise.turbulence(p, OCTAVES);
+        // narrow the troughs of the sine into thin veins
+        let t = (0.5 - 0.5 * phase.sin()).powi(3);
+        util::lerp(self.base, self.vein, t)
+    }
+}
+
+/// Growth rings around the y axis from `light` to `dark` wood, made uneven by
+/// noise. `scale` is the number of rings per unit.
+pub struct Wood {
+    noise: Perlin,
+    scale: f64,
+    light: Vec3,
+    dark: Vec3,
+}
+
+impl Wood {
+    pub fn new(seed: u64, scale: f64, light: Vec3, dark: Vec3) -> Arc<Wood> {
+        Arc::new(Wood {
+            noise: Perlin::new(seed),
+            scale,
+            light,
+            dark,
+        })
+    }
+}
+
+impl Texture for Wood {
+    fn value(&self, _u: f64, _v: f64, p: Vec3) -> Vec3 {
+        let p = self.scale * p;
+        // the grain stretches along the trunk
+        let grain = Vec3::new(p.x, 0.1 * p.y, p.z);
+        let radius = (p.x * p.x + p.z * p.z).sqrt() + 0.5 * self.noise.fbm(grain, OCTAVES);
+        let ring = radius - radius.floor();
+        // early wood grows slowly into the dark late wood of each ring
+        util::lerp(self.light, self.dark, ring * ring)
+    }
+}
+
+/// Soft `cloud` puffs over `sky`, from fractal noise. `scale` is the
+/// frequency of the largest puffs.
+pub struct Clouds {
+    noise: Perlin,
+    scale: f64,
+    sky: Vec3,
+    cloud: Vec3,
+}
+
+impl Clouds {
+    pub fn new(seed: u64, scale: f64, sky: Vec3, cloud: Vec3) -> Arc<Clouds> {
+        Arc::new(Clouds {
+            noise: Perlin::new(seed),
+            scale,
+            sky,
+            cloud,
+        })
+    }
+}
+
+impl Texture for Clouds {
+    fn value(&self, _u: f64, _v: f64, p: Vec3) -> Vec3 {
+        let density = self.noise.fbm(self.scale * p, OCTAVES);
+        // thin out the clouds so that some sky shows between them
+        let t = (2.0 * density + 0.2).clamp(0.0, 1.0);
+        util::lerp(self.sky, self.cloud, t)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::sampler::Sampler;
+
+    #[test]
+    fn range_test() {
+        let (a, b) = (Vec3::new(0.9, 0.8, 0.7), Vec3::new(0.1, 0.2, 0.3));
+        let textures: [Arc<dyn Texture>; 3] = [
+            Marble::new(1, 4.0, a, b),
+            Wood::new(2, 3.0, a, b),
+            Clouds::new(3, 2.0, a, b),
+        ];
+        let mut sampler = Sampler::new(5, 0);
+        for texture in &textures {
+            let (mut near_a, mut near_b) = (false, false);
+            for _ in 0..2000 {
+                let p = Vec3::new(
+                    sampler.gen_range(-10.0, 10.0),
+                    sampler.gen_range(-10.0, 10.0),
+                    sampler.gen_range(-10.0, 10.0),
+                );
+                let c = texture.value(0.0, 0.0, p);
+                // on the segment between both colors
+                assert!(c.x <= 0.9 + 1e-12 && c.x >= 0.1 - 1e-12);
+                assert!((c - b).cross(a - b).length() < 1e-12);
+                near_a |= (c - a).length() < 0.1;
+                near_b |= (c - b).length() < 0.1;
+            }
+            assert!(near_a && near_b);
+        }
+    }
+}
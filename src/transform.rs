@@ -0,0 +1,143 @@
+use crate::aabb::Aabb;
+use crate::mat4::Mat4;
+use crate::ray::Ray;
+use crate::vec3::Vec3;
+
+/// An invertible affine transform, keeping its inverse to move rays back
+/// into the space of the transformed object.
+///
+/// Transforms compose with [`Transform::then`]:
+/// `Transform::scaling(s).then(&Transform::translation(t))` scales then
+/// translates.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Transform {
+    matrix: Mat4,
+    inverse: Mat4,
+}
+
+impl Transform {
+    pub fn identity() -> Transform {
+        Transform {
+            matrix: Mat4::identity(),
+            inverse: Mat4::identity(),
+        }
+    }
+
+    /// The transform applying `matrix`, `None` when it cannot be inverted
+    pub fn new(matrix: Mat4) -> Option<Transform> {
+        Some(Transform {
+            matrix,
+            inverse: matrix.inverse()?,
+        })
+    }
+
+    pub fn translation(t: Vec3) -> Transform {
+        Transform {
+            matrix: Mat4::translation(t),
+            inverse: Mat4::translation(-t),
+        }
+    }
+
+    /// Scale by `s` along each axis, none of which may be zero
+    pub fn scaling(s: Vec3) -> Transform {
+        assert!(
+            s.x != 0.0 && s.y != 0.0 && s.z != 0.0,
+            "cannot scale by zero"
+        );
+        Transform {
+            matrix: Mat4::scaling(s),
+            inverse: Mat4::scaling(Vec3::new(1.0 / s.x, 1.0 / s.y, 1.0 / s.z)),
+        }
+    }
+
+    /// Counter-clockwise rotation of `degrees` around `axis`
+    pub fn rotation(axis: Vec3, degrees: f64) -> Transform {
+        let matrix = Mat4::rotation(axis, degrees);
+        Transform {
+            matrix,
+            // rotations are orthogonal
+            inverse: matrix.transpose(),
+        }
+    }
+
+    /// This transform followed by `next`
+    pub fn then(&self, next: &Transform) -> Transform {
+        Transform {
+            matrix: next.matrix * self.matrix,
+            inverse: self.inverse * next.inverse,
+        }
+    }
+
+    pub fn inverse(&self) -> Transform {
+        Transform {
+            matrix: self.inverse,
+            inverse: self.matrix,
+        }
+    }
+
+    pub fn matrix(&self) -> &Mat4 {
+        &self.matrix
+    }
+
+    pub fn point(&self, p: Vec3) -> Vec3 {
+        self.matrix.transform_point(p)
+    }
+
+    pub fn vector(&self, v: Vec3) -> Vec3 {
+        self.matrix.transform_vector(v)
+    }
+
+    /// Transform a normal with the inverse transpose, the result is not
+    /// normalized
+    pub fn normal(&self, n: Vec3) -> Vec3 {
+        self.inverse.transpose().transform_vector(n)
+    }
+
+    /// Transform a ray, its direction is not normalized so that parameters
+    /// along the ray are the same in both spaces
+    pub fn ray(&self, r: &Ray) -> Ray {
+        Ray::with_time(self.point(r.origin), self.vector(r.direction), r.time)
+    }
+
+    /// The box enclosing the transformed corners of `b`
+    pub fn bounding_box(&self, b: &Aabb) -> Aabb {
+        (0..8).fold(Aabb::empty(), |bounds, i| {
+            let corner = Vec3::new(
+                if i & 1 == 0 { b.min.x } else { b.max.x },
+                if i & 2 == 0 { b.min.y } else { b.max.y },
+                if i & 4 == 0 { b.min.z } else { b.max.z },
+            );
+            bounds.grow(self.point(corner))
+        })
+    }
+}
+
+impl Default for Transform {
+    fn default() -> Transform {
+        Transform::identity()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn compose_test() {
+        let t = Transform::scaling(Vec3::all(2.0))
+            .then(&Transform::rotation(Vec3::new(0.0, 1.0, 0.0), 90.0))
+            .then(&Transform::translation(Vec3::new(0.0, 1.0, 0.0)));
+        let p = t.point(Vec3::new(1.0, 0.0, 0.0));
+        assert!((p - Vec3::new(0.0, 1.0, -2.0)).length() < 1e-9);
+        let back = t.inverse().point(p);
+        assert!((back - Vec3::new(1.0, 0.0, 0.0)).length() < 1e-9);
+
+        let squash = Transform::scaling(Vec3::new(1.0, 0.5, 1.0));
+        let n = squash.normal(Vec3::new(1.0, 1.0, 0.0));
+        assert_eq!(n, Vec3::new(1.0, 2.0, 0.0));
+
+        let b = t.bounding_box(&Aabb::new(Vec3::zeros(), Vec3::all(1.0)));
+        assert!((b.min - Vec3::new(0.0, 1.0, -2.0)).length() < 1e-9);
+        assert!((b.max - Vec3::new(2.0, 3.0, 0.0)).length() < 1e-9);
+    }
+}
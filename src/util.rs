@@ -1,20 +1,18 @@
+use crate::sampler::Sampler;
 use crate::vec3::Vec3;
-use rand::prelude::*;
 
 pub fn is_between(t: f64, min: f64, max: f64) -> bool {
     t < max && t > min
 }
 
-pub fn random_double(rng: &mut ThreadRng) -> f64 {
-    let x: f64 = rng.gen_range(0.0, 1.0);
-    x
+pub fn random_double(sampler: &mut Sampler) -> f64 {
+    sampler.next_f64()
 }
 
-pub fn random_in_unit_disk(rng: &mut ThreadRng) -> Vec3 {
-    let mut rng: ThreadRng = thread_rng();
+pub fn random_in_unit_disk(sampler: &mut Sampler) -> Vec3 {
     loop {
-        let x: f64 = rng.gen_range(0.0, 1.0);
-        let y: f64 = rng.gen_range(0.0, 1.0);
+        let x: f64 = sampler.next_f64();
+        let y: f64 = sampler.next_f64();
         let z: f64 = 0.0;
         let p = 2.0 * Vec3::new(x, y, z) - Vec3::new(1.0, 1.0, 0.0);
         if p.length() < 1.0 {
@@ -23,11 +21,11 @@ pub fn random_in_unit_disk(rng: &mut ThreadRng) -> Vec3 {
     }
 }
 
-pub fn random_in_unit_sphere(rng: &mut ThreadRng) -> Vec3 {
+pub fn random_in_unit_sphere(sampler: &mut Sampler) -> Vec3 {
     loop {
-        let x: f64 = rng.gen_range(0.0, 1.0);
-        let y: f64 = rng.gen_range(0.0, 1.0);
-        let z: f64 = rng.gen_range(0.0, 1.0);
+        let x: f64 = sampler.next_f64();
+        let y: f64 = sampler.next_f64();
+        let z: f64 = sampler.next_f64();
         let p = 2.0 * Vec3::new(x, y, z) - Vec3::all(1.0);
         if p.length() < 1.0 {
             break p;
@@ -35,6 +33,26 @@ pub fn random_in_unit_sphere(rng: &mut ThreadRng) -> Vec3 {
     }
 }
 
+/// A direction uniformly distributed over the unit sphere
+pub fn random_unit_vector(sampler: &mut Sampler) -> Vec3 {
+    let z = 1.0 - 2.0 * sampler.next_f64();
+    let r = (1.0 - z * z).max(0.0).sqrt();
+    let phi = 2.0 * std::f64::consts::PI * sampler.next_f64();
+    Vec3::new(r * phi.cos(), r * phi.sin(), z)
+}
+
+/// Two unit vectors completing the unit vector `n` into an orthonormal basis
+pub fn orthonormal_basis(n: Vec3) -> (Vec3, Vec3) {
+    // Duff et al., "Building an Orthonormal Basis, Revisited"
+    let sign = 1.0_f64.copysign(n.z);
+    let a = -1.0 / (sign + n.z);
+    let b = n.x * n.y * a;
+    (
+        Vec3::new(1.0 + sign * n.x * n.x * a, sign * b, -sign * n.x),
+        Vec3::new(b, sign + n.y * n.y * a, -n.y),
+    )
+}
+
 pub fn from_u8_rgb(rgb: Vec3) -> u32 {
     let r = (255.0 * rgb.x) as u32;
     let g = (255.0 * rgb.y) as u32;
@@ -63,6 +81,31 @@ pub fn refract(v: Vec3, n: Vec3, ni_over_nt: f64) -> Option<Vec3> {
     }
 }
 
+/// A directory for the files a test writes, unique to the test and the
+/// process and removed with everything in it once dropped
+#[cfg(test)]
+pub(crate) struct TempDir(std::path::PathBuf);
+
+#[cfg(test)]
+impl TempDir {
+    pub fn new(name: &str) -> TempDir {
+        let dir = std::env::temp_dir().join(format!("rustracer_{}_{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        TempDir(dir)
+    }
+
+    pub fn path(&self) -> &std::path::Path {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -79,4 +122,18 @@ mod test {
         let reflected_ray = reflect(incoming_ray, normal);
         assert_eq!(reflected_ray, Vec3::new(1.0, 1.0, 0.0));
     }
+
+    #[test]
+    fn orthonormal_basis_test() {
+        for n in [
+            Vec3::new(0.0, 0.0, 1.0),
+            Vec3::new(0.0, 0.0, -1.0),
+            Vec3::new(1.0, 2.0, -3.0).make_unit_vector(),
+        ] {
+            let (u, v) = orthonormal_basis(n);
+            assert!((u.length() - 1.0).abs() < 1e-12 && (v.length() - 1.0).abs() < 1e-12);
+            assert!(u.dot(v).abs() < 1e-12 && u.dot(n).abs() < 1e-12 && v.dot(n).abs() < 1e-12);
+            assert!((u.cross(v) - n).length() < 1e-12);
+        }
+    }
 }
@@ -1,4 +1,4 @@
-use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+use std::ops::{Add, AddAssign, Div, DivAssign, Index, Mul, MulAssign, Neg, Sub, SubAssign};
 
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub struct Vec3 {
@@ -53,6 +53,30 @@ impl Vec3 {
             z: self.x * rhs.y - self.y * rhs.x,
         }
     }
+
+    /// Component wise minimum
+    pub fn min(&self, rhs: Vec3) -> Vec3 {
+        Vec3::new(self.x.min(rhs.x), self.y.min(rhs.y), self.z.min(rhs.z))
+    }
+
+    /// Component wise maximum
+    pub fn max(&self, rhs: Vec3) -> Vec3 {
+        Vec3::new(self.x.max(rhs.x), self.y.max(rhs.y), self.z.max(rhs.z))
+    }
+}
+
+/// Access the components by axis, 0 being x, 1 y and 2 z
+impl Index<usize> for Vec3 {
+    type Output = f64;
+
+    fn index(&self, axis: usize) -> &f64 {
+        match axis {
+            0 => &self.x,
+            1 => &self.y,
+            2 => &self.z,
+            _ => panic!("axis {} out of range for Vec3", axis),
+        }
+    }
 }
 
 impl Add for Vec3 {
@@ -333,6 +357,20 @@ mod test {
         assert_eq!(-lhs, rst);
     }
 
+    #[test]
+    fn min_max_test() {
+        let lhs = Vec3::new(1.0, 5.0, -2.0);
+        let rhs = Vec3::new(2.0, 3.0, -1.0);
+        assert_eq!(lhs.min(rhs), Vec3::new(1.0, 3.0, -2.0));
+        assert_eq!(lhs.max(rhs), Vec3::new(2.0, 5.0, -1.0));
+    }
+
+    #[test]
+    fn index_test() {
+        let v = Vec3::new(1.0, 2.0, 3.0);
+        assert_eq!((v[0], v[1], v[2]), (1.0, 2.0, 3.0));
+    }
+
     #[test]
     fn dot_cross_test() {
         let v = Vec3::new(2.0, 3.0, 1.0);